@@ -0,0 +1,275 @@
+//! Note-level change summaries
+//!
+//! Turns a set of changed files (old and new content) into a summary of what
+//! happened in terms of notes, tasks and tags, so sync commits read like a
+//! work journal instead of a list of file names.
+
+use crate::content_hash;
+use crate::note::{Note, Task, TaskStatus, TaskVocabulary};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// One changed file, described by its content before and after
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NoteChange {
+    /// Path before the change (None if the file is new)
+    pub old_path: Option<PathBuf>,
+    /// Path after the change (None if the file was deleted)
+    pub new_path: Option<PathBuf>,
+    pub old_content: Option<String>,
+    pub new_content: Option<String>,
+}
+
+/// A task mentioned in a summary, with the title of its note
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaskChange {
+    pub note_title: String,
+    pub text: String,
+}
+
+/// What changed across a set of files
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChangeSummary {
+    /// Titles of new notes
+    pub created: Vec<String>,
+    /// (old title, new title, old path, new path) of moved notes
+    pub renamed: Vec<(String, String, PathBuf, PathBuf)>,
+    /// Titles of deleted notes
+    pub deleted: Vec<String>,
+    /// Titles of edited notes
+    pub updated: Vec<String>,
+    pub tasks_completed: Vec<TaskChange>,
+    pub tasks_added: Vec<TaskChange>,
+    pub tasks_migrated: Vec<TaskChange>,
+    /// (tag, note title) for tags newly added to a note
+    pub tags_added: Vec<(String, String)>,
+    /// Changed files that aren't notes
+    pub other_files: Vec<String>,
+}
+
+impl ChangeSummary {
+    /// Summarize a set of file changes
+    ///
+    /// A deleted note and a new note with the same title or content are
+    /// reported as a rename, so this works whether or not git detected it.
+    /// Tasks are found with `vocabulary`.
+    pub fn from_changes(changes: &[NoteChange], vocabulary: &TaskVocabulary) -> Self {
+        let mut summary = Self::default();
+        let mut created: Vec<(PathBuf, Note)> = Vec::new();
+        let mut deleted: Vec<(PathBuf, Note)> = Vec::new();
+
+        for change in changes {
+            let Some(path) = change.new_path.as_ref().or(change.old_path.as_ref()) else {
+                continue;
+            };
+            if path.extension().is_none_or(|e| e != "md") {
+                summary.other_files.push(path.display().to_string());
+                continue;
+            }
+
+            let old = parse(change.old_path.as_ref(), change.old_content.as_deref());
+            let new = parse(change.new_path.as_ref(), change.new_content.as_deref());
+
+            match (old, new) {
+                (None, Some(new)) => created.push((new.path.clone(), new)),
+                (Some(old), None) => deleted.push((old.path.clone(), old)),
+                (Some(old), Some(new)) => {
+                    if old.path != new.path {
+                        summary.renamed.push((old.title.clone(), new.title.clone(), old.path.clone(), new.path.clone()));
+                    } else if content_hash(&old.content) != content_hash(&new.content) {
+                        summary.updated.push(new.title.clone());
+                    }
+                    summary.diff_note(Some(&old), &new, vocabulary);
+                }
+                (None, None) => {}
+            }
+        }
+
+        // Pair up deletions and creations that are really renames
+        for (old_path, old) in deleted {
+            let position = created
+                .iter()
+                .position(|(_, new)| new.title == old.title || content_hash(&new.content) == content_hash(&old.content));
+
+            match position {
+                Some(i) => {
+                    let (new_path, new) = created.remove(i);
+                    summary.renamed.push((old.title.clone(), new.title.clone(), old_path, new_path));
+                    summary.diff_note(Some(&old), &new, vocabulary);
+                }
+                None => summary.deleted.push(old.title),
+            }
+        }
+
+        for (_, new) in created {
+            summary.created.push(new.title.clone());
+            summary.diff_note(None, &new, vocabulary);
+        }
+
+        summary
+    }
+
+    /// Record task and tag changes between two versions of a note
+    fn diff_note(&mut self, old: Option<&Note>, new: &Note, vocabulary: &TaskVocabulary) {
+        let old_tasks: HashMap<String, TaskStatus> = old
+            .map(|note| {
+                Task::extract_with_vocabulary(note, vocabulary)
+                    .into_iter()
+                    .map(|task| (task.text, task.status))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        for task in Task::extract_with_vocabulary(new, vocabulary) {
+            let change = TaskChange {
+                note_title: new.title.clone(),
+                text: task.text.clone(),
+            };
+
+            match (old_tasks.get(&task.text), task.status) {
+                (Some(TaskStatus::Completed), TaskStatus::Completed) => {}
+                (_, TaskStatus::Completed) => self.tasks_completed.push(change),
+                (Some(TaskStatus::Uncompleted), TaskStatus::Migrated) => self.tasks_migrated.push(change),
+                (None, TaskStatus::Uncompleted) => self.tasks_added.push(change),
+                _ => {}
+            }
+        }
+
+        let old_tags = old.map(|note| note.tags.as_slice()).unwrap_or_default();
+        for tag in &new.tags {
+            let is_new = !old_tags
+                .iter()
+                .any(|t| crate::tags::normalize(t) == crate::tags::normalize(tag));
+            if is_new {
+                self.tags_added.push((tag.clone(), new.title.clone()));
+            }
+        }
+    }
+
+    /// Check if nothing was summarized
+    pub fn is_empty(&self) -> bool {
+        self.created.is_empty()
+            && self.renamed.is_empty()
+            && self.deleted.is_empty()
+            && self.updated.is_empty()
+            && self.tasks_completed.is_empty()
+            && self.tasks_added.is_empty()
+            && self.tasks_migrated.is_empty()
+            && self.tags_added.is_empty()
+            && self.other_files.is_empty()
+    }
+
+    /// Number of changed files covered by the summary
+    pub fn file_count(&self) -> usize {
+        self.created.len() + self.renamed.len() + self.deleted.len() + self.updated.len() + self.other_files.len()
+    }
+
+    /// Build a commit message with `subject` as its first line and the summary as its body
+    pub fn commit_message(&self, subject: &str) -> String {
+        if self.is_empty() {
+            subject.to_string()
+        } else {
+            format!("{}\n\n{}", subject, self.to_commit_body())
+        }
+    }
+
+    /// Render the summary as a commit message body
+    pub fn to_commit_body(&self) -> String {
+        let task_line = |task: &TaskChange| format!("{} ({})", task.text, task.note_title);
+        let rename_line = |(old_title, new_title, old_path, new_path): &(String, String, PathBuf, PathBuf)| {
+            if old_title == new_title {
+                format!("{} ({} -> {})", new_title, old_path.display(), new_path.display())
+            } else {
+                format!("{} -> {}", old_title, new_title)
+            }
+        };
+
+        let sections: [(&str, Vec<String>); 9] = [
+            ("Created:", self.created.clone()),
+            ("Renamed:", self.renamed.iter().map(rename_line).collect()),
+            ("Deleted:", self.deleted.clone()),
+            ("Updated:", self.updated.clone()),
+            ("Completed:", self.tasks_completed.iter().map(task_line).collect()),
+            ("Added tasks:", self.tasks_added.iter().map(task_line).collect()),
+            ("Migrated:", self.tasks_migrated.iter().map(task_line).collect()),
+            (
+                "Tags added:",
+                self.tags_added.iter().map(|(tag, title)| format!("{} ({})", tag, title)).collect(),
+            ),
+            ("Other files:", self.other_files.clone()),
+        ];
+
+        let mut lines = Vec::new();
+        for (heading, mut items) in sections {
+            if items.is_empty() {
+                continue;
+            }
+            items.sort();
+
+            if !lines.is_empty() {
+                lines.push(String::new());
+            }
+            lines.push(heading.to_string());
+            lines.extend(items.into_iter().map(|item| format!("- {}", item)));
+        }
+
+        lines.join("\n")
+    }
+}
+
+/// Parse one side of a change, ignoring content that isn't a valid note
+fn parse(path: Option<&PathBuf>, content: Option<&str>) -> Option<Note> {
+    Note::parse(path?, content?).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn change(old: Option<(&str, &str)>, new: Option<(&str, &str)>) -> NoteChange {
+        NoteChange {
+            old_path: old.map(|(p, _)| PathBuf::from(p)),
+            new_path: new.map(|(p, _)| PathBuf::from(p)),
+            old_content: old.map(|(_, c)| c.to_string()),
+            new_content: new.map(|(_, c)| c.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_summarize_tasks_and_tags() {
+        let old = "---\ntags: [work]\n---\n# Plan\n\n- [ ] Write spec\n- [ ] Review\n- [ ] Ship\n";
+        let new = "---\ntags: [work, launch]\n---\n# Plan\n\n- [x] Write spec\n- [>] Review\n- [ ] Ship\n- [ ] Announce\n";
+
+        let summary = ChangeSummary::from_changes(&[change(Some(("plan.md", old)), Some(("plan.md", new)))], &TaskVocabulary::default());
+
+        assert_eq!(summary.updated, vec!["Plan"]);
+        assert_eq!(summary.tasks_completed[0].text, "Write spec");
+        assert_eq!(summary.tasks_migrated[0].text, "Review");
+        assert_eq!(summary.tasks_added.len(), 1);
+        assert_eq!(summary.tasks_added[0].text, "Announce");
+        assert_eq!(summary.tags_added, vec![("launch".to_string(), "Plan".to_string())]);
+    }
+
+    #[test]
+    fn test_summarize_created_deleted_and_renamed() {
+        let summary = ChangeSummary::from_changes(&[
+            change(None, Some(("new.md", "# New Idea\n\n- [ ] Try it\n"))),
+            change(Some(("gone.md", "# Gone\n")), None),
+            change(Some(("draft.md", "# Essay\n\nText\n")), None),
+            change(None, Some(("essays/essay.md", "# Essay\n\nText\n"))),
+            change(None, Some(("image.png", ""))),
+        ], &TaskVocabulary::default());
+
+        assert_eq!(summary.created, vec!["New Idea"]);
+        assert_eq!(summary.deleted, vec!["Gone"]);
+        assert_eq!(summary.renamed.len(), 1);
+        assert_eq!(summary.renamed[0].3, PathBuf::from("essays/essay.md"));
+        assert_eq!(summary.tasks_added[0].note_title, "New Idea");
+        assert_eq!(summary.other_files, vec!["image.png"]);
+        assert_eq!(summary.file_count(), 4);
+
+        let body = summary.to_commit_body();
+        assert!(body.starts_with("Created:\n- New Idea\n\nRenamed:\n- Essay (draft.md -> essays/essay.md)\n\nDeleted:\n- Gone\n"));
+        assert!(body.contains("Added tasks:\n- Try it (New Idea)"));
+    }
+}
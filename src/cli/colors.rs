@@ -48,6 +48,18 @@ pub fn highlight() -> ColorSpec {
     spec
 }
 
+/// A color by name from config: "dim" or a terminal color like "green" or "208"
+pub fn named(name: &str) -> ColorSpec {
+    if name.eq_ignore_ascii_case("dim") {
+        return dim();
+    }
+    let mut spec = ColorSpec::new();
+    if let Ok(color) = name.parse::<Color>() {
+        spec.set_fg(Some(color));
+    }
+    spec
+}
+
 /// Dim color: gray (for secondary info)
 pub fn dim() -> ColorSpec {
     let mut spec = ColorSpec::new();
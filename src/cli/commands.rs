@@ -4,15 +4,18 @@
 //! from the main entry point.
 
 use super::colors;
-use super::git::GitRepo;
+use super::git::{FileDates, GitRepo};
 use super::utils::pluralize;
 use anyhow::{Context, Result};
-use bnotes::{BNotes, PeriodType, RealStorage};
+use bnotes::{AutoSyncConfig, AutoSyncMode, BNotes, LibraryConfig, PeriodType, RealStorage};
+use bnotes::sync::{GitSync, Sync as _, SyncError, SyncLock};
+use std::cell::OnceCell;
 use std::io::{self, Write};
 use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
 use termcolor::{ColorChoice, WriteColor};
-use wildmatch::WildMatch;
 
 /// Validate that notes directory exists
 fn validate_notes_dir(notes_dir: &Path) -> Result<()> {
@@ -105,14 +108,31 @@ fn write_tags_with_highlights<W: WriteColor>(
     Ok(())
 }
 
+/// Resolve a title to exactly one note, listing candidates when ambiguous
+fn find_single_note(bnotes: &BNotes, title: &str) -> Result<bnotes::Note> {
+    let mut matches = bnotes.find_note_by_title(title)?;
+
+    match matches.len() {
+        0 => anyhow::bail!("Note not found: {}", title),
+        1 => Ok(matches.remove(0)),
+        _ => {
+            println!("Multiple notes found with title '{}':", title);
+            for note in matches {
+                println!("  - {}", note.path.display());
+            }
+            anyhow::bail!("Please be more specific or use the full path.");
+        }
+    }
+}
+
 // ============================================================================
 // Core Commands
 // ============================================================================
 
-pub fn search(notes_dir: &Path, query: &str, limit: usize, color: ColorChoice) -> Result<()> {
+pub fn search(notes_dir: &Path, query: &str, tags: &[String], limit: usize, color: ColorChoice) -> Result<()> {
     validate_notes_dir(notes_dir)?;
     let storage = Box::new(RealStorage::new(notes_dir.to_path_buf()));
-    let bnotes = BNotes::with_defaults(storage);
+    let bnotes = BNotes::open(storage)?;
 
     let title_base_color = colors::highlight();
     let mut title_highlight_color = title_base_color.clone();
@@ -121,7 +141,7 @@ pub fn search(notes_dir: &Path, query: &str, limit: usize, color: ColorChoice) -
     let text_base_color = colors::default();
     let text_highlight_color = colors::highlight();
 
-    let matches = bnotes.search(query)?;
+    let matches = bnotes.search_with_tags(query, tags)?;
 
     let mut stdout = colors::create_stdout(color);
 
@@ -223,7 +243,7 @@ pub fn search(notes_dir: &Path, query: &str, limit: usize, color: ColorChoice) -
 pub fn edit(notes_dir: &Path, title: &str, template_name: Option<String>, print_path: bool) -> Result<()> {
     validate_notes_dir(notes_dir)?;
     let storage = Box::new(RealStorage::new(notes_dir.to_path_buf()));
-    let bnotes = BNotes::with_defaults(storage);
+    let bnotes = BNotes::open(storage)?;
 
     let matches = bnotes.find_note_by_title(title)?;
 
@@ -275,305 +295,953 @@ pub fn edit(notes_dir: &Path, title: &str, template_name: Option<String>, print_
 // Health & Maintenance Commands
 // ============================================================================
 
-pub fn doctor(notes_dir: &Path, color: ColorChoice) -> Result<()> {
+/// Output format for `doctor` reports
+#[derive(Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ReportFormat {
+    Text,
+    Json,
+    Sarif,
+}
+
+/// Run lint rules over the notes; returns false if any rule reported an error
+pub fn doctor(notes_dir: &Path, format: ReportFormat, color: ColorChoice) -> Result<bool> {
     validate_notes_dir(notes_dir)?;
     let storage = Box::new(RealStorage::new(notes_dir.to_path_buf()));
-    let bnotes = BNotes::with_defaults(storage);
+    let bnotes = BNotes::open(storage)?;
+
+    let linter = bnotes::Linter::with_builtin_rules();
+    let report = bnotes.lint_with(&linter)?;
+
+    match format {
+        ReportFormat::Text => write_doctor_text(&report, &linter, color)?,
+        ReportFormat::Json => {
+            let json = serde_json::json!({
+                "notes_checked": report.notes_checked,
+                "errors": report.error_count(),
+                "warnings": report.warning_count(),
+                "diagnostics": report.diagnostics,
+            });
+            writeln!(io::stdout(), "{}", serde_json::to_string_pretty(&json)?)?;
+        }
+        ReportFormat::Sarif => {
+            let sarif = doctor_sarif(&report, &linter, bnotes.config());
+            writeln!(io::stdout(), "{}", serde_json::to_string_pretty(&sarif)?)?;
+        }
+    }
 
-    // Get note count for display
-    let notes = bnotes.list_notes(&[])?;
+    Ok(!report.has_errors())
+}
 
+fn write_doctor_text(report: &bnotes::LintReport, linter: &bnotes::Linter, color: ColorChoice) -> Result<()> {
     let mut stdout = colors::create_stdout(color);
 
-    if notes.is_empty() {
+    if report.notes_checked == 0 {
         writeln!(stdout, "No notes found to check.")?;
         return Ok(());
     }
 
-    writeln!(stdout, "Running health checks on {} notes...\n", notes.len())?;
-
-    // Run health checks
-    let report = bnotes.check_health()?;
-
-    // Display broken wiki links
-    if !report.broken_links.is_empty() {
-        stdout.set_color(&colors::error())?;
-        write!(stdout, "ERROR:")?;
-        stdout.reset()?;
-        writeln!(stdout, " Broken wiki links:")?;
-        for (note_title, broken) in &report.broken_links {
-            writeln!(stdout, "  {} has broken links:", note_title)?;
-            for link in broken {
-                writeln!(stdout, "    - [[{}]]", link)?;
-            }
-        }
-        writeln!(stdout)?;
-    }
+    writeln!(stdout, "Running health checks on {} notes...\n", report.notes_checked)?;
 
-    // Display notes without tags
-    if !report.notes_without_tags.is_empty() {
-        stdout.set_color(&colors::warning())?;
-        write!(stdout, "WARNING:")?;
-        stdout.reset()?;
-        writeln!(stdout, " Notes without tags:")?;
-        for title in &report.notes_without_tags {
-            writeln!(stdout, "  - {}", title)?;
-        }
-        writeln!(stdout)?;
-    }
+    // Group diagnostics under their rule, in rule order
+    for rule in linter.rules() {
+        let diagnostics: Vec<_> = report.diagnostics.iter().filter(|d| d.rule == rule.id()).collect();
+        let Some(first) = diagnostics.first() else {
+            continue;
+        };
 
-    // Display notes missing frontmatter
-    if !report.notes_without_frontmatter.is_empty() {
-        stdout.set_color(&colors::warning())?;
-        write!(stdout, "WARNING:")?;
-        stdout.reset()?;
-        writeln!(stdout, " Notes missing frontmatter:")?;
-        for title in &report.notes_without_frontmatter {
-            writeln!(stdout, "  - {}", title)?;
+        if first.severity == bnotes::RuleLevel::Error {
+            stdout.set_color(&colors::error())?;
+            write!(stdout, "ERROR:")?;
+        } else {
+            stdout.set_color(&colors::warning())?;
+            write!(stdout, "WARNING:")?;
         }
-        writeln!(stdout)?;
-    }
-
-    // Display duplicate titles
-    if !report.duplicate_titles.is_empty() {
-        stdout.set_color(&colors::error())?;
-        write!(stdout, "ERROR:")?;
         stdout.reset()?;
-        writeln!(stdout, " Multiple notes with the same title:")?;
-        for (title, paths) in &report.duplicate_titles {
-            writeln!(stdout, "  Title: {}", title)?;
-            for path in paths {
-                writeln!(stdout, "    - {}", path)?;
-            }
-        }
-        writeln!(stdout)?;
-    }
+        writeln!(stdout, " {} [{}]", rule.description(), rule.id())?;
 
-    // Display orphaned notes
-    if !report.orphaned_notes.is_empty() {
-        stdout.set_color(&colors::warning())?;
-        write!(stdout, "WARNING:")?;
-        stdout.reset()?;
-        writeln!(stdout, " Orphaned notes (no links, no tags):")?;
-        for title in &report.orphaned_notes {
-            writeln!(stdout, "  - {}", title)?;
+        for diagnostic in diagnostics {
+            let location = match diagnostic.line {
+                Some(line) => format!("{}:{}", diagnostic.path.display(), line),
+                None => diagnostic.path.display().to_string(),
+            };
+            writeln!(stdout, "  {}: {}", location, diagnostic.message)?;
         }
         writeln!(stdout)?;
     }
 
     // Summary
-    if !report.has_issues() {
+    let errors = report.error_count();
+    let warnings = report.warning_count();
+    if errors == 0 && warnings == 0 {
         stdout.set_color(&colors::success())?;
         writeln!(stdout, "All checks passed! Your notes are healthy.")?;
         stdout.reset()?;
     } else {
         writeln!(
             stdout,
-            "Found {} {} that may need attention.",
-            report.issue_count(),
-            pluralize(report.issue_count(), "issue", "issues")
+            "Found {} {} and {} {}.",
+            errors,
+            pluralize(errors, "error", "errors"),
+            warnings,
+            pluralize(warnings, "warning", "warnings")
         )?;
     }
 
     Ok(())
 }
 
-// ============================================================================
-// Git Commands
-// ============================================================================
+/// Render a lint report as SARIF 2.1.0 for code scanning tools
+fn doctor_sarif(report: &bnotes::LintReport, linter: &bnotes::Linter, config: &bnotes::LibraryConfig) -> serde_json::Value {
+    use serde_json::json;
 
-pub fn sync(notes_dir: &Path, message: Option<String>, color: ColorChoice) -> Result<()> {
-    validate_notes_dir(notes_dir)?;
-    let repo = GitRepo::new(notes_dir.to_path_buf())?;
+    let level = |severity: bnotes::RuleLevel| match severity {
+        bnotes::RuleLevel::Error => "error",
+        bnotes::RuleLevel::Warning => "warning",
+        bnotes::RuleLevel::Off => "none",
+    };
 
-    // Verify git repository and remote
-    repo.check_is_repo()?;
-    repo.check_has_remote()?;
+    let rules: Vec<_> = linter
+        .rules()
+        .map(|rule| {
+            json!({
+                "id": rule.id(),
+                "shortDescription": { "text": rule.description() },
+                "defaultConfiguration": { "level": level(bnotes::Linter::level(rule, config)) },
+            })
+        })
+        .collect();
+
+    let results: Vec<_> = report
+        .diagnostics
+        .iter()
+        .map(|diagnostic| {
+            let mut location = json!({
+                "artifactLocation": { "uri": diagnostic.path.to_string_lossy().replace('\\', "/") },
+            });
+            if let Some(line) = diagnostic.line {
+                location["region"] = json!({ "startLine": line });
+            }
+            json!({
+                "ruleId": diagnostic.rule,
+                "level": level(diagnostic.severity),
+                "message": { "text": diagnostic.message },
+                "locations": [{ "physicalLocation": location }],
+            })
+        })
+        .collect();
+
+    json!({
+        "version": "2.1.0",
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "bnotes",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules,
+                },
+            },
+            "results": results,
+        }],
+    })
+}
 
-    // Check for uncommitted changes
-    let has_changes = repo.has_uncommitted_changes()?;
+/// Reply to a "fix this?" prompt
+enum Answer {
+    Yes,
+    No,
+    Quit,
+}
 
-    let mut stdout = colors::create_stdout(color);
+/// Ask a question on stdout and read a line of input (None at end of input)
+fn prompt(stdout: &mut termcolor::StandardStream, question: &str) -> Result<Option<String>> {
+    write!(stdout, "{}", question)?;
+    stdout.flush()?;
 
-    if has_changes {
-        // Generate change summary before staging
-        let change_summary = repo.generate_change_summary()?;
+    let mut input = String::new();
+    if io::stdin().read_line(&mut input)? == 0 {
+        writeln!(stdout)?;
+        return Ok(None);
+    }
+    Ok(Some(input.trim().to_string()))
+}
+
+/// Print a unified diff with added and removed lines colored
+fn write_diff(stdout: &mut termcolor::StandardStream, diff: &str) -> Result<()> {
+    for line in diff.lines() {
+        let color = if line.starts_with("+++") || line.starts_with("---") || line.starts_with("@@") {
+            colors::dim()
+        } else if line.starts_with('+') {
+            colors::success()
+        } else if line.starts_with('-') {
+            colors::error()
+        } else {
+            colors::default()
+        };
+        stdout.set_color(&color)?;
+        write!(stdout, "{}", line)?;
+        stdout.reset()?;
+        writeln!(stdout)?;
+    }
+    Ok(())
+}
+
+/// Shows each fix as a diff and applies it once confirmed
+struct Fixer<'a> {
+    bnotes: &'a BNotes,
+    stdout: termcolor::StandardStream,
+    yes: bool,
+    dry_run: bool,
+    applied: usize,
+    quit: bool,
+}
+
+impl Fixer<'_> {
+    fn offer(&mut self, path: &Path, fix: bnotes::Fix) -> Result<()> {
+        if self.quit {
+            return Ok(());
+        }
+
+        let (old, new) = self.bnotes.preview_fix(path, &fix)?;
+        if old == new {
+            return Ok(());
+        }
 
-        // Stage all changes
-        repo.stage_all()?;
+        self.stdout.set_color(&colors::highlight())?;
+        write!(self.stdout, "{}", path.display())?;
+        self.stdout.reset()?;
+        writeln!(self.stdout, ": {}", fix.describe())?;
+        write_diff(&mut self.stdout, &bnotes::repair::diff(path, &old, &new))?;
 
-        // Create commit message
-        let subject = message.unwrap_or_else(|| format!("bnotes sync: {}", GitRepo::get_timestamp()));
+        if self.dry_run {
+            writeln!(self.stdout)?;
+            self.applied += 1;
+            return Ok(());
+        }
 
-        let commit_message = if change_summary.is_empty() {
-            subject
+        let answer = if self.yes {
+            Answer::Yes
         } else {
-            format!("{}\n\n{}", subject, change_summary)
+            match prompt(&mut self.stdout, "Apply this fix? [y/N/q] ")? {
+                Some(input) if input.eq_ignore_ascii_case("y") || input.eq_ignore_ascii_case("yes") => Answer::Yes,
+                Some(input) if input.eq_ignore_ascii_case("q") || input.eq_ignore_ascii_case("quit") => Answer::Quit,
+                None => Answer::Quit,
+                Some(_) => Answer::No,
+            }
         };
 
-        // Commit changes
-        repo.commit(&commit_message)?;
+        match answer {
+            Answer::Yes => {
+                if self.bnotes.apply_fix(path, &fix)? {
+                    self.applied += 1;
+                }
+            }
+            Answer::No => {}
+            Answer::Quit => self.quit = true,
+        }
+        writeln!(self.stdout)?;
+
+        Ok(())
+    }
 
-        // Count files in summary for success message
-        let num_changes = change_summary.lines().filter(|l| l.starts_with('-')).count();
+    /// Let the user tag or link an orphaned note from a picklist
+    fn offer_orphan(&mut self, orphan: &bnotes::repair::Orphan) -> Result<()> {
+        if self.quit {
+            return Ok(());
+        }
 
-        // Pull and push
-        repo.pull()?;
-        repo.push()?;
+        let choices: Vec<bnotes::Fix> = orphan
+            .tags
+            .iter()
+            .map(|tag| bnotes::Fix::AddTag(tag.clone()))
+            .chain(orphan.links.iter().map(|title| bnotes::Fix::AddLink(title.clone())))
+            .collect();
 
-        stdout.set_color(&colors::success())?;
-        writeln!(
-            stdout,
-            "Synced successfully: committed {} changes, pulled, and pushed",
-            num_changes
-        )?;
-        stdout.reset()?;
-    } else {
-        // No local changes, just pull and push
-        repo.pull()?;
-        repo.push()?;
+        self.stdout.set_color(&colors::highlight())?;
+        write!(self.stdout, "{}", orphan.title)?;
+        self.stdout.reset()?;
+        writeln!(self.stdout, " ({}) has no links and no tags", orphan.path.display())?;
+        for (i, choice) in choices.iter().enumerate() {
+            writeln!(self.stdout, "  {}) {}", i + 1, choice.describe())?;
+        }
 
-        stdout.set_color(&colors::success())?;
-        writeln!(stdout, "Synced successfully: pulled and pushed")?;
-        stdout.reset()?;
-    }
+        let question = "Pick a number, type a tag or [[Title]], or press Enter to skip: ";
+        let Some(input) = prompt(&mut self.stdout, question)? else {
+            self.quit = true;
+            return Ok(());
+        };
 
-    Ok(())
+        let fix = if input.is_empty() {
+            writeln!(self.stdout)?;
+            return Ok(());
+        } else if let Ok(n) = input.parse::<usize>()
+            && let Some(choice) = n.checked_sub(1).and_then(|i| choices.get(i))
+        {
+            choice.clone()
+        } else if let Some(title) = input.strip_prefix("[[").and_then(|t| t.strip_suffix("]]")) {
+            bnotes::Fix::AddLink(title.to_string())
+        } else {
+            bnotes::Fix::AddTag(input)
+        };
+
+        self.offer(&orphan.path, fix)
+    }
 }
 
-pub fn pull(notes_dir: &Path, color: ColorChoice) -> Result<()> {
+/// Repair what `doctor` reports, showing every change as a diff first
+pub fn doctor_fix(
+    notes_dir: &Path,
+    only: Option<bnotes::RepairKind>,
+    yes: bool,
+    dry_run: bool,
+    color: ColorChoice,
+) -> Result<()> {
+    use bnotes::{Fix, RepairKind};
+
     validate_notes_dir(notes_dir)?;
-    let repo = GitRepo::new(notes_dir.to_path_buf())?;
+    let storage = Box::new(RealStorage::new(notes_dir.to_path_buf()));
+    let bnotes = BNotes::open(storage)?;
+
+    let plan = bnotes.plan_repairs()?;
+    let wants = |kind: RepairKind| only.is_none_or(|k| k == kind);
+
+    let mut fixer = Fixer {
+        bnotes: &bnotes,
+        stdout: colors::create_stdout(color),
+        yes,
+        dry_run,
+        applied: 0,
+        quit: false,
+    };
 
-    // Verify git repository and remote
-    repo.check_is_repo()?;
-    repo.check_has_remote()?;
+    if wants(RepairKind::Frontmatter) {
+        let created = chrono::Utc::now();
+        for path in &plan.missing_frontmatter {
+            fixer.offer(path, Fix::AddFrontmatter { created })?;
+        }
+    }
 
-    // Check for uncommitted changes
-    let has_changes = repo.has_uncommitted_changes()?;
+    if wants(RepairKind::Links) {
+        for link in &plan.broken_links {
+            if let Some(closest) = link.suggestions.first() {
+                let fix = Fix::RetargetLink {
+                    from: link.target.clone(),
+                    to: closest.clone(),
+                };
+                fixer.offer(&link.path, fix)?;
+            }
+        }
+    }
 
-    if has_changes {
-        // Stash changes with timestamp
-        let stash_message = format!("bnotes pull auto-stash {}", GitRepo::get_timestamp());
-        repo.stash_push(&stash_message)?;
+    if wants(RepairKind::Titles) {
+        for (path, _, title) in &plan.retitles {
+            fixer.offer(path, Fix::SetTitle(title.clone()))?;
+        }
+    }
 
-        // Pull changes
-        repo.pull()?;
+    if wants(RepairKind::Orphans) && !plan.orphans.is_empty() {
+        if yes || dry_run {
+            writeln!(
+                fixer.stdout,
+                "Skipping {} orphaned {}: tagging or linking them needs a choice, run without --yes or --dry-run.\n",
+                plan.orphans.len(),
+                pluralize(plan.orphans.len(), "note", "notes")
+            )?;
+        } else {
+            for orphan in &plan.orphans {
+                fixer.offer_orphan(orphan)?;
+            }
+        }
+    }
 
-        // Pop stash to reapply changes
-        repo.stash_pop()?;
+    let applied = fixer.applied;
+    let mut stdout = fixer.stdout;
+    if dry_run {
+        writeln!(stdout, "Would apply {} {}.", applied, pluralize(applied, "fix", "fixes"))?;
     } else {
-        // Clean working directory, just pull
-        repo.pull()?;
+        stdout.set_color(&colors::success())?;
+        writeln!(stdout, "Applied {} {}.", applied, pluralize(applied, "fix", "fixes"))?;
+        stdout.reset()?;
     }
 
-    let mut stdout = colors::create_stdout(color);
-    stdout.set_color(&colors::success())?;
-    writeln!(stdout, "Pulled successfully")?;
-    stdout.reset()?;
-
     Ok(())
 }
 
-// ============================================================================
-// Note Commands
-// ============================================================================
+/// First and last commit times of the files in the notes directory, or None
+/// if it isn't a git repository
+fn git_file_dates(notes_dir: &Path) -> Result<Option<FileDates>> {
+    let git_repo = GitRepo::new(notes_dir.to_path_buf())?;
+    if git_repo.check_is_repo().is_err() {
+        return Ok(None);
+    }
+    git_repo.get_file_dates().map(Some)
+}
+
+/// First and last change times of a note from its git history, or from the
+/// filesystem for files git doesn't know about
+fn file_dates(
+    git_dates: Option<&FileDates>,
+    notes_dir: &Path,
+    path: &Path,
+) -> Result<(chrono::DateTime<chrono::Utc>, chrono::DateTime<chrono::Utc>)> {
+    if let Some(&dates) = git_dates.and_then(|git_dates| git_dates.get(path)) {
+        return Ok(dates);
+    }
+
+    let metadata = std::fs::metadata(notes_dir.join(path))?;
+    let modified: chrono::DateTime<chrono::Utc> = metadata.modified()?.into();
+    let created = metadata.created().map(Into::into).unwrap_or(modified);
+    Ok((created, modified))
+}
 
-pub fn note_list(notes_dir: &Path, tags: &[String], color: ColorChoice) -> Result<()> {
+pub fn backfill_dates(notes_dir: &Path, dry_run: bool, color: ColorChoice) -> Result<()> {
     validate_notes_dir(notes_dir)?;
     let storage = Box::new(RealStorage::new(notes_dir.to_path_buf()));
-    let bnotes = BNotes::with_defaults(storage);
+    let bnotes = BNotes::open(storage)?;
 
-    let notes = bnotes.list_notes(tags)?;
+    let git_dates = git_file_dates(notes_dir)?;
+
+    let backfills = bnotes.plan_date_backfill(|note| file_dates(git_dates.as_ref(), notes_dir, &note.path).map(Some))?;
 
     let mut stdout = colors::create_stdout(color);
 
-    if notes.is_empty() {
-        if tags.is_empty() {
-            writeln!(stdout, "No notes found.")?;
-        } else {
-            writeln!(stdout, "No notes found with tags: {}", tags.join(", "))?;
-        }
+    if backfills.is_empty() {
+        writeln!(stdout, "All notes have created and updated timestamps.")?;
         return Ok(());
     }
 
-    // Sort by title
-    let mut notes = notes;
-    notes.sort_by(|a, b| a.title.cmp(&b.title));
-
-    let count = notes.len();
+    for backfill in &backfills {
+        stdout.set_color(&colors::highlight())?;
+        write!(stdout, "{}", backfill.title)?;
+        stdout.reset()?;
 
-    for note in notes {
-        let tag_str = if note.tags.is_empty() {
-            String::new()
-        } else {
-            format!(" [{}]", note.tags.join(", "))
-        };
+        let mut fields = Vec::new();
+        if let Some(created) = backfill.created {
+            fields.push(format!("created {}", created.format("%Y-%m-%d %H:%M")));
+        }
+        if let Some(updated) = backfill.updated {
+            fields.push(format!("updated {}", updated.format("%Y-%m-%d %H:%M")));
+        }
 
-        writeln!(stdout, "{}{}", note.title, tag_str)?;
+        stdout.set_color(&colors::dim())?;
+        write!(stdout, " ({})", backfill.path.display())?;
+        stdout.reset()?;
+        writeln!(stdout, ": {}", fields.join(", "))?;
     }
 
-    write!(stdout, "\nTotal: ")?;
-    stdout.set_color(&colors::highlight())?;
-    write!(stdout, "{}", count)?;
-    stdout.reset()?;
-    writeln!(stdout, " {}", pluralize(count, "note", "notes"))?;
+    writeln!(stdout)?;
+    let count = backfills.len();
+    if dry_run {
+        writeln!(stdout, "Would update {} {} (dry run).", count, pluralize(count, "note", "notes"))?;
+    } else {
+        bnotes.apply_date_backfill(&backfills)?;
+        writeln!(stdout, "Updated {} {}.", count, pluralize(count, "note", "notes"))?;
+    }
 
     Ok(())
 }
 
-pub fn note_show(notes_dir: &Path, title: &str) -> Result<()> {
+pub fn touch(notes_dir: &Path, paths: &[PathBuf], color: ColorChoice) -> Result<()> {
     validate_notes_dir(notes_dir)?;
     let storage = Box::new(RealStorage::new(notes_dir.to_path_buf()));
-    let bnotes = BNotes::with_defaults(storage);
+    let bnotes = BNotes::open(storage)?;
 
-    let matches = bnotes.find_note_by_title(title)?;
+    let git_repo = GitRepo::new(notes_dir.to_path_buf())?;
+    let in_repo = git_repo.check_is_repo().is_ok();
 
-    match matches.len() {
-        0 => anyhow::bail!("Note not found: {}", title),
-        1 => {
-            let note = &matches[0];
-            println!("{}", note.content);
-            Ok(())
-        }
-        _ => {
-            println!("Multiple notes found with title '{}':", title);
-            for note in matches {
-                println!("  - {}", note.path.display());
-            }
-            anyhow::bail!("Please be more specific or use the full path.");
+    // Default to every note changed since the last commit
+    let candidates: Vec<PathBuf> = if paths.is_empty() {
+        git_repo.check_is_repo()?;
+        git_repo.get_changed_files()?.into_iter().map(PathBuf::from).collect()
+    } else {
+        paths
+            .iter()
+            .map(|p| p.strip_prefix(notes_dir).unwrap_or(p).to_path_buf())
+            .collect()
+    };
+
+    let mut stdout = colors::create_stdout(color);
+    let mut touched = 0;
+
+    for path in candidates.iter().filter(|p| p.extension().is_some_and(|e| e == "md")) {
+        // Without a committed version the note counts as new
+        let previous = if in_repo {
+            git_repo.show_head(&path.to_string_lossy())?
+        } else {
+            None
+        };
+
+        if bnotes.touch_note(path, previous.as_deref())? {
+            touched += 1;
+            stdout.set_color(&colors::dim())?;
+            writeln!(stdout, "  {}", path.display())?;
+            stdout.reset()?;
         }
     }
+
+    writeln!(stdout, "Updated {} {}.", touched, pluralize(touched, "note", "notes"))?;
+
+    Ok(())
 }
 
-pub fn note_links(notes_dir: &Path, title: &str, color: ColorChoice) -> Result<()> {
+// ============================================================================
+// Git Commands
+// ============================================================================
+
+pub fn status(notes_dir: &Path, color: ColorChoice) -> Result<()> {
     validate_notes_dir(notes_dir)?;
     let storage = Box::new(RealStorage::new(notes_dir.to_path_buf()));
-    let bnotes = BNotes::with_defaults(storage);
+    let bnotes = BNotes::open(storage)?;
 
-    let matches = bnotes.find_note_by_title(title)?;
+    let repo = GitRepo::new(notes_dir.to_path_buf())?;
+    repo.check_is_repo()?;
 
     let mut stdout = colors::create_stdout(color);
 
-    let note = match matches.len() {
-        0 => anyhow::bail!("Note not found: {}", title),
-        1 => &matches[0],
-        _ => {
-            writeln!(stdout, "Multiple notes found with title '{}':", title)?;
-            for note in matches {
-                writeln!(stdout, "  - {}", note.path.display())?;
+    // Branch and upstream
+    match repo.get_branch()? {
+        Some(branch) => write!(stdout, "On branch {}", branch)?,
+        None => write!(stdout, "HEAD detached")?,
+    }
+    match repo.get_upstream_status()? {
+        Some((upstream, 0, 0)) => writeln!(stdout, ", up to date with {}", upstream)?,
+        Some((upstream, ahead, behind)) => {
+            let mut parts = Vec::new();
+            if ahead > 0 {
+                parts.push(format!("{} ahead", ahead));
             }
-            anyhow::bail!("Please be more specific.");
+            if behind > 0 {
+                parts.push(format!("{} behind", behind));
+            }
+            writeln!(stdout, ", {} {}", parts.join(" and "), upstream)?;
         }
+        None => writeln!(stdout, ", no upstream configured")?,
+    }
+    stdout.set_color(&colors::dim())?;
+    writeln!(stdout, "(remote state as of the last pull)")?;
+    stdout.reset()?;
+    writeln!(stdout)?;
+
+    // Working tree changes, shown by note title where possible
+    let changes = repo.get_working_changes()?;
+    let titles: std::collections::HashMap<PathBuf, String> = bnotes
+        .list_notes(&[])?
+        .into_iter()
+        .map(|note| (note.path, note.title))
+        .collect();
+    let current_title = |path: &str| titles.get(Path::new(path)).cloned();
+    let deleted_title = |path: &str| -> Result<Option<String>> {
+        Ok(repo
+            .show_head(path)?
+            .and_then(|content| bnotes::Note::parse(Path::new(path), &content).ok())
+            .map(|note| note.title))
     };
 
-    let (outbound, inbound) = bnotes.get_note_links(&note.title)?;
+    if changes.is_empty() {
+        writeln!(stdout, "No uncommitted changes.")?;
+    } else {
+        let mut new_entries: Vec<(Option<String>, String)> = changes
+            .added
+            .iter()
+            .map(|p| (current_title(p), p.clone()))
+            .collect();
+        let mut modified_entries: Vec<(Option<String>, String)> = changes
+            .modified
+            .iter()
+            .map(|p| (current_title(p), p.clone()))
+            .collect();
+        modified_entries.extend(
+            changes
+                .renamed
+                .iter()
+                .map(|(from, to)| (current_title(to), format!("{} -> {}", from, to))),
+        );
+        let mut deleted_entries = Vec::new();
+        for path in &changes.deleted {
+            deleted_entries.push((deleted_title(path)?, path.clone()));
+        }
 
-    writeln!(stdout, "Links for: {}\n", note.title)?;
+        for entries in [&mut new_entries, &mut modified_entries, &mut deleted_entries] {
+            entries.sort();
+        }
 
-    // Show outbound links (what this note links to)
-    if !outbound.is_empty() {
-        write!(stdout, "Outbound links (")?;
+        write_status_group(&mut stdout, "New", &new_entries, colors::success())?;
+        write_status_group(&mut stdout, "Modified", &modified_entries, colors::warning())?;
+        write_status_group(&mut stdout, "Deleted", &deleted_entries, colors::error())?;
+    }
+
+    // Stash and merge state
+    let stashes = repo.get_stash_count()?;
+    if stashes > 0 {
+        stdout.set_color(&colors::warning())?;
+        write!(stdout, "WARNING:")?;
+        stdout.reset()?;
+        writeln!(
+            stdout,
+            " {} stashed {} (see 'git stash list')",
+            stashes,
+            pluralize(stashes, "change", "changes")
+        )?;
+    }
+
+    let conflicted = repo.get_conflicted_files()?;
+    if !conflicted.is_empty() {
+        stdout.set_color(&colors::error())?;
+        write!(stdout, "ERROR:")?;
+        stdout.reset()?;
+        writeln!(stdout, " Unresolved conflicts:")?;
+        for file in &conflicted {
+            writeln!(stdout, "  - {}", file)?;
+        }
+    } else if repo.is_merging()? {
+        stdout.set_color(&colors::warning())?;
+        write!(stdout, "WARNING:")?;
+        stdout.reset()?;
+        writeln!(stdout, " Merge in progress, run 'git merge --continue' to finish it")?;
+    }
+
+    Ok(())
+}
+
+/// Write one group of changed files for `status`
+fn write_status_group<W: WriteColor>(
+    stdout: &mut W,
+    heading: &str,
+    entries: &[(Option<String>, String)],
+    color: termcolor::ColorSpec,
+) -> io::Result<()> {
+    if entries.is_empty() {
+        return Ok(());
+    }
+
+    writeln!(stdout, "{} ({}):", heading, entries.len())?;
+    for (title, path) in entries {
+        write!(stdout, "  ")?;
+        stdout.set_color(&color)?;
+        write!(stdout, "{}", title.as_deref().unwrap_or(path))?;
+        stdout.reset()?;
+        if title.is_some() {
+            stdout.set_color(&colors::dim())?;
+            write!(stdout, " ({})", path)?;
+            stdout.reset()?;
+        }
+        writeln!(stdout)?;
+    }
+    writeln!(stdout)
+}
+
+/// Build the commit message for a sync from the custom message and current changes
+fn sync_commit_message(repo: &GitSync, message: Option<String>) -> Result<(String, usize)> {
+    let change_summary = repo.change_summary()?;
+    let subject = message.unwrap_or_else(|| format!("bnotes sync: {}", GitRepo::get_timestamp()));
+
+    Ok((change_summary.commit_message(&subject), change_summary.file_count()))
+}
+
+pub fn sync(notes_dir: &Path, message: Option<String>, dry_run: bool, color: ColorChoice) -> Result<()> {
+    validate_notes_dir(notes_dir)?;
+    let repo = GitSync::open(notes_dir)?;
+
+    // Verify there is a remote to sync with
+    if !repo.has_remote()? {
+        return Err(SyncError::NoRemote.into());
+    }
+
+    // Check for uncommitted changes
+    let has_changes = repo.has_changes()?;
+
+    let mut stdout = colors::create_stdout(color);
+
+    if dry_run {
+        if has_changes {
+            let (commit_message, _) = sync_commit_message(&repo, message)?;
+            writeln!(stdout, "Would commit with message:\n")?;
+            for line in commit_message.lines() {
+                if line.is_empty() {
+                    writeln!(stdout)?;
+                } else {
+                    writeln!(stdout, "    {}", line)?;
+                }
+            }
+            writeln!(stdout)?;
+        } else {
+            writeln!(stdout, "Nothing to commit.")?;
+        }
+        writeln!(stdout, "Would then pull and push.")?;
+        return Ok(());
+    }
+
+    let _lock = repo.lock()?;
+
+    if has_changes {
+        // Generate commit message before committing
+        let (commit_message, num_changes) = sync_commit_message(&repo, message)?;
+        repo.commit_all(&commit_message)?;
+
+        // Pull and push
+        repo.pull()?;
+        repo.push()?;
+        repo.record_sync()?;
+
+        stdout.set_color(&colors::success())?;
+        writeln!(
+            stdout,
+            "Synced successfully: committed {} changes, pulled, and pushed",
+            num_changes
+        )?;
+        stdout.reset()?;
+    } else {
+        // No local changes, just pull and push
+        repo.pull()?;
+        repo.push()?;
+        repo.record_sync()?;
+
+        stdout.set_color(&colors::success())?;
+        writeln!(stdout, "Synced successfully: pulled and pushed")?;
+        stdout.reset()?;
+    }
+
+    Ok(())
+}
+
+/// Line added to .gitattributes to route markdown files through the merge driver
+const MERGE_DRIVER_ATTRIBUTE: &str = "*.md merge=bnotes";
+
+pub fn install_merge_driver(notes_dir: &Path, color: ColorChoice) -> Result<()> {
+    validate_notes_dir(notes_dir)?;
+    let repo = GitRepo::new(notes_dir.to_path_buf())?;
+    repo.check_is_repo()?;
+
+    // Driver definitions live in the local git config, so each clone needs this
+    repo.set_config("merge.bnotes.name", "bnotes markdown merge")?;
+    repo.set_config("merge.bnotes.driver", "bnotes merge-driver %O %A %B")?;
+
+    // The attribute is committed so every clone uses the driver once it's defined
+    let attributes_path = notes_dir.join(".gitattributes");
+    let mut attributes = if attributes_path.exists() {
+        std::fs::read_to_string(&attributes_path).context("Failed to read .gitattributes")?
+    } else {
+        String::new()
+    };
+
+    let mut stdout = colors::create_stdout(color);
+
+    if attributes.lines().any(|line| line.trim() == MERGE_DRIVER_ATTRIBUTE) {
+        writeln!(stdout, ".gitattributes already routes notes through the merge driver.")?;
+    } else {
+        if !attributes.is_empty() && !attributes.ends_with('\n') {
+            attributes.push('\n');
+        }
+        attributes.push_str(MERGE_DRIVER_ATTRIBUTE);
+        attributes.push('\n');
+        std::fs::write(&attributes_path, attributes).context("Failed to write .gitattributes")?;
+        writeln!(stdout, "Added '{}' to .gitattributes.", MERGE_DRIVER_ATTRIBUTE)?;
+    }
+
+    stdout.set_color(&colors::success())?;
+    writeln!(stdout, "Installed the bnotes merge driver.")?;
+    stdout.reset()?;
+    writeln!(stdout, "Run 'bnotes sync --install-merge-driver' on your other machines too.")?;
+
+    Ok(())
+}
+
+/// Merge driver invoked by git as `bnotes merge-driver %O %A %B`
+///
+/// Writes the merged note to `ours` and returns whether the merge was clean.
+pub fn merge_driver(notes_dir: &Path, base: &Path, ours: &Path, theirs: &Path) -> Result<bool> {
+    let read = |path: &Path| {
+        std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))
+    };
+
+    // A broken config shouldn't leave a merge half done
+    let storage = RealStorage::new(notes_dir.to_path_buf());
+    let vocabulary = LibraryConfig::load_or_default(&storage).task_vocabulary();
+
+    let result = bnotes::merge_notes(&read(base)?, &read(ours)?, &read(theirs)?, &vocabulary);
+
+    std::fs::write(ours, &result.content).with_context(|| format!("Failed to write {}", ours.display()))?;
+
+    Ok(!result.has_conflicts)
+}
+
+pub fn pull(notes_dir: &Path, color: ColorChoice) -> Result<()> {
+    validate_notes_dir(notes_dir)?;
+    let repo = GitSync::open(notes_dir)?;
+
+    // Verify there is a remote to pull from
+    if !repo.has_remote()? {
+        return Err(SyncError::NoRemote.into());
+    }
+
+    // Uncommitted changes are stashed and reapplied around the merge
+    let _lock = repo.lock()?;
+    repo.pull()?;
+
+    let mut stdout = colors::create_stdout(color);
+    stdout.set_color(&colors::success())?;
+    writeln!(stdout, "Pulled successfully")?;
+    stdout.reset()?;
+
+    Ok(())
+}
+
+// ============================================================================
+// Note Commands
+// ============================================================================
+
+pub fn note_list(
+    notes_dir: &Path,
+    tags: &[String],
+    filters: &[bnotes::PropertyFilter],
+    sort: Option<&bnotes::PropertySort>,
+    columns: &[String],
+    color: ColorChoice,
+) -> Result<()> {
+    validate_notes_dir(notes_dir)?;
+    let storage = Box::new(RealStorage::new(notes_dir.to_path_buf()));
+    let bnotes = BNotes::open(storage)?;
+
+    let notes = bnotes.query_notes(tags, filters, sort)?;
+
+    let mut stdout = colors::create_stdout(color);
+
+    if notes.is_empty() {
+        if tags.is_empty() && filters.is_empty() {
+            writeln!(stdout, "No notes found.")?;
+        } else if filters.is_empty() {
+            writeln!(stdout, "No notes found with tags: {}", tags.join(", "))?;
+        } else {
+            writeln!(stdout, "No notes found matching filters.")?;
+        }
+        return Ok(());
+    }
+
+    let count = notes.len();
+
+    if columns.is_empty() {
+        for note in notes {
+            let tag_str = if note.tags.is_empty() {
+                String::new()
+            } else {
+                format!(" [{}]", note.tags.join(", "))
+            };
+
+            writeln!(stdout, "{}{}", note.title, tag_str)?;
+        }
+    } else {
+        write_property_table(&mut stdout, &notes, columns)?;
+    }
+
+    write!(stdout, "\nTotal: ")?;
+    stdout.set_color(&colors::highlight())?;
+    write!(stdout, "{}", count)?;
+    stdout.reset()?;
+    writeln!(stdout, " {}", pluralize(count, "note", "notes"))?;
+
+    Ok(())
+}
+
+/// Write notes as an aligned table with one column per property
+fn write_property_table<W: WriteColor>(stdout: &mut W, notes: &[bnotes::Note], columns: &[String]) -> io::Result<()> {
+    let rows: Vec<Vec<String>> = notes
+        .iter()
+        .map(|note| {
+            columns
+                .iter()
+                .map(|col| note.property(col).map(|v| v.to_string()).unwrap_or_default())
+                .collect()
+        })
+        .collect();
+
+    let widths: Vec<usize> = columns
+        .iter()
+        .enumerate()
+        .map(|(i, col)| {
+            rows.iter()
+                .map(|row| row[i].chars().count())
+                .chain(std::iter::once(col.chars().count()))
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    // Header
+    let mut header_color = colors::highlight();
+    header_color.set_bold(true);
+    stdout.set_color(&header_color)?;
+    let header: Vec<String> = columns
+        .iter()
+        .zip(&widths)
+        .map(|(col, width)| format!("{:<width$}", col, width = width))
+        .collect();
+    writeln!(stdout, "{}", header.join("  ").trim_end())?;
+    stdout.reset()?;
+
+    for row in rows {
+        let cells: Vec<String> = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect();
+        writeln!(stdout, "{}", cells.join("  ").trim_end())?;
+    }
+
+    Ok(())
+}
+
+pub fn note_show(notes_dir: &Path, title: &str) -> Result<()> {
+    validate_notes_dir(notes_dir)?;
+    let storage = Box::new(RealStorage::new(notes_dir.to_path_buf()));
+    let bnotes = BNotes::open(storage)?;
+
+    let matches = bnotes.find_note_by_title(title)?;
+
+    match matches.len() {
+        0 => anyhow::bail!("Note not found: {}", title),
+        1 => {
+            let note = &matches[0];
+            println!("{}", note.content);
+            Ok(())
+        }
+        _ => {
+            println!("Multiple notes found with title '{}':", title);
+            for note in matches {
+                println!("  - {}", note.path.display());
+            }
+            anyhow::bail!("Please be more specific or use the full path.");
+        }
+    }
+}
+
+pub fn note_links(notes_dir: &Path, title: &str, color: ColorChoice) -> Result<()> {
+    validate_notes_dir(notes_dir)?;
+    let storage = Box::new(RealStorage::new(notes_dir.to_path_buf()));
+    let bnotes = BNotes::open(storage)?;
+
+    let matches = bnotes.find_note_by_title(title)?;
+
+    let mut stdout = colors::create_stdout(color);
+
+    let note = match matches.len() {
+        0 => anyhow::bail!("Note not found: {}", title),
+        1 => &matches[0],
+        _ => {
+            writeln!(stdout, "Multiple notes found with title '{}':", title)?;
+            for note in matches {
+                writeln!(stdout, "  - {}", note.path.display())?;
+            }
+            anyhow::bail!("Please be more specific.");
+        }
+    };
+
+    let (outbound, inbound) = bnotes.get_note_links(&note.title)?;
+
+    writeln!(stdout, "Links for: {}\n", note.title)?;
+
+    // Show outbound links (what this note links to)
+    if !outbound.is_empty() {
+        write!(stdout, "Outbound links (")?;
         stdout.set_color(&colors::highlight())?;
         write!(stdout, "{}", outbound.len())?;
         stdout.reset()?;
@@ -611,6 +1279,25 @@ pub fn note_links(notes_dir: &Path, title: &str, color: ColorChoice) -> Result<(
         writeln!(stdout)?;
     }
 
+    // Show tasks that link here
+    let tasks = bnotes.get_linking_tasks(&note.title)?;
+    if !tasks.is_empty() {
+        write!(stdout, "Linked from tasks (")?;
+        stdout.set_color(&colors::highlight())?;
+        write!(stdout, "{}", tasks.len())?;
+        stdout.reset()?;
+        writeln!(stdout, "):")?;
+
+        for task in &tasks {
+            let checkbox = format!("[{}]", task.status.checkbox_char());
+            write!(stdout, "  {} {} ", checkbox, task.text)?;
+            stdout.set_color(&colors::dim())?;
+            writeln!(stdout, "({} {})", task.note_title, task.id)?;
+            stdout.reset()?;
+        }
+        writeln!(stdout)?;
+    }
+
     // If no links at all
     if outbound.is_empty() && inbound.is_empty() {
         writeln!(stdout, "No links found for this note.")?;
@@ -619,228 +1306,896 @@ pub fn note_links(notes_dir: &Path, title: &str, color: ColorChoice) -> Result<(
     Ok(())
 }
 
-pub fn note_graph(notes_dir: &Path, color: ColorChoice) -> Result<()> {
+pub fn note_graph(notes_dir: &Path, color: ColorChoice) -> Result<()> {
+    validate_notes_dir(notes_dir)?;
+    let storage = Box::new(RealStorage::new(notes_dir.to_path_buf()));
+    let bnotes = BNotes::open(storage)?;
+
+    let notes = bnotes.list_notes(&[])?;
+
+    let mut stdout = colors::create_stdout(color);
+
+    if notes.is_empty() {
+        writeln!(stdout, "No notes found.")?;
+        return Ok(());
+    }
+
+    let graph = bnotes.get_link_graph()?;
+
+    writeln!(stdout, "Link Graph ({} notes):\n", notes.len())?;
+
+    // Collect all notes that have links (either inbound or outbound)
+    let mut connected_notes: std::collections::HashSet<String> =
+        std::collections::HashSet::new();
+
+    for (note, links) in &graph.outbound {
+        if !links.is_empty() {
+            connected_notes.insert(note.clone());
+        }
+    }
+
+    for (note, links) in &graph.inbound {
+        if !links.is_empty() {
+            connected_notes.insert(note.clone());
+        }
+    }
+
+    if connected_notes.is_empty() {
+        writeln!(stdout, "No links found between notes.")?;
+        return Ok(());
+    }
+
+    // Sort for consistent output
+    let mut sorted_notes: Vec<_> = connected_notes.iter().collect();
+    sorted_notes.sort();
+
+    // Simple ASCII representation
+    for note in sorted_notes {
+        let outbound = graph.outbound.get(note);
+        let inbound = graph.inbound.get(note);
+
+        let out_count = outbound.map(|s| s.len()).unwrap_or(0);
+        let in_count = inbound.map(|s| s.len()).unwrap_or(0);
+
+        write!(stdout, "- {} (", note)?;
+        stdout.set_color(&colors::highlight())?;
+        write!(stdout, "->{} <-{}", out_count, in_count)?;
+        stdout.reset()?;
+        writeln!(stdout, ")")?;
+
+        if let Some(links) = outbound
+            && !links.is_empty()
+        {
+            let mut sorted_links: Vec<_> = links.iter().collect();
+            sorted_links.sort();
+            for link in sorted_links {
+                write!(stdout, "  ")?;
+                stdout.set_color(&colors::highlight())?;
+                write!(stdout, "->")?;
+                stdout.reset()?;
+                writeln!(stdout, " {}", link)?;
+            }
+        }
+    }
+
+    write!(stdout, "\nTotal: ")?;
+    stdout.set_color(&colors::highlight())?;
+    write!(stdout, "{}", connected_notes.len())?;
+    stdout.reset()?;
+    writeln!(
+        stdout,
+        " connected {}",
+        pluralize(connected_notes.len(), "note", "notes")
+    )?;
+
+    Ok(())
+}
+
+pub fn note_set(notes_dir: &Path, title: &str, key: &str, value: &str, color: ColorChoice) -> Result<()> {
+    validate_notes_dir(notes_dir)?;
+    let storage = Box::new(RealStorage::new(notes_dir.to_path_buf()));
+    let bnotes = BNotes::open(storage)?;
+
+    let note = find_single_note(&bnotes, title)?;
+    let changed = bnotes.set_note_property(&note.path, key, value)?;
+
+    let mut stdout = colors::create_stdout(color);
+    if changed {
+        write!(stdout, "Set ")?;
+        stdout.set_color(&colors::highlight())?;
+        write!(stdout, "{}", key)?;
+        stdout.reset()?;
+        writeln!(stdout, " on {}", note.title)?;
+    } else {
+        writeln!(stdout, "{} already has {} set to that value.", note.title, key)?;
+    }
+
+    Ok(())
+}
+
+pub fn note_unset(notes_dir: &Path, title: &str, key: &str, color: ColorChoice) -> Result<()> {
+    validate_notes_dir(notes_dir)?;
+    let storage = Box::new(RealStorage::new(notes_dir.to_path_buf()));
+    let bnotes = BNotes::open(storage)?;
+
+    let note = find_single_note(&bnotes, title)?;
+    let changed = bnotes.unset_note_property(&note.path, key)?;
+
+    let mut stdout = colors::create_stdout(color);
+    if changed {
+        write!(stdout, "Removed ")?;
+        stdout.set_color(&colors::highlight())?;
+        write!(stdout, "{}", key)?;
+        stdout.reset()?;
+        writeln!(stdout, " from {}", note.title)?;
+    } else {
+        writeln!(stdout, "{} has no property {}.", note.title, key)?;
+    }
+
+    Ok(())
+}
+
+pub fn note_history(notes_dir: &Path, title: &str, color: ColorChoice) -> Result<()> {
+    validate_notes_dir(notes_dir)?;
+    let storage = Box::new(RealStorage::new(notes_dir.to_path_buf()));
+    let bnotes = BNotes::open(storage)?;
+
+    let repo = GitRepo::new(notes_dir.to_path_buf())?;
+    repo.check_is_repo()?;
+
+    let note = find_single_note(&bnotes, title)?;
+    let path = note.path.to_string_lossy();
+    let history = repo.get_file_history(&path)?;
+
+    let mut stdout = colors::create_stdout(color);
+
+    if history.is_empty() {
+        writeln!(stdout, "{} has not been committed yet.", note.title)?;
+        return Ok(());
+    }
+
+    for commit in &history {
+        stdout.set_color(&colors::highlight())?;
+        write!(stdout, "{}", commit.short_hash)?;
+        stdout.reset()?;
+
+        write!(
+            stdout,
+            "  {}  {}",
+            commit.date.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M"),
+            commit.subject
+        )?;
+
+        stdout.set_color(&colors::dim())?;
+        write!(stdout, "  ({})", commit.author)?;
+        if commit.path != path {
+            write!(stdout, "  [{}]", commit.path)?;
+        }
+        stdout.reset()?;
+        writeln!(stdout)?;
+    }
+
+    writeln!(stdout)?;
+    writeln!(stdout, "Total: {} {}", history.len(), pluralize(history.len(), "commit", "commits"))?;
+
+    Ok(())
+}
+
+pub fn note_diff(notes_dir: &Path, title: &str, rev: Option<&str>, color: ColorChoice) -> Result<()> {
+    validate_notes_dir(notes_dir)?;
+    let storage = Box::new(RealStorage::new(notes_dir.to_path_buf()));
+    let bnotes = BNotes::open(storage)?;
+
+    let repo = GitRepo::new(notes_dir.to_path_buf())?;
+    repo.check_is_repo()?;
+
+    let note = find_single_note(&bnotes, title)?;
+    let path = note.path.to_string_lossy();
+
+    // Without a revision, show uncommitted changes
+    let rev = rev.unwrap_or("HEAD");
+    let (hash, old_path) = path_at_revision(&repo, &path, rev)?;
+
+    let old = repo.show_file(&hash, &old_path)?.unwrap_or_default();
+    let new = std::fs::read_to_string(notes_dir.join(&note.path))
+        .with_context(|| format!("Failed to read {}", note.path.display()))?;
+    if old == new {
+        return Ok(());
+    }
+
+    let mut diff = bnotes::repair::diff(&note.path, &old, &new);
+    if old_path != path {
+        diff = diff.replacen(&format!("--- a/{}", path), &format!("--- a/{}", old_path), 1);
+    }
+    write_diff(&mut colors::create_stdout(color), &diff)
+}
+
+pub fn note_restore(
+    notes_dir: &Path,
+    title: &str,
+    rev: &str,
+    force: bool,
+    color: ColorChoice,
+) -> Result<()> {
+    validate_notes_dir(notes_dir)?;
+    let storage = Box::new(RealStorage::new(notes_dir.to_path_buf()));
+    let bnotes = BNotes::open(storage)?;
+
+    let repo = GitRepo::new(notes_dir.to_path_buf())?;
+    repo.check_is_repo()?;
+
+    let note = find_single_note(&bnotes, title)?;
+    let path = note.path.to_string_lossy();
+
+    // Don't silently throw away edits that were never committed
+    if !force && repo.get_changed_files()?.iter().any(|f| *f == path) {
+        anyhow::bail!(
+            "{} has uncommitted changes. Commit them first or use --force to overwrite.",
+            note.title
+        );
+    }
+
+    let (hash, old_path) = path_at_revision(&repo, &path, rev)?;
+    let content = repo
+        .show_file(&hash, &old_path)?
+        .with_context(|| format!("{} does not exist at {}", note.title, rev))?;
+
+    std::fs::write(notes_dir.join(&note.path), content)
+        .with_context(|| format!("Failed to write {}", note.path.display()))?;
+
+    let mut stdout = colors::create_stdout(color);
+    stdout.set_color(&colors::success())?;
+    write!(stdout, "Restored {}", note.title)?;
+    stdout.reset()?;
+    writeln!(stdout, " to its version at {}.", rev)?;
+    writeln!(stdout, "Review with 'bnotes note diff \"{}\"' and run 'bnotes sync' to commit.", note.title)?;
+
+    Ok(())
+}
+
+/// Resolve a revision to a commit hash and the note's path as of that commit
+///
+/// The path is taken from the newest commit in the note's history that is an
+/// ancestor of the revision, so renames are followed.
+fn path_at_revision(repo: &GitRepo, path: &str, rev: &str) -> Result<(String, String)> {
+    let hash = repo.resolve_commit(rev)?;
+
+    for commit in repo.get_file_history(path)? {
+        if repo.is_ancestor(&commit.hash, &hash)? {
+            return Ok((hash, commit.path));
+        }
+    }
+
+    anyhow::bail!("{} has no history at {}", path, rev)
+}
+
+// ============================================================================
+// Tag Commands
+// ============================================================================
+
+pub fn tag_list(notes_dir: &Path, tree: bool, color: ColorChoice) -> Result<()> {
+    validate_notes_dir(notes_dir)?;
+    let storage = Box::new(RealStorage::new(notes_dir.to_path_buf()));
+    let bnotes = BNotes::open(storage)?;
+
+    let tag_tree = bnotes.list_tags()?;
+
+    let mut stdout = colors::create_stdout(color);
+
+    if tag_tree.is_empty() {
+        writeln!(stdout, "No tags found.")?;
+        return Ok(());
+    }
+
+    let nodes = tag_tree.flatten();
+
+    if tree {
+        for node in &nodes {
+            let depth = node.path.matches('/').count();
+            let name = node.path.rsplit('/').next().unwrap_or(&node.path);
+
+            write!(stdout, "{}", "  ".repeat(depth))?;
+            stdout.set_color(&colors::highlight())?;
+            write!(stdout, "{}", name)?;
+            stdout.reset()?;
+            write_tag_counts(&mut stdout, node.total_notes, node.total_tasks)?;
+        }
+    } else {
+        // Flat view only lists tags that are actually used
+        let used: Vec<_> = nodes.iter().filter(|n| n.notes > 0 || n.tasks > 0).collect();
+        let max_width = used.iter().map(|n| n.path.len()).max().unwrap_or(0);
+
+        for node in &used {
+            stdout.set_color(&colors::highlight())?;
+            write!(stdout, "{:<width$}", node.path, width = max_width)?;
+            stdout.reset()?;
+            write_tag_counts(&mut stdout, node.notes, node.tasks)?;
+        }
+    }
+
+    Ok(())
+}
+
+pub fn tag_rename(notes_dir: &Path, old: &str, new: &str, color: ColorChoice) -> Result<()> {
+    validate_notes_dir(notes_dir)?;
+    let storage = Box::new(RealStorage::new(notes_dir.to_path_buf()));
+    let bnotes = BNotes::open(storage)?;
+
+    let changed = bnotes.rename_tag(old, new)?;
+
+    let mut stdout = colors::create_stdout(color);
+    if changed.is_empty() {
+        writeln!(stdout, "No notes use tag '{}'.", old)?;
+        return Ok(());
+    }
+
+    for path in &changed {
+        stdout.set_color(&colors::dim())?;
+        writeln!(stdout, "  {}", path.display())?;
+        stdout.reset()?;
+    }
+
+    writeln!(stdout)?;
+    writeln!(
+        stdout,
+        "Renamed '{}' to '{}' in {} {}.",
+        old,
+        new,
+        changed.len(),
+        pluralize(changed.len(), "note", "notes")
+    )?;
+
+    Ok(())
+}
+
+/// Write dimmed note/task counts for a tag, followed by a newline
+fn write_tag_counts<W: WriteColor>(stdout: &mut W, notes: usize, tasks: usize) -> io::Result<()> {
+    let mut parts = Vec::new();
+    if notes > 0 {
+        parts.push(format!("{} {}", notes, pluralize(notes, "note", "notes")));
+    }
+    if tasks > 0 {
+        parts.push(format!("{} {}", tasks, pluralize(tasks, "task", "tasks")));
+    }
+
+    stdout.set_color(&colors::dim())?;
+    write!(stdout, "  ({})", parts.join(", "))?;
+    stdout.reset()?;
+    writeln!(stdout)
+}
+
+// ============================================================================
+// Task Commands
+// ============================================================================
+
+/// A column of `task list` output
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, clap::ValueEnum)]
+pub enum TaskColumn {
+    Id,
+    Score,
+    Note,
+    Status,
+    Urgency,
+    Priority,
+    Due,
+    Text,
+    Progress,
+    Tags,
+    /// Headings the task sits under
+    Heading,
+}
+
+/// Columns shown when `--columns` isn't given
+const DEFAULT_TASK_COLUMNS: &[TaskColumn] = &[
+    TaskColumn::Id,
+    TaskColumn::Note,
+    TaskColumn::Status,
+    TaskColumn::Urgency,
+    TaskColumn::Priority,
+    TaskColumn::Text,
+    TaskColumn::Progress,
+    TaskColumn::Tags,
+    TaskColumn::Due,
+    TaskColumn::Heading,
+];
+
+/// What `task list --group-by` splits tasks into sections by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum TaskGroupBy {
+    Note,
+    Tag,
+    Urgency,
+    Priority,
+    Due,
+    Status,
+}
+
+/// File format for `task export` and `task import`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum TaskFormat {
+    Todotxt,
+}
+
+/// How `task list` lays out its output
+#[derive(Debug, Clone, Default)]
+pub struct TaskListDisplay {
+    /// Show subtasks indented under their parents
+    pub tree: bool,
+    /// Leave out subtasks of completed tasks
+    pub hide_completed_subtasks: bool,
+    /// Only show blocked tasks, each with what it's waiting on
+    pub blocked: bool,
+    /// Leave out blocked tasks
+    pub hide_blocked: bool,
+    /// Show each task's computed score
+    pub show_score: bool,
+    /// Split tasks into sections with a header and count
+    pub group_by: Option<TaskGroupBy>,
+    /// Show at most this many tasks
+    pub limit: Option<usize>,
+    /// Columns to show, in order (empty for the default columns)
+    pub columns: Vec<TaskColumn>,
+    /// Leave out the note column when grouping by note
+    pub compact: bool,
+}
+
+/// Combine a query expression with the `--tag`, `--status` and `--note` options
+pub fn task_query(
+    expression: &[String],
+    tags: &[String],
+    status: Option<&str>,
+    note_pattern: Option<&str>,
+) -> Result<bnotes::TaskQuery> {
+    let mut query = bnotes::TaskQuery::parse(&expression.join(" "))?.and(bnotes::TaskQuery::tags(tags)?);
+    if let Some(status) = status {
+        query = query.and(bnotes::TaskQuery::status(status));
+    }
+    if let Some(pattern) = note_pattern {
+        query = query.and(bnotes::TaskQuery::note(pattern));
+    }
+    Ok(query)
+}
+
+pub fn task_list(
+    notes_dir: &Path,
+    query: bnotes::TaskQuery,
+    sort_order: bnotes::TaskSortOrder,
+    display: TaskListDisplay,
+    color: ColorChoice,
+) -> Result<()> {
     validate_notes_dir(notes_dir)?;
     let storage = Box::new(RealStorage::new(notes_dir.to_path_buf()));
-    let bnotes = BNotes::with_defaults(storage);
+    // Notes without a `created` date count their age from their first commit.
+    // The history is only read if such a note has tasks to score.
+    let dates_dir = notes_dir.to_path_buf();
+    let git_dates = OnceCell::new();
+    let bnotes = BNotes::open(storage)?.with_note_dates(move |path| {
+        let git_dates = git_dates.get_or_init(|| git_file_dates(&dates_dir).ok().flatten());
+        file_dates(git_dates.as_ref(), &dates_dir, path).ok().map(|(created, _)| created)
+    });
+
+    let query = if display.blocked {
+        query.and(bnotes::TaskQuery::blocked())
+    } else if display.hide_blocked {
+        query.and(bnotes::TaskQuery::blocked().negate())
+    } else {
+        query
+    };
+    let mut tasks = bnotes.query_tasks(&query, sort_order)?;
 
-    let notes = bnotes.list_notes(&[])?;
+    if display.hide_completed_subtasks {
+        tasks.retain(|task| !task.under_completed);
+    }
+
+    let dependencies = if display.blocked { Some(bnotes.task_dependencies()?) } else { None };
+
+    let matching = tasks.len();
+    if let Some(limit) = display.limit {
+        tasks.truncate(limit);
+    }
 
     let mut stdout = colors::create_stdout(color);
 
-    if notes.is_empty() {
-        writeln!(stdout, "No notes found.")?;
+    if tasks.is_empty() {
+        writeln!(stdout, "No tasks found.")?;
         return Ok(());
     }
 
-    let graph = bnotes.get_link_graph()?;
+    let mut columns = if display.columns.is_empty() {
+        DEFAULT_TASK_COLUMNS.to_vec()
+    } else {
+        display.columns.clone()
+    };
+    if display.show_score && !columns.contains(&TaskColumn::Score) {
+        let after_id = columns.iter().position(|c| *c == TaskColumn::Id).map(|i| i + 1).unwrap_or(0);
+        columns.insert(after_id, TaskColumn::Score);
+    }
+    if display.compact && display.group_by == Some(TaskGroupBy::Note) {
+        columns.retain(|c| *c != TaskColumn::Note);
+    }
 
-    writeln!(stdout, "Link Graph ({} notes):\n", notes.len())?;
+    let table = TaskTable {
+        widths: TaskTable::widths(&columns, &tasks),
+        columns,
+        vocabulary: bnotes.config().task_vocabulary(),
+        scorer: if display.show_score || display.columns.contains(&TaskColumn::Score) {
+            Some(bnotes.task_scorer()?)
+        } else {
+            None
+        },
+        today: chrono::Local::now().date_naive(),
+    };
 
-    // Collect all notes that have links (either inbound or outbound)
-    let mut connected_notes: std::collections::HashSet<String> =
-        std::collections::HashSet::new();
+    let groups = match display.group_by {
+        Some(group_by) => task_groups(&tasks, group_by, &table.vocabulary, table.today),
+        None => vec![(String::new(), tasks.iter().collect())],
+    };
 
-    for (note, links) in &graph.outbound {
-        if !links.is_empty() {
-            connected_notes.insert(note.clone());
+    for (i, (label, group)) in groups.iter().enumerate() {
+        if display.group_by.is_some() {
+            if i > 0 {
+                writeln!(stdout)?;
+            }
+            let mut header_color = colors::highlight();
+            header_color.set_bold(true);
+            stdout.set_color(&header_color)?;
+            write!(stdout, "{}", label)?;
+            stdout.reset()?;
+            writeln!(stdout, " ({})", group.len())?;
         }
-    }
 
-    for (note, links) in &graph.inbound {
-        if !links.is_empty() {
-            connected_notes.insert(note.clone());
+        let rows = if display.tree {
+            task_tree(group)
+        } else {
+            group.iter().map(|task| (*task, 0)).collect()
+        };
+
+        for (task, depth) in rows {
+            table.write_row(&mut stdout, task, depth)?;
+
+            if display.blocked {
+                let indent = table.widths.get(&TaskColumn::Id).copied().unwrap_or(0);
+                for blocker in dependencies.iter().flat_map(|graph| graph.blockers(task)) {
+                    stdout.set_color(&colors::dim())?;
+                    write!(stdout, "{:width$} waiting on {}", "", blocker.reference, width = indent)?;
+                    match blocker.task {
+                        Some(waiting_on) => write!(stdout, ": {} ({})", waiting_on.text, waiting_on.note_title)?,
+                        None => write!(stdout, " (not found)")?,
+                    }
+                    stdout.reset()?;
+                    writeln!(stdout)?;
+                }
+            }
         }
     }
 
-    if connected_notes.is_empty() {
-        writeln!(stdout, "No links found between notes.")?;
-        return Ok(());
+    write!(stdout, "\nTotal: {} {}", matching, pluralize(matching, "task", "tasks"))?;
+    if tasks.len() < matching {
+        write!(stdout, " (showing {})", tasks.len())?;
     }
+    writeln!(stdout)?;
 
-    // Sort for consistent output
-    let mut sorted_notes: Vec<_> = connected_notes.iter().collect();
-    sorted_notes.sort();
+    Ok(())
+}
 
-    // Simple ASCII representation
-    for note in sorted_notes {
-        let outbound = graph.outbound.get(note);
-        let inbound = graph.inbound.get(note);
+/// Lays out tasks as aligned rows of the chosen columns
+struct TaskTable {
+    columns: Vec<TaskColumn>,
+    /// Width of each padded column; columns nobody has a value for are left out
+    widths: std::collections::HashMap<TaskColumn, usize>,
+    vocabulary: bnotes::note::TaskVocabulary,
+    scorer: Option<bnotes::TaskScorer>,
+    today: chrono::NaiveDate,
+}
 
-        let out_count = outbound.map(|s| s.len()).unwrap_or(0);
-        let in_count = inbound.map(|s| s.len()).unwrap_or(0);
+impl TaskTable {
+    fn widths(columns: &[TaskColumn], tasks: &[bnotes::Task]) -> std::collections::HashMap<TaskColumn, usize> {
+        let max = |width: &dyn Fn(&bnotes::Task) -> usize| tasks.iter().map(width).max().unwrap_or(0);
+        columns
+            .iter()
+            .filter_map(|column| {
+                let width = match column {
+                    TaskColumn::Id => max(&|t| t.id.len()),
+                    TaskColumn::Score => 5,
+                    TaskColumn::Note => max(&|t| t.note_title.len()),
+                    TaskColumn::Status => 3,
+                    TaskColumn::Urgency => max(&|t| t.urgency.as_ref().map(|u| u.len()).unwrap_or(0)),
+                    TaskColumn::Priority => max(&|t| t.priority.as_ref().map(|p| p.len() + 2).unwrap_or(0)),
+                    _ => return None,
+                };
+                Some((*column, width))
+            })
+            .collect()
+    }
 
-        write!(stdout, "- {} (", note)?;
-        stdout.set_color(&colors::highlight())?;
-        write!(stdout, "->{} <-{}", out_count, in_count)?;
-        stdout.reset()?;
-        writeln!(stdout, ")")?;
+    fn write_row<W: WriteColor>(&self, stdout: &mut W, task: &bnotes::Task, depth: usize) -> io::Result<()> {
+        let mut first = true;
+        for column in &self.columns {
+            let (text, color) = self.cell(*column, task, depth);
+            let width = self.widths.get(column).copied();
+            // Padded columns that are empty for every task, and empty trailing cells, are skipped
+            if width == Some(0) || (width.is_none() && text.is_empty()) {
+                continue;
+            }
+            if !first {
+                write!(stdout, " ")?;
+            }
+            first = false;
 
-        if let Some(links) = outbound
-            && !links.is_empty()
-        {
-            let mut sorted_links: Vec<_> = links.iter().collect();
-            sorted_links.sort();
-            for link in sorted_links {
-                write!(stdout, "  ")?;
-                stdout.set_color(&colors::highlight())?;
-                write!(stdout, "->")?;
-                stdout.reset()?;
-                writeln!(stdout, " {}", link)?;
+            stdout.set_color(&color)?;
+            match (column, width) {
+                (TaskColumn::Urgency | TaskColumn::Score, Some(width)) => write!(stdout, "{:>width$}", text, width = width)?,
+                (_, Some(width)) => write!(stdout, "{:<width$}", text, width = width)?,
+                (_, None) => write!(stdout, "{}", text)?,
             }
+            stdout.reset()?;
         }
+        writeln!(stdout)
     }
 
-    write!(stdout, "\nTotal: ")?;
-    stdout.set_color(&colors::highlight())?;
-    write!(stdout, "{}", connected_notes.len())?;
-    stdout.reset()?;
-    writeln!(
-        stdout,
-        " connected {}",
-        pluralize(connected_notes.len(), "note", "notes")
-    )?;
+    fn cell(&self, column: TaskColumn, task: &bnotes::Task, depth: usize) -> (String, termcolor::ColorSpec) {
+        match column {
+            TaskColumn::Id => (task.id.clone(), colors::dim()),
+            TaskColumn::Score => {
+                let score = self.scorer.as_ref().map(|scorer| scorer.score(task)).unwrap_or(0.0);
+                (format!("{:.1}", score), colors::default())
+            }
+            TaskColumn::Note => (task.note_title.clone(), colors::highlight()),
+            TaskColumn::Status => {
+                // Checkbox in its status color ([x] green, [>] yellow, [ ] default unless configured)
+                let status_color = self.vocabulary.info(&task.status).and_then(|info| info.color.as_deref());
+                (
+                    format!("[{}]", task.status.checkbox_char()),
+                    status_color.map(colors::named).unwrap_or_else(colors::default),
+                )
+            }
+            TaskColumn::Urgency => (task.urgency.clone().unwrap_or_default(), colors::default()),
+            TaskColumn::Priority => (
+                task.priority.as_ref().map(|p| format!("({})", p)).unwrap_or_default(),
+                colors::default(),
+            ),
+            TaskColumn::Due => match task.due {
+                Some(due) => {
+                    let color = match due.cmp(&self.today) {
+                        _ if !task.status.is_incomplete() => colors::default(),
+                        std::cmp::Ordering::Less => colors::error(),
+                        std::cmp::Ordering::Equal => colors::warning(),
+                        std::cmp::Ordering::Greater => colors::default(),
+                    };
+                    (format!("due:{}", due.format("%Y-%m-%d")), color)
+                }
+                None => (String::new(), colors::default()),
+            },
+            // Indented under its parent in tree mode
+            TaskColumn::Text => (format!("{}{}", "  ".repeat(depth), task.text), colors::default()),
+            TaskColumn::Progress => (
+                task.progress.map(|(done, total)| format!("[{}/{}]", done, total)).unwrap_or_default(),
+                colors::dim(),
+            ),
+            TaskColumn::Tags => (
+                task.tags.iter().map(|tag| format!("@{}", tag)).collect::<Vec<_>>().join(" "),
+                colors::highlight(), // Cyan, same as note name
+            ),
+            TaskColumn::Heading => match task_heading(task) {
+                heading if heading.is_empty() => (heading, colors::dim()),
+                heading => (format!("({})", heading), colors::dim()),
+            },
+        }
+    }
+}
 
-    Ok(())
+/// The headings a task sits under, e.g. "Backlog > Later"
+///
+/// A top-level heading that is just the note's title is left out.
+fn task_heading(task: &bnotes::Task) -> String {
+    let mut headings: Vec<&str> = task
+        .breadcrumb
+        .iter()
+        .map(|heading| heading.trim_start_matches('#').trim())
+        .collect();
+    if task.breadcrumb.first().is_some_and(|h| h.starts_with("# ")) && headings.first() == Some(&task.note_title.as_str()) {
+        headings.remove(0);
+    }
+    headings.join(" > ")
 }
 
-// ============================================================================
-// Task Commands
-// ============================================================================
+/// Split tasks into labelled sections, keeping their order within each
+///
+/// Grouping by tag puts a task in the section for each of its tags.
+fn task_groups<'a>(
+    tasks: &'a [bnotes::Task],
+    group_by: TaskGroupBy,
+    vocabulary: &bnotes::note::TaskVocabulary,
+    today: chrono::NaiveDate,
+) -> Vec<(String, Vec<&'a bnotes::Task>)> {
+    // Sections are ordered by rank, then label
+    let keys = |task: &bnotes::Task| -> Vec<(usize, String)> {
+        match group_by {
+            TaskGroupBy::Note => vec![(0, task.note_title.clone())],
+            TaskGroupBy::Tag if task.tags.is_empty() => vec![(1, "No tags".to_string())],
+            TaskGroupBy::Tag => task.tags.iter().map(|tag| (0, format!("@{}", tag))).collect(),
+            TaskGroupBy::Urgency => vec![match task.urgency.as_deref() {
+                Some(urgency @ "!!!") => (0, urgency.to_string()),
+                Some(urgency @ "!!") => (1, urgency.to_string()),
+                Some(urgency) => (2, urgency.to_string()),
+                None => (3, "No urgency".to_string()),
+            }],
+            TaskGroupBy::Priority => vec![match &task.priority {
+                Some(priority) => (0, format!("({})", priority)),
+                None => (1, "No priority".to_string()),
+            }],
+            TaskGroupBy::Due => vec![match task.due.map(|due| (due - today).num_days()) {
+                Some(days) if days < 0 => (0, "Overdue".to_string()),
+                Some(0) => (1, "Today".to_string()),
+                Some(days) if days <= 7 => (2, "Next 7 days".to_string()),
+                Some(_) => (3, "Later".to_string()),
+                None => (4, "No due date".to_string()),
+            }],
+            TaskGroupBy::Status => {
+                let rank = if task.status.is_incomplete() { 0 } else { 1 };
+                vec![(rank, vocabulary.name(&task.status))]
+            }
+        }
+    };
 
-pub fn task_list(
-    notes_dir: &Path,
-    tags: &[String],
-    status: Option<String>,
-    note_pattern: Option<&str>,
-    sort_order: bnotes::TaskSortOrder,
-    color: ColorChoice,
-) -> Result<()> {
-    validate_notes_dir(notes_dir)?;
-    let storage = Box::new(RealStorage::new(notes_dir.to_path_buf()));
-    let bnotes = BNotes::with_defaults(storage);
+    let mut groups: std::collections::BTreeMap<(usize, String), Vec<&bnotes::Task>> = std::collections::BTreeMap::new();
+    for task in tasks {
+        for key in keys(task) {
+            groups.entry(key).or_default().push(task);
+        }
+    }
+    groups.into_iter().map(|((_, label), tasks)| (label, tasks)).collect()
+}
 
-    let mut tasks = bnotes.list_tasks(&[], status.as_deref(), sort_order)?;
+/// Order tasks so subtasks follow their parent, with their depth in the tree
+///
+/// Siblings keep the order they have in `tasks`. Tasks whose parent isn't in
+/// the list are shown at the top level.
+fn task_tree<'a>(tasks: &[&'a bnotes::Task]) -> Vec<(&'a bnotes::Task, usize)> {
+    let key = |task: &bnotes::Task, id: &str| (task.note_path.clone(), id.to_string());
+    let listed: std::collections::HashSet<_> = tasks.iter().map(|t| key(t, &t.id)).collect();
+
+    let mut children: std::collections::HashMap<_, Vec<&bnotes::Task>> = std::collections::HashMap::new();
+    let mut roots = Vec::new();
+    for &task in tasks {
+        match &task.parent {
+            Some(parent) if listed.contains(&key(task, parent)) => {
+                children.entry(key(task, parent)).or_default().push(task)
+            }
+            _ => roots.push(task),
+        }
+    }
 
-    // Filter by note pattern if provided
-    if let Some(pattern) = note_pattern {
-        // Use lowercase pattern and titles for case-insensitive matching
-        let pattern_lower = pattern.to_lowercase();
-        let matcher = WildMatch::new(&pattern_lower);
-        tasks.retain(|task| matcher.matches(&task.note_title.to_lowercase()));
+    fn visit<'a>(
+        task: &'a bnotes::Task,
+        depth: usize,
+        children: &std::collections::HashMap<(PathBuf, String), Vec<&'a bnotes::Task>>,
+        rows: &mut Vec<(&'a bnotes::Task, usize)>,
+    ) {
+        rows.push((task, depth));
+        for child in children.get(&(task.note_path.clone(), task.id.clone())).into_iter().flatten() {
+            visit(child, depth + 1, children, rows);
+        }
     }
 
-    // Filter by tags if provided (AND logic with hierarchical matching)
-    if !tags.is_empty() {
-        // Normalize and deduplicate filter tags
-        let mut filter_tags: Vec<String> = tags.iter()
-            .map(|t| t.to_lowercase())
-            .collect();
-        filter_tags.sort();
-        filter_tags.dedup();
-
-        tasks.retain(|task| {
-            filter_tags.iter().all(|filter_tag| {
-                task.tags.iter().any(|task_tag| {
-                    // Hierarchical: task_tag equals or starts with filter_tag/
-                    task_tag == filter_tag || task_tag.starts_with(&format!("{}/", filter_tag))
-                })
-            })
-        });
+    let mut rows = Vec::new();
+    for root in roots {
+        visit(root, 0, &children, &mut rows);
     }
+    rows
+}
+
+pub fn task_done(notes_dir: &Path, ids: &[String], color: ColorChoice) -> Result<()> {
+    validate_notes_dir(notes_dir)?;
+    let storage = Box::new(RealStorage::new(notes_dir.to_path_buf()));
+    let bnotes = BNotes::open(storage)?;
 
     let mut stdout = colors::create_stdout(color);
+    for id in ids {
+        let task = bnotes.set_task_status(id, bnotes::note::TaskStatus::Completed)?;
+        stdout.set_color(&colors::success())?;
+        write!(stdout, "[x]")?;
+        stdout.reset()?;
+        writeln!(stdout, " {} ({})", task.text, task.note_title)?;
+    }
 
-    if tasks.is_empty() {
-        writeln!(stdout, "No tasks found.")?;
+    Ok(())
+}
+
+pub fn task_anchor(notes_dir: &Path, ids: &[String], all: bool, color: ColorChoice) -> Result<()> {
+    validate_notes_dir(notes_dir)?;
+    let storage = Box::new(RealStorage::new(notes_dir.to_path_buf()));
+    let bnotes = BNotes::open(storage)?;
+
+    let mut stdout = colors::create_stdout(color);
+    if all {
+        let count = bnotes.anchor_all_tasks()?;
+        writeln!(stdout, "Anchored {} {}.", count, pluralize(count, "task", "tasks"))?;
         return Ok(());
     }
 
-    // Calculate maximum column widths for alignment
-    let max_note_width = tasks.iter()
-        .map(|t| t.note_title.len())
-        .max()
-        .unwrap_or(0);
+    for id in ids {
+        let anchor = bnotes.anchor_task(id)?;
+        write!(stdout, "{} ", id.trim_start_matches('^'))?;
+        stdout.set_color(&colors::dim())?;
+        write!(stdout, "->")?;
+        stdout.reset()?;
+        writeln!(stdout, " {}", anchor)?;
+    }
 
-    let max_urgency_width = tasks.iter()
-        .map(|t| t.urgency.as_ref().map(|u| u.to_string().len()).unwrap_or(0))
-        .max()
-        .unwrap_or(0);
+    Ok(())
+}
 
-    let max_priority_width = tasks.iter()
-        .map(|t| t.priority.as_ref().map(|p| format!("({})", p).len()).unwrap_or(0))
-        .max()
-        .unwrap_or(0);
+/// Print tasks matching a query in another task format
+pub fn task_export(notes_dir: &Path, query: bnotes::TaskQuery, format: TaskFormat) -> Result<()> {
+    validate_notes_dir(notes_dir)?;
+    let storage = Box::new(RealStorage::new(notes_dir.to_path_buf()));
+    let bnotes = BNotes::open(storage)?;
 
-    // Display tasks with aligned columns
-    for task in &tasks {
-        // Note name in cyan, left-aligned with padding
-        stdout.set_color(&colors::highlight())?;
-        write!(stdout, "{:<width$}", task.note_title, width = max_note_width)?;
-        stdout.reset()?;
+    let output = match format {
+        TaskFormat::Todotxt => bnotes.export_todotxt(&query)?,
+    };
+    io::stdout().lock().write_all(output.as_bytes())?;
 
-        write!(stdout, " ")?;
+    Ok(())
+}
 
-        // Checkbox - [x] in green, [>] in yellow, [ ] default
-        match task.status {
-            bnotes::note::TaskStatus::Completed => {
-                stdout.set_color(&colors::success())?;
-                write!(stdout, "[x]")?;
-                stdout.reset()?;
-            }
-            bnotes::note::TaskStatus::Migrated => {
-                stdout.set_color(&colors::warning())?;
-                write!(stdout, "[>]")?;
-                stdout.reset()?;
-            }
-            bnotes::note::TaskStatus::Uncompleted => {
-                write!(stdout, "[ ]")?;
-            }
-        }
+/// Append the tasks in a file to a note
+pub fn task_import(notes_dir: &Path, file: &Path, into: &str, format: TaskFormat, color: ColorChoice) -> Result<()> {
+    validate_notes_dir(notes_dir)?;
+    let storage = Box::new(RealStorage::new(notes_dir.to_path_buf()));
+    let bnotes = BNotes::open(storage)?;
 
-        write!(stdout, " ")?;
+    let content = std::fs::read_to_string(file).with_context(|| format!("Failed to read {}", file.display()))?;
+    let note = find_single_note(&bnotes, into)?;
+    let count = match format {
+        TaskFormat::Todotxt => bnotes.import_todotxt(&note.path, &content)?,
+    };
 
-        // Urgency: right-aligned with dynamic width
-        let urgency_str = task.urgency.as_ref()
-            .map(|u| u.to_string())
-            .unwrap_or_default();
-        write!(stdout, "{:>width$}", urgency_str, width = max_urgency_width)?;
+    let mut stdout = colors::create_stdout(color);
+    stdout.set_color(&colors::success())?;
+    write!(stdout, "Imported")?;
+    stdout.reset()?;
+    writeln!(stdout, " {} {} into {}", count, pluralize(count, "task", "tasks"), note.title)?;
 
-        if max_urgency_width > 0 {
-            write!(stdout, " ")?;
-        }
+    Ok(())
+}
 
-        // Priority: dynamic width
-        let priority_str = task.priority.as_ref()
-            .map(|p| format!("({})", p))
-            .unwrap_or_default();
-        write!(stdout, "{:<width$}", priority_str, width = max_priority_width)?;
+// ============================================================================
+// Export Commands
+// ============================================================================
 
-        if max_priority_width > 0 {
-            write!(stdout, " ")?;
-        }
+/// Write dated tasks, and optionally daily notes, as an iCalendar file
+pub fn export_ics(
+    notes_dir: &Path,
+    query: bnotes::TaskQuery,
+    output: Option<&Path>,
+    daily_notes: bool,
+    color: ColorChoice,
+) -> Result<()> {
+    validate_notes_dir(notes_dir)?;
+    let storage = Box::new(RealStorage::new(notes_dir.to_path_buf()));
+    let bnotes = BNotes::open(storage)?;
 
-        // Task text
-        write!(stdout, "{} ", task.text)?;
+    // Calendar apps need absolute links back to the notes
+    let link_root = notes_dir.canonicalize().ok();
+    let calendar = bnotes.export_ics(&query, daily_notes, link_root)?;
 
-        // Tags (if any)
-        if !task.tags.is_empty() {
-            stdout.set_color(&colors::highlight())?; // Cyan, same as note name
-            for tag in &task.tags {
-                write!(stdout, "@{} ", tag)?;
-            }
-            stdout.reset()?;
-        }
+    let Some(output) = output else {
+        print!("{}", calendar.to_ics());
+        return Ok(());
+    };
+    std::fs::write(output, calendar.to_ics()).with_context(|| format!("Failed to write {}", output.display()))?;
 
-        writeln!(stdout)?;
+    let mut stdout = colors::create_stdout(color);
+    stdout.set_color(&colors::success())?;
+    write!(stdout, "Wrote")?;
+    stdout.reset()?;
+    write!(stdout, " {} {}", calendar.todos(), pluralize(calendar.todos(), "task", "tasks"))?;
+    if daily_notes {
+        write!(stdout, " and {} daily {}", calendar.events(), pluralize(calendar.events(), "note", "notes"))?;
     }
-
-    writeln!(
-        stdout,
-        "\nTotal: {} {}",
-        tasks.len(),
-        pluralize(tasks.len(), "task", "tasks")
-    )?;
+    writeln!(stdout, " to {}", output.display())?;
 
     Ok(())
 }
@@ -865,7 +2220,7 @@ pub fn periodic<P: bnotes::PeriodType>(
 ) -> Result<()> {
     validate_notes_dir(notes_dir)?;
     let storage = Box::new(RealStorage::new(notes_dir.to_path_buf()));
-    let bnotes = BNotes::with_defaults(storage);
+    let bnotes = BNotes::open(storage)?;
 
     match action {
         PeriodicAction::Open(date_str) => {
@@ -948,7 +2303,7 @@ pub fn weekly(
 
     validate_notes_dir(notes_dir)?;
     let storage = Box::new(RealStorage::new(notes_dir.to_path_buf()));
-    let bnotes = BNotes::with_defaults(storage);
+    let bnotes = BNotes::open(storage)?;
 
     match action {
         PeriodicAction::Open(date_opt) => {
@@ -1043,6 +2398,21 @@ fn launch_editor(notes_dir: &Path, note_path: &PathBuf, bnotes: &BNotes, print_p
         return Ok(());
     }
 
+    let auto_sync = &bnotes.config().auto_sync;
+    let _release = ReleaseAbandonedLocks;
+
+    // Sync problems are reported but never keep the note from opening
+    let flushed = flush_pending_sync(notes_dir, auto_sync).unwrap_or_else(|e| {
+        eprintln!("Warning: Automatic sync failed: {}", e);
+        false
+    });
+    if auto_sync.pull_before_open
+        && !flushed
+        && let Err(e) = auto_pull(notes_dir, Duration::from_secs(auto_sync.network_timeout_seconds))
+    {
+        eprintln!("Warning: Failed to pull before opening: {}", e);
+    }
+
     // Capture state before editing (if possible)
     let before_state = bnotes::capture_note_state(&full_path).ok();
 
@@ -1067,5 +2437,157 @@ fn launch_editor(notes_dir: &Path, note_path: &PathBuf, bnotes: &BNotes, print_p
         eprintln!("Warning: Failed to update timestamp: {}", e);
     }
 
+    if auto_sync.mode != AutoSyncMode::Off
+        && let Err(e) = auto_sync_after_edit(notes_dir, auto_sync)
+    {
+        eprintln!("Warning: Automatic sync failed: {}", e);
+    }
+
+    Ok(())
+}
+
+/// Pull before opening a note, if the notes directory is a repo with a remote
+fn auto_pull(notes_dir: &Path, timeout: Duration) -> Result<()> {
+    let Ok(repo) = GitSync::open(notes_dir) else {
+        return Ok(());
+    };
+    if !repo.has_remote()? {
+        return Ok(());
+    }
+
+    // Another process is already syncing, don't wait for it
+    let Some(lock) = repo.try_lock()? else {
+        return Ok(());
+    };
+
+    let _lock = with_timeout(notes_dir, lock, timeout, GitSync::fetch)?;
+    repo.merge_fetched()?;
+    Ok(())
+}
+
+/// Commit after an edit, then pull and push according to the auto-sync mode
+fn auto_sync_after_edit(notes_dir: &Path, config: &AutoSyncConfig) -> Result<()> {
+    let repo = GitSync::open(notes_dir)?;
+
+    let Some(_lock) = repo.try_lock()? else {
+        anyhow::bail!("another bnotes process is running git, skipping this time");
+    };
+
+    if repo.has_changes()? {
+        let subject = format!("bnotes auto-sync: {}", GitRepo::get_timestamp());
+        let (commit_message, _) = sync_commit_message(&repo, Some(subject))?;
+        repo.commit_all(&commit_message)?;
+    }
+
+    if !repo.has_remote()? {
+        return Ok(());
+    }
+
+    if config.mode == AutoSyncMode::Debounced && !debounce_elapsed(&repo, config) {
+        // Picked up by the next bnotes command once the interval has passed
+        repo.mark_sync_pending()?;
+        return Ok(());
+    }
+
+    repo.pull()?;
+    repo.push()?;
+    repo.record_sync()?;
+
     Ok(())
 }
+
+/// Pull and push a sync that debounced auto-sync put off, once it's due
+///
+/// Runs before a note is opened, so the last edits of a session go out with
+/// the next one. Returns whether it synced.
+fn flush_pending_sync(notes_dir: &Path, config: &AutoSyncConfig) -> Result<bool> {
+    let Ok(repo) = GitSync::open(notes_dir) else {
+        return Ok(false);
+    };
+    if !repo.is_sync_pending()
+        || config.mode != AutoSyncMode::Debounced
+        || !debounce_elapsed(&repo, config)
+        || !repo.has_remote()?
+    {
+        return Ok(false);
+    }
+
+    let Some(lock) = repo.try_lock()? else {
+        return Ok(false);
+    };
+
+    let timeout = Duration::from_secs(config.network_timeout_seconds);
+    let lock = with_timeout(notes_dir, lock, timeout, GitSync::fetch)?;
+    repo.merge_fetched()?;
+    let _lock = with_timeout(notes_dir, lock, timeout, <GitSync as bnotes::sync::Sync>::push)?;
+    repo.record_sync()?;
+    Ok(true)
+}
+
+/// Run a network operation on its own repository handle, giving up after `timeout`
+///
+/// A remote that doesn't answer is left to time out in the background, so
+/// only operations that don't touch the working tree may be passed in. The
+/// sync lock goes with the operation and is handed back when it finishes in
+/// time; otherwise it's released only once the operation gives up, so no other
+/// sync can start while it's still writing refs.
+fn with_timeout(
+    notes_dir: &Path,
+    lock: SyncLock,
+    timeout: Duration,
+    op: fn(&GitSync) -> bnotes::sync::SyncResult<()>,
+) -> Result<SyncLock> {
+    let slot: LockSlot = Arc::new(Mutex::new(Some(lock)));
+    let thread_slot = Arc::clone(&slot);
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let root = notes_dir.to_path_buf();
+    std::thread::spawn(move || {
+        let result = GitSync::open(&root).and_then(|repo| op(&repo));
+        // Nobody is waiting anymore, so release the lock now
+        if sender.send(result).is_err() {
+            take_lock(&thread_slot);
+        }
+    });
+
+    match receiver.recv_timeout(timeout) {
+        Ok(result) => {
+            result?;
+            Ok(take_lock(&slot).expect("the lock is only released after a timeout"))
+        }
+        Err(_) => {
+            ABANDONED_LOCKS.lock().unwrap_or_else(PoisonError::into_inner).push(slot);
+            anyhow::bail!("the remote didn't answer within {}s", timeout.as_secs())
+        }
+    }
+}
+
+/// The sync lock held by a network operation running in the background
+type LockSlot = Arc<Mutex<Option<SyncLock>>>;
+
+/// Locks of timed-out operations that may still be running
+static ABANDONED_LOCKS: Mutex<Vec<LockSlot>> = Mutex::new(Vec::new());
+
+fn take_lock(slot: &LockSlot) -> Option<SyncLock> {
+    slot.lock().unwrap_or_else(PoisonError::into_inner).take()
+}
+
+/// Releases the locks of timed-out operations when dropped
+///
+/// Only for the end of a command: exiting stops those operations, so nothing
+/// writes to the repository after the lock is gone.
+struct ReleaseAbandonedLocks;
+
+impl Drop for ReleaseAbandonedLocks {
+    fn drop(&mut self) {
+        for slot in ABANDONED_LOCKS.lock().unwrap_or_else(PoisonError::into_inner).drain(..) {
+            take_lock(&slot);
+        }
+    }
+}
+
+/// Whether debounced auto-sync may pull and push again
+fn debounce_elapsed(repo: &GitSync, config: &AutoSyncConfig) -> bool {
+    let interval = Duration::from_secs(config.debounce_minutes * 60);
+    repo.last_sync_time()
+        .is_none_or(|last| last.elapsed().unwrap_or_default() >= interval)
+}
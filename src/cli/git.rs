@@ -1,27 +1,57 @@
 use anyhow::{anyhow, Context, Result};
-use chrono::Utc;
-use std::path::PathBuf;
-use std::process::Command;
+use chrono::{DateTime, Utc};
+use git2::{
+    Branch, Commit, ConfigLevel, Delta, Diff, Oid, Repository, RepositoryState, Sort, StatusOptions,
+};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// First and last commit times of committed files, by path
+pub type FileDates = HashMap<PathBuf, (DateTime<Utc>, DateTime<Utc>)>;
 
 pub struct GitRepo {
     notes_dir: PathBuf,
 }
 
+/// Uncommitted changes in the working tree, grouped by kind
+#[derive(Default)]
+pub struct WorkingChanges {
+    pub added: Vec<String>,
+    pub modified: Vec<String>,
+    pub deleted: Vec<String>,
+    /// (old path, new path)
+    pub renamed: Vec<(String, String)>,
+}
+
+impl WorkingChanges {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.modified.is_empty() && self.deleted.is_empty() && self.renamed.is_empty()
+    }
+}
+
+/// A commit that touched a file
+pub struct FileCommit {
+    pub hash: String,
+    pub short_hash: String,
+    pub date: DateTime<Utc>,
+    pub author: String,
+    pub subject: String,
+    /// Path of the file as of this commit (differs from the current path after renames)
+    pub path: String,
+}
+
 impl GitRepo {
     pub fn new(notes_dir: PathBuf) -> Result<Self> {
         Ok(Self { notes_dir })
     }
 
+    fn repo(&self) -> Result<Repository> {
+        Repository::open(&self.notes_dir).context("Failed to open git repository")
+    }
+
     /// Check if the notes directory is a git repository
     pub fn check_is_repo(&self) -> Result<()> {
-        let output = Command::new("git")
-            .arg("rev-parse")
-            .arg("--git-dir")
-            .current_dir(&self.notes_dir)
-            .output()
-            .context("Failed to execute git command")?;
-
-        if !output.status.success() {
+        if Repository::open(&self.notes_dir).is_err() {
             return Err(anyhow!(
                 "Error: Not a git repository\n\
                 The notes directory is not initialized with git.\n\n\
@@ -32,282 +62,365 @@ impl GitRepo {
         Ok(())
     }
 
-    /// Check if a remote repository is configured
-    pub fn check_has_remote(&self) -> Result<()> {
-        let output = Command::new("git")
-            .arg("remote")
-            .current_dir(&self.notes_dir)
-            .output()
-            .context("Failed to execute git command")?;
-
-        if !output.status.success() {
-            return Err(anyhow!("Failed to check git remote"));
-        }
+    /// Get list of files with conflicts
+    pub fn get_conflicted_files(&self) -> Result<Vec<String>> {
+        let index = self.repo()?.index().context("Failed to get conflicted files")?;
 
-        let remotes = String::from_utf8_lossy(&output.stdout);
-        if remotes.trim().is_empty() {
-            return Err(anyhow!(
-                "Error: No remote repository configured\n\
-                Run 'git remote add origin <url>' to configure a remote."
-            ));
+        let mut files = Vec::new();
+        for conflict in index.conflicts()? {
+            let conflict = conflict?;
+            if let Some(entry) = conflict.our.or(conflict.their).or(conflict.ancestor) {
+                files.push(String::from_utf8_lossy(&entry.path).into_owned());
+            }
         }
 
-        Ok(())
+        Ok(files)
     }
 
-    /// Check if there are uncommitted changes
-    pub fn has_uncommitted_changes(&self) -> Result<bool> {
-        let output = Command::new("git")
-            .arg("status")
-            .arg("--porcelain")
-            .current_dir(&self.notes_dir)
-            .output()
-            .context("Failed to execute git status")?;
-
-        if !output.status.success() {
-            return Err(anyhow!("Failed to check git status"));
-        }
+    /// Get files that were added or modified since the last commit (including untracked)
+    pub fn get_changed_files(&self) -> Result<Vec<String>> {
+        let changes = self.get_working_changes()?;
 
-        Ok(!output.stdout.is_empty())
+        Ok(changes
+            .added
+            .into_iter()
+            .chain(changes.modified)
+            .chain(changes.renamed.into_iter().map(|(_, to)| to))
+            .collect())
     }
 
-    /// Get list of files with conflicts
-    pub fn get_conflicted_files(&self) -> Result<Vec<String>> {
-        let output = Command::new("git")
-            .arg("diff")
-            .arg("--name-only")
-            .arg("--diff-filter=U")
-            .current_dir(&self.notes_dir)
-            .output()
-            .context("Failed to get conflicted files")?;
-
-        if !output.status.success() {
-            return Err(anyhow!("Failed to get conflicted files"));
+    /// Get the content of a file as of the last commit, or None if it isn't committed
+    pub fn show_head(&self, path: &str) -> Result<Option<String>> {
+        self.show_file("HEAD", path)
+    }
+
+    /// Get the first and last commit times of every file in HEAD, following renames
+    ///
+    /// Walks the history once, oldest commit first, so asking for many files
+    /// costs no more than asking for one.
+    pub fn get_file_dates(&self) -> Result<FileDates> {
+        let repo = self.repo()?;
+        let mut dates = FileDates::new();
+        if repo.head().is_err() {
+            // Nothing committed yet
+            return Ok(dates);
         }
 
-        let files: Vec<String> = String::from_utf8_lossy(&output.stdout)
-            .lines()
-            .map(|s| s.to_string())
-            .collect();
+        let mut revwalk = repo.revwalk()?;
+        revwalk.push_head()?;
+        revwalk.set_sorting(Sort::TOPOLOGICAL | Sort::REVERSE)?;
+
+        for oid in revwalk {
+            let commit = repo.find_commit(oid?)?;
+            let date = commit_date(&commit);
+            let mut diff = diff_to_first_parent(&repo, &commit)?;
+            diff.find_similar(None)?;
+
+            for delta in diff.deltas() {
+                let (Some(old_path), Some(path)) = (delta.old_file().path(), delta.new_file().path()) else {
+                    continue;
+                };
+                let known = match delta.status() {
+                    Delta::Deleted => {
+                        dates.remove(old_path);
+                        continue;
+                    }
+                    Delta::Renamed => dates.remove(old_path),
+                    _ => dates.get(path).copied(),
+                };
+                let (first, last) = known.unwrap_or((date, date));
+                dates.insert(path.to_path_buf(), (first.min(date), last.max(date)));
+            }
+        }
 
-        Ok(files)
+        Ok(dates)
     }
 
-    /// Stage all changes
-    pub fn stage_all(&self) -> Result<()> {
-        let output = Command::new("git")
-            .arg("add")
-            .arg(".")
-            .current_dir(&self.notes_dir)
-            .output()
-            .context("Failed to stage changes")?;
-
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(anyhow!("Failed to stage changes: {}", stderr));
+    /// List the commits that touched a file, newest first, following renames
+    pub fn get_file_history(&self, path: &str) -> Result<Vec<FileCommit>> {
+        let repo = self.repo()?;
+        let mut commits = Vec::new();
+        if repo.head().is_err() {
+            return Ok(commits);
         }
 
-        Ok(())
-    }
+        let mut revwalk = repo.revwalk()?;
+        revwalk.push_head()?;
+        revwalk.set_sorting(Sort::TOPOLOGICAL | Sort::TIME)?;
 
-    /// Create a commit with the given message
-    pub fn commit(&self, message: &str) -> Result<()> {
-        let output = Command::new("git")
-            .arg("commit")
-            .arg("-m")
-            .arg(message)
-            .current_dir(&self.notes_dir)
-            .output()
-            .context("Failed to commit changes")?;
-
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(anyhow!("Failed to commit changes: {}", stderr));
-        }
+        let blob_at = |commit: &Commit, path: &str| -> Result<Option<Oid>> {
+            Ok(commit.tree()?.get_path(Path::new(path)).ok().map(|entry| entry.id()))
+        };
 
-        Ok(())
-    }
+        let mut current = path.to_string();
+        for oid in revwalk {
+            let commit = repo.find_commit(oid?)?;
+            let Some(blob) = blob_at(&commit, &current)? else {
+                continue;
+            };
 
-    /// Pull changes from remote with merge strategy
-    pub fn pull(&self) -> Result<()> {
-        let output = Command::new("git")
-            .arg("pull")
-            .arg("--no-rebase")
-            .current_dir(&self.notes_dir)
-            .output()
-            .context("Failed to pull changes")?;
-
-        if !output.status.success() {
-            // Check if there are merge conflicts
-            let conflicted_files = self.get_conflicted_files()?;
-            if !conflicted_files.is_empty() {
-                let files_list = conflicted_files
-                    .iter()
-                    .map(|f| format!("  - {}", f))
-                    .collect::<Vec<_>>()
-                    .join("\n");
-
-                return Err(anyhow!(
-                    "Error: Merge conflicts detected\n\n\
-                    The following files have conflicts:\n\
-                    {}\n\n\
-                    Resolve conflicts manually and run 'git merge --continue'",
-                    files_list
-                ));
+            // Like `git log`, skip commits that left the file as one of their parents had it
+            let mut unchanged = false;
+            for parent in commit.parents() {
+                unchanged |= blob_at(&parent, &current)? == Some(blob);
+            }
+            if unchanged {
+                continue;
             }
 
-            // Some other error
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(anyhow!("Failed to pull changes: {}", stderr));
+            commits.push(FileCommit {
+                hash: commit.id().to_string(),
+                short_hash: commit.as_object().short_id()?.as_str().unwrap_or_default().to_string(),
+                date: commit_date(&commit),
+                author: commit.author().name().unwrap_or_default().to_string(),
+                subject: commit.summary().unwrap_or_default().to_string(),
+                path: current.clone(),
+            });
+
+            // Added here: keep following it under its old name if it was renamed
+            if let Some(parent) = commit.parents().next()
+                && blob_at(&parent, &current)?.is_none()
+            {
+                let mut diff = diff_to_first_parent(&repo, &commit)?;
+                diff.find_similar(None)?;
+                let renamed_from = diff.deltas().find_map(|delta| {
+                    (delta.status() == Delta::Renamed && delta.new_file().path() == Some(Path::new(&current)))
+                        .then(|| delta.old_file().path().map(|p| p.to_string_lossy().into_owned()))
+                        .flatten()
+                });
+                match renamed_from {
+                    Some(old_path) => current = old_path,
+                    None => break,
+                }
+            }
         }
 
-        Ok(())
+        Ok(commits)
     }
 
-    /// Push changes to remote
-    pub fn push(&self) -> Result<()> {
-        let output = Command::new("git")
-            .arg("push")
-            .current_dir(&self.notes_dir)
-            .output()
-            .context("Failed to push changes")?;
-
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(anyhow!("Failed to push changes: {}", stderr));
-        }
+    /// Resolve a revision (hash, branch, HEAD~2, ...) to a full commit hash
+    pub fn resolve_commit(&self, rev: &str) -> Result<String> {
+        let repo = self.repo()?;
+        let commit = repo
+            .revparse_single(rev)
+            .and_then(|object| object.peel_to_commit())
+            .map_err(|_| anyhow!("Unknown revision: {}", rev))?;
 
-        Ok(())
+        Ok(commit.id().to_string())
     }
 
-    /// Stash uncommitted changes with a timestamped message
-    pub fn stash_push(&self, message: &str) -> Result<()> {
-        let output = Command::new("git")
-            .arg("stash")
-            .arg("push")
-            .arg("-m")
-            .arg(message)
-            .current_dir(&self.notes_dir)
-            .output()
-            .context("Failed to stash changes")?;
-
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(anyhow!("Failed to stash changes: {}", stderr));
-        }
+    /// Check whether `ancestor` is `commit` or one of its ancestors
+    pub fn is_ancestor(&self, ancestor: &str, commit: &str) -> Result<bool> {
+        let repo = self.repo()?;
+        let ancestor = Oid::from_str(ancestor)?;
+        let commit = Oid::from_str(commit)?;
 
-        Ok(())
+        Ok(ancestor == commit || repo.graph_descendant_of(commit, ancestor)?)
     }
 
-    /// Pop the most recent stash
-    pub fn stash_pop(&self) -> Result<()> {
-        let output = Command::new("git")
-            .arg("stash")
-            .arg("pop")
-            .current_dir(&self.notes_dir)
-            .output()
-            .context("Failed to pop stash")?;
-
-        if !output.status.success() {
-            // Check if there are conflicts
-            let conflicted_files = self.get_conflicted_files()?;
-            if !conflicted_files.is_empty() {
-                let files_list = conflicted_files
-                    .iter()
-                    .map(|f| format!("  - {}", f))
-                    .collect::<Vec<_>>()
-                    .join("\n");
-
-                eprintln!(
-                    "Warning: Conflicts occurred while reapplying stashed changes\n\n\
-                    The following files have conflicts:\n\
-                    {}\n\n\
-                    The stash has been applied but conflicts need resolution.\n\
-                    Run 'git status' to see details.\n\
-                    Your stashed changes are preserved in the stash list.",
-                    files_list
-                );
-
-                // Return Ok because this is a warning, not a fatal error
-                return Ok(());
-            }
+    /// Get the content of a file at a revision, or None if it doesn't exist there
+    pub fn show_file(&self, rev: &str, path: &str) -> Result<Option<String>> {
+        let repo = self.repo()?;
+        let Ok(tree) = repo.revparse_single(rev).and_then(|object| object.peel_to_tree()) else {
+            return Ok(None);
+        };
+        let Ok(entry) = tree.get_path(Path::new(path)) else {
+            return Ok(None);
+        };
+        let blob = repo.find_blob(entry.id())?;
+
+        Ok(Some(String::from_utf8_lossy(blob.content()).into_owned()))
+    }
 
-            // Some other error
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(anyhow!("Failed to pop stash: {}", stderr));
+    /// Get uncommitted changes (staged, unstaged and untracked) from git status
+    pub fn get_working_changes(&self) -> Result<WorkingChanges> {
+        let repo = self.repo()?;
+        let mut options = StatusOptions::new();
+        options
+            .include_untracked(true)
+            .recurse_untracked_dirs(true)
+            .renames_head_to_index(true);
+        let statuses = repo.statuses(Some(&mut options)).context("Failed to get git status")?;
+
+        let mut changes = WorkingChanges::default();
+        for entry in statuses.iter() {
+            let status = entry.status();
+            let Some(path) = entry.path() else { continue };
+            let path = path.to_string();
+
+            if status.is_index_renamed()
+                && let Some(delta) = entry.head_to_index()
+                && let (Some(from), Some(to)) = (delta.old_file().path(), delta.new_file().path())
+            {
+                changes.renamed.push((from.to_string_lossy().into_owned(), to.to_string_lossy().into_owned()));
+            } else if status.is_index_deleted() || status.is_wt_deleted() {
+                changes.deleted.push(path);
+            } else if status.is_wt_new() || status.is_index_new() {
+                changes.added.push(path);
+            } else if status.is_index_modified()
+                || status.is_wt_modified()
+                || status.is_index_typechange()
+                || status.is_wt_typechange()
+            {
+                changes.modified.push(path);
+            }
         }
 
-        Ok(())
+        Ok(changes)
     }
 
-    /// Generate a summary of changes from git status
-    pub fn generate_change_summary(&self) -> Result<String> {
-        let output = Command::new("git")
-            .arg("status")
-            .arg("--porcelain")
-            .current_dir(&self.notes_dir)
-            .output()
-            .context("Failed to get git status")?;
-
-        if !output.status.success() {
-            return Err(anyhow!("Failed to get git status"));
-        }
-
-        let status_output = String::from_utf8_lossy(&output.stdout);
-        let mut modified = Vec::new();
-        let mut added = Vec::new();
-        let mut deleted = Vec::new();
+    /// Get the current branch name, or None if HEAD is detached
+    pub fn get_branch(&self) -> Result<Option<String>> {
+        let repo = self.repo()?;
+        // HEAD of a repository with no commits yet still names a branch
+        let head = repo.find_reference("HEAD")?;
+        Ok(head
+            .symbolic_target()
+            .and_then(|target| target.strip_prefix("refs/heads/"))
+            .map(str::to_string))
+    }
 
-        for line in status_output.lines() {
-            if line.len() < 3 {
-                continue;
-            }
+    /// Get the upstream branch and how many commits HEAD is (ahead, behind) it
+    ///
+    /// Uses the last fetched state of the remote. Returns None if there's no upstream.
+    pub fn get_upstream_status(&self) -> Result<Option<(String, usize, usize)>> {
+        let repo = self.repo()?;
+        let Ok(head) = repo.head() else {
+            return Ok(None);
+        };
+        if !head.is_branch() {
+            return Ok(None);
+        }
+        let Ok(upstream) = Branch::wrap(head).upstream() else {
+            return Ok(None);
+        };
+
+        let name = upstream.name()?.unwrap_or_default().to_string();
+        let (Some(local), Some(remote)) = (repo.head()?.target(), upstream.get().target()) else {
+            return Ok(None);
+        };
+        let (ahead, behind) = repo
+            .graph_ahead_behind(local, remote)
+            .context("Failed to compare with upstream")?;
+
+        Ok(Some((name, ahead, behind)))
+    }
 
-            let status = &line[..2];
-            let filename = &line[3..];
+    /// Count stash entries
+    pub fn get_stash_count(&self) -> Result<usize> {
+        let mut repo = self.repo()?;
+        let mut count = 0;
+        repo.stash_foreach(|_, _, _| {
+            count += 1;
+            true
+        })
+        .context("Failed to list stashes")?;
+
+        Ok(count)
+    }
 
-            match status {
-                "M " | " M" | "MM" => modified.push(filename.to_string()),
-                "A " | "??" => added.push(filename.to_string()),
-                "D " | " D" => deleted.push(filename.to_string()),
-                _ => {}
-            }
-        }
+    /// Check whether a merge is in progress
+    pub fn is_merging(&self) -> Result<bool> {
+        Ok(self.repo()?.state() == RepositoryState::Merge)
+    }
 
-        let mut summary = Vec::new();
+    /// Set a git config value in the repository's local config
+    pub fn set_config(&self, key: &str, value: &str) -> Result<()> {
+        let mut config = self.repo()?.config()?.open_level(ConfigLevel::Local)?;
+        config
+            .set_str(key, value)
+            .with_context(|| format!("Failed to set {}", key))
+    }
 
-        if !modified.is_empty() {
-            summary.push("Modified:".to_string());
-            for file in modified {
-                summary.push(format!("- {}", file));
-            }
-        }
+    /// Get a timestamp for commit messages
+    pub fn get_timestamp() -> String {
+        Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string()
+    }
+}
 
-        if !added.is_empty() {
-            if !summary.is_empty() {
-                summary.push(String::new());
-            }
-            summary.push("Added:".to_string());
-            for file in added {
-                summary.push(format!("- {}", file));
-            }
-        }
+/// Author time of a commit, as `git log` shows it
+fn commit_date(commit: &Commit) -> DateTime<Utc> {
+    DateTime::from_timestamp(commit.author().when().seconds(), 0).unwrap_or_default()
+}
 
-        if !deleted.is_empty() {
-            if !summary.is_empty() {
-                summary.push(String::new());
-            }
-            summary.push("Deleted:".to_string());
-            for file in deleted {
-                summary.push(format!("- {}", file));
-            }
-        }
+/// Changes a commit made on top of its first parent (everything, for a root commit)
+fn diff_to_first_parent<'r>(repo: &'r Repository, commit: &Commit) -> Result<Diff<'r>> {
+    let parent_tree = match commit.parents().next() {
+        Some(parent) => Some(parent.tree()?),
+        None => None,
+    };
+    Ok(repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), None)?)
+}
 
-        Ok(summary.join("\n"))
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use git2::{IndexAddOption, Signature};
+
+    fn commit_all(repo: &Repository, message: &str) {
+        let mut index = repo.index().unwrap();
+        index.add_all(["*"], IndexAddOption::DEFAULT, None).unwrap();
+        index.update_all(["*"], None).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = Signature::now("Test", "test@example.com").unwrap();
+        let parent = repo.head().ok().map(|head| head.peel_to_commit().unwrap());
+        let parents: Vec<&Commit> = parent.iter().collect();
+        repo.commit(Some("HEAD"), &signature, &signature, message, &tree, &parents).unwrap();
     }
 
-    /// Get a timestamp for commit messages and stash names
-    pub fn get_timestamp() -> String {
-        Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string()
+    #[test]
+    fn test_working_changes_and_history() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        let write = |path: &str, content: &str| std::fs::write(dir.path().join(path), content).unwrap();
+
+        write("my note.md", "# My note\n");
+        write("gone.md", "# Gone\n");
+        write("old.md", "# Moving\n\nA note that keeps its content when renamed.\n");
+        commit_all(&repo, "first");
+        write("my note.md", "# My note\n\nMore\n");
+        commit_all(&repo, "second");
+
+        let git_repo = GitRepo::new(dir.path().to_path_buf()).unwrap();
+        assert!(git_repo.get_working_changes().unwrap().is_empty());
+
+        write("my note.md", "# My note\n\nEven more\n");
+        write("caf\u{e9}.md", "# Caf\u{e9}\n");
+        std::fs::remove_file(dir.path().join("gone.md")).unwrap();
+        std::fs::rename(dir.path().join("old.md"), dir.path().join("new name.md")).unwrap();
+        let mut index = repo.index().unwrap();
+        index.remove_path(Path::new("old.md")).unwrap();
+        index.add_path(Path::new("new name.md")).unwrap();
+        index.write().unwrap();
+
+        let changes = git_repo.get_working_changes().unwrap();
+        assert_eq!(changes.modified, vec!["my note.md"]);
+        assert_eq!(changes.added, vec!["caf\u{e9}.md"]);
+        assert_eq!(changes.renamed, vec![("old.md".to_string(), "new name.md".to_string())]);
+        assert_eq!(changes.deleted, vec!["gone.md"]);
+
+        commit_all(&repo, "rename");
+        let history = git_repo.get_file_history("new name.md").unwrap();
+        let subjects: Vec<(&str, &str)> = history.iter().map(|c| (c.subject.as_str(), c.path.as_str())).collect();
+        assert_eq!(subjects, vec![("rename", "new name.md"), ("first", "old.md")]);
+        assert_eq!(git_repo.get_file_history("my note.md").unwrap().len(), 3);
+
+        let first = &history[1].hash;
+        assert!(git_repo.is_ancestor(first, &git_repo.resolve_commit("HEAD").unwrap()).unwrap());
+        let content = git_repo.show_file(first, "old.md").unwrap().unwrap();
+        assert!(content.starts_with("# Moving\n"));
+        assert_eq!(git_repo.show_file(first, "new name.md").unwrap(), None);
+
+        let dates = git_repo.get_file_dates().unwrap();
+        assert_eq!(dates[Path::new("new name.md")].0, history[1].date);
+        assert!(!dates.contains_key(Path::new("gone.md")));
+        assert_eq!(git_repo.get_branch().unwrap().as_deref(), repo.head().unwrap().shorthand());
+        assert_eq!(git_repo.get_upstream_status().unwrap(), None);
+        assert!(!git_repo.is_merging().unwrap());
     }
 }
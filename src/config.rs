@@ -5,8 +5,12 @@
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 
+use crate::lint::{Linter, RuleLevel};
+use crate::note::TaskVocabulary;
+use crate::schema::NoteType;
 use crate::storage::Storage;
 
 /// Library configuration loaded from the notes directory
@@ -21,6 +25,106 @@ pub struct LibraryConfig {
     pub periodic: PeriodicConfig,
     #[serde(default = "default_auto_update_timestamp")]
     pub auto_update_timestamp: bool,
+    /// Note-type schemas keyed by type name (see [`crate::schema`])
+    #[serde(default)]
+    pub types: BTreeMap<String, NoteType>,
+    #[serde(default)]
+    pub auto_sync: AutoSyncConfig,
+    /// Lint rule levels keyed by rule id (see [`crate::lint`])
+    #[serde(default)]
+    pub lint: BTreeMap<String, RuleLevel>,
+    /// Task checkbox markers keyed by the marker character, added to the defaults
+    #[serde(default)]
+    pub task_statuses: BTreeMap<String, TaskStatusConfig>,
+    /// Weights for the computed task score (see [`crate::score`])
+    #[serde(default)]
+    pub task_score: TaskScoreConfig,
+}
+
+/// A task checkbox marker, e.g. `"-" = { name = "cancelled", closed = true }`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaskStatusConfig {
+    pub name: String,
+    /// Whether tasks with this marker are finished (not listed as open or migrated)
+    #[serde(default)]
+    pub closed: bool,
+    /// Color in `task list`: a terminal color name like "green", or "dim"
+    #[serde(default)]
+    pub color: Option<String>,
+}
+
+/// Coefficients for each part of a task's score
+///
+/// Each part is a factor between 0 and 1 multiplied by its coefficient;
+/// tag coefficients are added for every tag the task has (hierarchically,
+/// so `work` also applies to `@work/meetings`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TaskScoreConfig {
+    /// `!!!`, `!!` and `!` markers
+    pub urgency: f64,
+    /// `(A)`, `(B)` and `(C)` priorities
+    pub priority: f64,
+    /// How close the `due:` date is, or how far past it
+    pub due: f64,
+    /// Age of the note the task is in, up to a year
+    pub age: f64,
+    /// Waiting on unfinished tasks
+    pub blocked: f64,
+    pub tags: BTreeMap<String, f64>,
+}
+
+impl Default for TaskScoreConfig {
+    fn default() -> Self {
+        Self {
+            urgency: 6.0,
+            priority: 4.0,
+            due: 12.0,
+            age: 2.0,
+            blocked: -5.0,
+            tags: BTreeMap::new(),
+        }
+    }
+}
+
+/// When to sync with git automatically after editing a note
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AutoSyncMode {
+    /// Only sync when running `bnotes sync`
+    #[default]
+    Off,
+    /// Commit, pull and push every time the editor exits
+    OnExitFromEditor,
+    /// Commit every time the editor exits, pull and push at most every `debounce_minutes`
+    Debounced,
+}
+
+/// Configuration for automatic git sync
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutoSyncConfig {
+    #[serde(default)]
+    pub mode: AutoSyncMode,
+    /// Pull before opening a note in the editor
+    #[serde(default)]
+    pub pull_before_open: bool,
+    /// Minimum time between pushes in debounced mode
+    #[serde(default = "default_debounce_minutes")]
+    pub debounce_minutes: u64,
+    /// How long a pull before opening a note or a put-off sync waits for the remote
+    #[serde(default = "default_network_timeout_seconds")]
+    pub network_timeout_seconds: u64,
+}
+
+impl Default for AutoSyncConfig {
+    fn default() -> Self {
+        Self {
+            mode: AutoSyncMode::default(),
+            pull_before_open: false,
+            debounce_minutes: default_debounce_minutes(),
+            network_timeout_seconds: default_network_timeout_seconds(),
+        }
+    }
 }
 
 /// Configuration for periodic notes
@@ -64,12 +168,25 @@ fn default_auto_update_timestamp() -> bool {
     true // Enabled by default
 }
 
+fn default_debounce_minutes() -> u64 {
+    15
+}
+
+fn default_network_timeout_seconds() -> u64 {
+    10
+}
+
 impl Default for LibraryConfig {
     fn default() -> Self {
         Self {
             template_dir: default_template_dir(),
             periodic: PeriodicConfig::default(),
             auto_update_timestamp: default_auto_update_timestamp(),
+            types: BTreeMap::new(),
+            auto_sync: AutoSyncConfig::default(),
+            lint: BTreeMap::new(),
+            task_statuses: BTreeMap::new(),
+            task_score: TaskScoreConfig::default(),
         }
     }
 }
@@ -81,13 +198,39 @@ impl LibraryConfig {
     pub fn load(storage: &dyn Storage) -> Result<Self> {
         if storage.exists(Path::new(".bnotes/config.toml")) {
             let content = storage.read_to_string(Path::new(".bnotes/config.toml"))?;
-            return toml::from_str(&content).context("Failed to parse .bnotes/config.toml");
+            let config: Self = toml::from_str(&content).context("Failed to parse .bnotes/config.toml")?;
+            if let Some(marker) = config.task_statuses.keys().find(|marker| marker.chars().count() != 1) {
+                anyhow::bail!("Invalid task status '{}' in .bnotes/config.toml: must be a single character", marker);
+            }
+            // A misspelled rule id would otherwise leave the rule at its default level
+            let linter = Linter::with_builtin_rules();
+            let known: Vec<&str> = linter.rules().map(|rule| rule.id()).collect();
+            if let Some(id) = config.lint.keys().find(|id| !known.contains(&id.as_str())) {
+                anyhow::bail!(
+                    "Unknown lint rule '{}' in .bnotes/config.toml (known rules: {})",
+                    id,
+                    known.join(", ")
+                );
+            }
+            return Ok(config);
         }
 
         anyhow::bail!("No library config found. Expected .bnotes/config.toml")
     }
 
-    /// Load config or return defaults if not found
+    /// Load config, or return defaults if there is no config file
+    ///
+    /// Unlike [`LibraryConfig::load_or_default`], a config file that exists
+    /// but can't be used is an error.
+    pub fn load_if_present(storage: &dyn Storage) -> Result<Self> {
+        if storage.exists(Path::new(".bnotes/config.toml")) {
+            Self::load(storage)
+        } else {
+            Ok(Self::default())
+        }
+    }
+
+    /// Load config or return defaults if not found or invalid
     pub fn load_or_default(storage: &dyn Storage) -> Self {
         Self::load(storage).unwrap_or_default()
     }
@@ -96,6 +239,18 @@ impl LibraryConfig {
     pub fn template_dir_path(&self) -> &Path {
         &self.template_dir
     }
+
+    /// The default task statuses with the configured ones added
+    pub fn task_vocabulary(&self) -> TaskVocabulary {
+        let mut vocabulary = TaskVocabulary::default();
+        for (marker, status) in &self.task_statuses {
+            let mut chars = marker.chars();
+            if let (Some(marker), None) = (chars.next(), chars.next()) {
+                vocabulary.insert(marker, &status.name, !status.closed, status.color.clone());
+            }
+        }
+        vocabulary
+    }
 }
 
 #[cfg(test)]
@@ -123,6 +278,115 @@ daily_template = "custom-daily.md"
         assert_eq!(config.periodic.daily_template, "custom-daily.md");
     }
 
+    #[test]
+    fn test_load_config_with_types() {
+        let storage = MemoryStorage::new();
+        storage
+            .write(
+                Path::new(".bnotes/config.toml"),
+                r#"
+[types.person]
+required = ["email"]
+fields.company = "string"
+
+[types.book]
+tag = "reading"
+fields.status = ["reading", "finished"]
+"#,
+            )
+            .unwrap();
+
+        let config = LibraryConfig::load(&storage).unwrap();
+        assert_eq!(config.types.len(), 2);
+        assert_eq!(config.types["person"].required, vec!["email"]);
+        assert_eq!(config.types["book"].tag.as_deref(), Some("reading"));
+    }
+
+    #[test]
+    fn test_load_config_with_auto_sync() {
+        let storage = MemoryStorage::new();
+        storage
+            .write(
+                Path::new(".bnotes/config.toml"),
+                r#"
+[auto_sync]
+mode = "debounced"
+pull_before_open = true
+"#,
+            )
+            .unwrap();
+
+        let config = LibraryConfig::load(&storage).unwrap();
+        assert_eq!(config.auto_sync.mode, AutoSyncMode::Debounced);
+        assert!(config.auto_sync.pull_before_open);
+        assert_eq!(config.auto_sync.debounce_minutes, 15);
+
+        assert_eq!(LibraryConfig::default().auto_sync.mode, AutoSyncMode::Off);
+    }
+
+    #[test]
+    fn test_load_config_with_lint_levels() {
+        let storage = MemoryStorage::new();
+        storage
+            .write(
+                Path::new(".bnotes/config.toml"),
+                "[lint]\norphan = \"off\"\nmissing-tags = \"error\"\n",
+            )
+            .unwrap();
+
+        let config = LibraryConfig::load(&storage).unwrap();
+        assert_eq!(config.lint.get("orphan"), Some(&RuleLevel::Off));
+        assert_eq!(config.lint.get("missing-tags"), Some(&RuleLevel::Error));
+
+        storage.write(Path::new(".bnotes/config.toml"), "[lint]\norphan = \"warn\"\n").unwrap();
+        let config = LibraryConfig::load(&storage).unwrap();
+        assert_eq!(config.lint.get("orphan"), Some(&RuleLevel::Warning));
+
+        // A typo must not silently leave the rule at its default level
+        storage.write(Path::new(".bnotes/config.toml"), "[lint]\norphans = \"off\"\n").unwrap();
+        let err = LibraryConfig::load(&storage).unwrap_err();
+        assert!(err.to_string().contains("Unknown lint rule 'orphans'"));
+    }
+
+    #[test]
+    fn test_load_config_with_task_statuses() {
+        let storage = MemoryStorage::new();
+        storage
+            .write(
+                Path::new(".bnotes/config.toml"),
+                "[task_statuses]\n\"!\" = { name = \"blocked\", color = \"red\" }\n\"-\" = { name = \"dropped\", closed = true }\n",
+            )
+            .unwrap();
+
+        let vocabulary = LibraryConfig::load(&storage).unwrap().task_vocabulary();
+        let blocked = vocabulary.status('!').unwrap();
+        assert!(blocked.is_incomplete());
+        assert_eq!(vocabulary.name(&blocked), "blocked");
+        assert_eq!(vocabulary.name(&vocabulary.status('-').unwrap()), "dropped");
+        assert_eq!(vocabulary.name(&vocabulary.status('x').unwrap()), "done");
+
+        storage
+            .write(Path::new(".bnotes/config.toml"), "[task_statuses]\nno = { name = \"bad\" }\n")
+            .unwrap();
+        assert!(LibraryConfig::load(&storage).is_err());
+    }
+
+    #[test]
+    fn test_load_config_with_task_score() {
+        let storage = MemoryStorage::new();
+        storage
+            .write(
+                Path::new(".bnotes/config.toml"),
+                "[task_score]\ndue = 20.0\n\n[task_score.tags]\nwork = 1.5\nsomeday = -3\n",
+            )
+            .unwrap();
+
+        let score = LibraryConfig::load(&storage).unwrap().task_score;
+        assert_eq!(score.due, 20.0);
+        assert_eq!(score.urgency, 6.0);
+        assert_eq!(score.tags.get("someday"), Some(&-3.0));
+    }
+
     #[test]
     fn test_load_or_default_with_no_config() {
         let storage = MemoryStorage::new();
@@ -133,4 +397,13 @@ daily_template = "custom-daily.md"
         assert_eq!(config.periodic.weekly_template, "weekly.md");
         assert_eq!(config.periodic.quarterly_template, "quarterly.md");
     }
+
+    #[test]
+    fn test_load_if_present() {
+        let storage = MemoryStorage::new();
+        assert_eq!(LibraryConfig::load_if_present(&storage).unwrap().template_dir, PathBuf::from(".bnotes/templates"));
+
+        storage.write(Path::new(".bnotes/config.toml"), "[lint]\norphan = \"sometimes\"\n").unwrap();
+        assert!(LibraryConfig::load_if_present(&storage).is_err());
+    }
 }
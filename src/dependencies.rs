@@ -0,0 +1,245 @@
+//! Task dependencies
+//!
+//! Tasks name what they wait on with `blocked-by:^t-4f2a` (a task ID) or
+//! `after:[[Note]]#2` (the second task in a note). A task is blocked while
+//! any of those tasks is still open, or can't be found at all so a typo
+//! doesn't let it slip through.
+
+use crate::note::{Task, TaskRef, TaskStatus};
+use std::collections::{HashMap, VecDeque};
+
+/// Something a task is waiting on
+#[derive(Debug, Clone)]
+pub struct Blocker<'a> {
+    pub reference: &'a TaskRef,
+    /// The task the reference points at, if it exists
+    pub task: Option<&'a Task>,
+}
+
+/// Dependencies between every task in the notes
+#[derive(Debug, Clone)]
+pub struct DependencyGraph {
+    tasks: Vec<Task>,
+    by_id: HashMap<String, Vec<usize>>,
+    by_position: HashMap<(String, usize), usize>,
+}
+
+impl DependencyGraph {
+    /// Build the graph from all tasks, so references into any note resolve
+    pub fn build(tasks: Vec<Task>) -> Self {
+        let mut by_id: HashMap<String, Vec<usize>> = HashMap::new();
+        let mut by_position = HashMap::new();
+        for (i, task) in tasks.iter().enumerate() {
+            by_id.entry(task.id.clone()).or_default().push(i);
+            by_position.insert((task.note_title.to_lowercase(), task.index), i);
+        }
+        Self { tasks, by_id, by_position }
+    }
+
+    pub fn tasks(&self) -> &[Task] {
+        &self.tasks
+    }
+
+    /// The task a reference points at
+    pub fn resolve(&self, reference: &TaskRef) -> Option<&Task> {
+        self.resolve_index(reference).map(|i| &self.tasks[i])
+    }
+
+    /// Index of the task a reference points at
+    ///
+    /// An anchored task that was migrated exists twice; the copy that is
+    /// still live wins.
+    fn resolve_index(&self, reference: &TaskRef) -> Option<usize> {
+        match reference {
+            TaskRef::Id(id) => {
+                let candidates = self.by_id.get(id.trim_start_matches('^'))?;
+                candidates
+                    .iter()
+                    .copied()
+                    .find(|&i| self.tasks[i].status != TaskStatus::Migrated)
+                    .or_else(|| candidates.first().copied())
+            }
+            TaskRef::Position { note, index } => self.by_position.get(&(note.to_lowercase(), *index)).copied(),
+        }
+    }
+
+    /// Dependencies of a task that aren't finished yet
+    pub fn blockers<'a>(&'a self, task: &'a Task) -> Vec<Blocker<'a>> {
+        task.dependencies
+            .iter()
+            .map(|reference| Blocker { reference, task: self.resolve(reference) })
+            .filter(|blocker| blocker.task.is_none_or(|t| t.status.is_incomplete()))
+            .collect()
+    }
+
+    /// Whether an open task has dependencies that aren't finished
+    pub fn is_blocked(&self, task: &Task) -> bool {
+        task.status.is_incomplete() && !self.blockers(task).is_empty()
+    }
+
+    /// Every dependency cycle, each as the tasks along it in order
+    ///
+    /// Tasks in a cycle can never become unblocked. Each strongly connected
+    /// group of tasks is reported once, starting from its first task.
+    pub fn cycles(&self) -> Vec<Vec<&Task>> {
+        let edges: Vec<Vec<usize>> = self
+            .tasks
+            .iter()
+            .map(|task| task.dependencies.iter().filter_map(|r| self.resolve_index(r)).collect())
+            .collect();
+
+        let mut cycles = Vec::new();
+        for component in strongly_connected(&edges) {
+            let start = component[0];
+            let is_cycle = component.len() > 1 || edges[start].contains(&start);
+            if !is_cycle {
+                continue;
+            }
+            let path = cycle_path(&edges, &component, start);
+            cycles.push(path.into_iter().map(|i| &self.tasks[i]).collect());
+        }
+        cycles
+    }
+}
+
+/// Strongly connected components (Tarjan), each sorted, in order of their first node
+fn strongly_connected(edges: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    struct State {
+        next: usize,
+        index: Vec<Option<usize>>,
+        low: Vec<usize>,
+        stack: Vec<usize>,
+        on_stack: Vec<bool>,
+        components: Vec<Vec<usize>>,
+    }
+
+    fn visit(node: usize, edges: &[Vec<usize>], state: &mut State) {
+        state.index[node] = Some(state.next);
+        state.low[node] = state.next;
+        state.next += 1;
+        state.stack.push(node);
+        state.on_stack[node] = true;
+
+        for &next in &edges[node] {
+            match state.index[next] {
+                None => {
+                    visit(next, edges, state);
+                    state.low[node] = state.low[node].min(state.low[next]);
+                }
+                Some(index) if state.on_stack[next] => state.low[node] = state.low[node].min(index),
+                Some(_) => {}
+            }
+        }
+
+        if Some(state.low[node]) == state.index[node] {
+            let mut component = Vec::new();
+            while let Some(member) = state.stack.pop() {
+                state.on_stack[member] = false;
+                component.push(member);
+                if member == node {
+                    break;
+                }
+            }
+            component.sort_unstable();
+            state.components.push(component);
+        }
+    }
+
+    let mut state = State {
+        next: 0,
+        index: vec![None; edges.len()],
+        low: vec![0; edges.len()],
+        stack: Vec::new(),
+        on_stack: vec![false; edges.len()],
+        components: Vec::new(),
+    };
+    for node in 0..edges.len() {
+        if state.index[node].is_none() {
+            visit(node, edges, &mut state);
+        }
+    }
+
+    state.components.sort_by_key(|component| component[0]);
+    state.components
+}
+
+/// Shortest path from `start` back to itself within one component
+fn cycle_path(edges: &[Vec<usize>], component: &[usize], start: usize) -> Vec<usize> {
+    let mut previous: HashMap<usize, usize> = HashMap::new();
+    let mut queue = VecDeque::from([start]);
+
+    while let Some(node) = queue.pop_front() {
+        for &next in &edges[node] {
+            if next == start {
+                let mut path = vec![node];
+                while let Some(&before) = previous.get(path.last().unwrap()) {
+                    path.push(before);
+                }
+                path.reverse();
+                return path;
+            }
+            if component.binary_search(&next).is_ok() && next != start && !previous.contains_key(&next) {
+                previous.insert(next, node);
+                queue.push_back(next);
+            }
+        }
+    }
+
+    vec![start]
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::note::Note;
+    use std::path::Path;
+
+    fn tasks(notes: &[(&str, &str)]) -> Vec<Task> {
+        notes
+            .iter()
+            .flat_map(|(path, content)| Task::extract_from_note(&Note::parse(Path::new(path), content).unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn test_blockers() {
+        let graph = DependencyGraph::build(tasks(&[
+            ("plan.md", "# Plan\n\n- [x] Spec ^t-spec\n- [ ] Build blocked-by:^t-spec\n- [ ] Ship after:[[Plan]]#2\n"),
+            ("ops.md", "# Ops\n\n- [ ] Announce blocked-by:^t-gone\n"),
+        ]));
+        let [spec, build, ship, announce] = graph.tasks() else { panic!("expected four tasks") };
+
+        assert!(!graph.is_blocked(spec));
+        assert!(!graph.is_blocked(build));
+
+        let blockers = graph.blockers(ship);
+        assert_eq!(blockers.len(), 1);
+        assert_eq!(blockers[0].task.map(|t| t.text.as_str()), Some("Build"));
+
+        // Missing tasks block too
+        let blockers = graph.blockers(announce);
+        assert_eq!(blockers[0].reference.to_string(), "^t-gone");
+        assert!(blockers[0].task.is_none());
+
+        assert!(graph.cycles().is_empty());
+    }
+
+    #[test]
+    fn test_cycles() {
+        let graph = DependencyGraph::build(tasks(&[(
+            "plan.md",
+            "# Plan\n\n- [ ] A blocked-by:^t-b ^t-a\n- [ ] B after:[[Plan]]#3 ^t-b\n- [ ] C blocked-by:t-a\n- [ ] D blocked-by:^t-d ^t-d\n- [ ] E blocked-by:^t-a\n",
+        )]));
+
+        let cycles: Vec<Vec<&str>> = graph
+            .cycles()
+            .iter()
+            .map(|cycle| cycle.iter().map(|t| t.text.as_str()).collect())
+            .collect();
+        assert_eq!(cycles, vec![vec!["A", "B", "C"], vec!["D"]]);
+    }
+}
@@ -0,0 +1,532 @@
+//! In-place frontmatter editing
+//!
+//! Round-tripping frontmatter through serde reorders keys, drops comments and
+//! changes quoting, which makes every save show up as a noisy diff. The
+//! [`FrontmatterEditor`] instead patches individual top-level keys in the
+//! original text and leaves every other line (including comments, key order,
+//! flow/block style and line endings) exactly as it was.
+
+use anyhow::{Context, Result};
+use serde_yaml::Value;
+
+/// Editor for the YAML frontmatter block of a markdown document
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrontmatterEditor {
+    /// Line ending used by the document ("\n" or "\r\n")
+    newline: &'static str,
+    /// Frontmatter lines between the delimiters, without line endings
+    lines: Option<Vec<String>>,
+    /// Closing delimiter line as written ("---" or "...")
+    closing: String,
+    /// Everything after the closing delimiter, verbatim
+    body: String,
+}
+
+/// Location of a top-level key within the frontmatter lines
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    /// Index of the `key:` line
+    start: usize,
+    /// One past the last continuation line
+    end: usize,
+}
+
+/// How a scalar value was quoted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum QuoteStyle {
+    Plain,
+    Single,
+    Double,
+}
+
+impl FrontmatterEditor {
+    /// Split a document into frontmatter and body
+    pub fn parse(content: &str) -> Self {
+        let newline = if content.contains("\r\n") { "\r\n" } else { "\n" };
+
+        let no_frontmatter = || Self {
+            newline,
+            lines: None,
+            closing: "---".to_string(),
+            body: content.to_string(),
+        };
+
+        let Some(first_line_end) = content.find('\n') else {
+            return no_frontmatter();
+        };
+        if content[..first_line_end].trim_end_matches('\r') != "---" {
+            return no_frontmatter();
+        }
+
+        let mut lines = Vec::new();
+        let mut pos = first_line_end + 1;
+
+        while pos < content.len() {
+            let (line, next) = match content[pos..].find('\n') {
+                Some(offset) => (&content[pos..pos + offset], pos + offset + 1),
+                None => (&content[pos..], content.len()),
+            };
+            let line = line.trim_end_matches('\r');
+
+            if line == "---" || line == "..." {
+                return Self {
+                    newline,
+                    lines: Some(lines),
+                    closing: line.to_string(),
+                    body: content[next..].to_string(),
+                };
+            }
+
+            lines.push(line.to_string());
+            pos = next;
+        }
+
+        // No closing delimiter: treat the whole thing as body
+        no_frontmatter()
+    }
+
+    /// Check whether the document has a frontmatter block
+    pub fn has_frontmatter(&self) -> bool {
+        self.lines.is_some()
+    }
+
+    /// Get the document body (everything after the frontmatter)
+    pub fn body(&self) -> &str {
+        &self.body
+    }
+
+    /// Replace the document body
+    pub fn set_body(&mut self, body: String) {
+        self.body = body;
+    }
+
+    /// Get the raw YAML text of the frontmatter
+    pub fn yaml(&self) -> String {
+        self.lines
+            .as_ref()
+            .map(|lines| lines.iter().map(|l| format!("{}\n", l)).collect())
+            .unwrap_or_default()
+    }
+
+    /// Top-level keys in document order
+    pub fn keys(&self) -> Vec<String> {
+        let Some(lines) = &self.lines else {
+            return Vec::new();
+        };
+
+        lines.iter().filter_map(|line| key_of_line(line)).collect()
+    }
+
+    /// Parse the value of a top-level key
+    pub fn get(&self, key: &str) -> Option<Value> {
+        let lines = self.lines.as_ref()?;
+        let entry = find_entry(lines, key)?;
+        let text = lines[entry.start..entry.end].join("\n");
+
+        let mapping: Value = serde_yaml::from_str(&text).ok()?;
+        mapping.get(key).cloned()
+    }
+
+    /// Set a top-level key, replacing its value in place or appending it
+    ///
+    /// Existing quoting is kept for scalars and existing flow/block style is
+    /// kept for lists. An inline comment after a scalar value is preserved.
+    pub fn set(&mut self, key: &str, value: &Value) -> Result<()> {
+        let existing = self.entry_lines(key);
+        let new_lines = render_entry(key, value, existing.as_deref())?;
+        self.set_entry_lines(key, new_lines);
+        Ok(())
+    }
+
+    /// Set a top-level key to a string value
+    pub fn set_str(&mut self, key: &str, value: &str) -> Result<()> {
+        self.set(key, &Value::String(value.to_string()))
+    }
+
+    /// Remove a top-level key; returns whether it existed
+    pub fn remove(&mut self, key: &str) -> bool {
+        let Some(lines) = self.lines.as_mut() else {
+            return false;
+        };
+
+        match find_entry(lines, key) {
+            Some(entry) => {
+                lines.drain(entry.start..entry.end);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Raw lines of a top-level key, including any continuation lines
+    pub fn entry_lines(&self, key: &str) -> Option<Vec<String>> {
+        let lines = self.lines.as_ref()?;
+        let entry = find_entry(lines, key)?;
+        Some(lines[entry.start..entry.end].to_vec())
+    }
+
+    /// Replace the raw lines of a top-level key, appending them if the key is missing
+    ///
+    /// This bypasses value formatting entirely, for callers that already have
+    /// the exact text they want (for example lines taken from another document).
+    pub fn set_entry_lines(&mut self, key: &str, new_lines: Vec<String>) {
+        let lines = self.lines.get_or_insert_with(Vec::new);
+
+        match find_entry(lines, key) {
+            Some(entry) => {
+                lines.splice(entry.start..entry.end, new_lines);
+            }
+            None => {
+                // Keep trailing blank/comment lines at the end of the block
+                let mut insert_at = lines.len();
+                while insert_at > 0 && is_blank_or_comment(&lines[insert_at - 1]) {
+                    insert_at -= 1;
+                }
+                lines.splice(insert_at..insert_at, new_lines);
+            }
+        }
+    }
+
+    /// Render the full document
+    pub fn render(&self) -> String {
+        let Some(lines) = &self.lines else {
+            return self.body.clone();
+        };
+
+        let mut out = String::new();
+        out.push_str("---");
+        out.push_str(self.newline);
+        for line in lines {
+            out.push_str(line);
+            out.push_str(self.newline);
+        }
+        out.push_str(&self.closing);
+        out.push_str(self.newline);
+        out.push_str(&self.body);
+        out
+    }
+}
+
+/// Get the key of a top-level `key: value` line
+fn key_of_line(line: &str) -> Option<String> {
+    if line.is_empty() || line.starts_with([' ', '\t', '#', '-']) {
+        return None;
+    }
+
+    let (key, rest) = line.split_once(':')?;
+    if !(rest.is_empty() || rest.starts_with([' ', '\t'])) {
+        return None;
+    }
+
+    let key = key.trim();
+    let key = key
+        .strip_prefix('"')
+        .and_then(|k| k.strip_suffix('"'))
+        .or_else(|| key.strip_prefix('\'').and_then(|k| k.strip_suffix('\'')))
+        .unwrap_or(key);
+
+    Some(key.to_string())
+}
+
+fn is_blank_or_comment(line: &str) -> bool {
+    let trimmed = line.trim();
+    trimmed.is_empty() || trimmed.starts_with('#')
+}
+
+/// Check if a line continues the value of the previous key
+fn is_continuation(line: &str) -> bool {
+    line.starts_with([' ', '\t']) || line == "-" || line.starts_with("- ")
+}
+
+/// Find the span of lines belonging to a top-level key
+fn find_entry(lines: &[String], key: &str) -> Option<Entry> {
+    let start = lines
+        .iter()
+        .position(|line| key_of_line(line).as_deref() == Some(key))?;
+
+    let mut end = start + 1;
+    let mut scan = start + 1;
+    while scan < lines.len() {
+        let line = &lines[scan];
+        if is_continuation(line) {
+            scan += 1;
+            end = scan;
+        } else if line.trim().is_empty() {
+            // Blank lines only belong to the entry if more continuation follows
+            scan += 1;
+        } else {
+            break;
+        }
+    }
+
+    Some(Entry { start, end })
+}
+
+/// Split a scalar value from a trailing ` # comment` (with its leading whitespace), respecting quotes
+fn split_inline_comment(value: &str) -> (&str, &str) {
+    let mut in_single = false;
+    let mut in_double = false;
+    let mut prev = ' ';
+
+    for (i, c) in value.char_indices() {
+        match c {
+            '\'' if !in_double => in_single = !in_single,
+            '"' if !in_single && prev != '\\' => in_double = !in_double,
+            '#' if !in_single && !in_double && prev.is_whitespace() => {
+                let scalar = value[..i].trim_end();
+                return (scalar, &value[scalar.len()..]);
+            }
+            _ => {}
+        }
+        prev = c;
+    }
+
+    (value.trim_end(), "")
+}
+
+/// Detect how an existing scalar was quoted
+fn quote_style(raw: &str) -> QuoteStyle {
+    if raw.starts_with('\'') {
+        QuoteStyle::Single
+    } else if raw.starts_with('"') {
+        QuoteStyle::Double
+    } else {
+        QuoteStyle::Plain
+    }
+}
+
+/// Check whether a string can be written as a plain YAML scalar and read back unchanged
+fn is_plain_safe(s: &str, in_flow: bool) -> bool {
+    if s.is_empty() || s.trim() != s {
+        return false;
+    }
+    if s.starts_with([
+        '-', '?', ':', ',', '[', ']', '{', '}', '#', '&', '*', '!', '|', '>', '\'', '"', '%', '@', '`',
+    ]) {
+        return false;
+    }
+    if s.contains(": ") || s.contains(" #") || s.ends_with(':') || s.contains(['\n', '\r', '\t']) {
+        return false;
+    }
+    if in_flow && s.contains([',', '[', ']', '{', '}']) {
+        return false;
+    }
+
+    // Must not be read back as another type
+    matches!(serde_yaml::from_str::<Value>(s), Ok(Value::String(ref parsed)) if parsed == s)
+}
+
+/// Format a string scalar, preferring the given quoting style
+fn format_string(s: &str, style: QuoteStyle, in_flow: bool) -> String {
+    match style {
+        QuoteStyle::Plain if is_plain_safe(s, in_flow) => s.to_string(),
+        QuoteStyle::Single | QuoteStyle::Plain if !s.contains(['\n', '\r', '\t']) => {
+            format!("'{}'", s.replace('\'', "''"))
+        }
+        _ => {
+            let escaped = s
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('\n', "\\n")
+                .replace('\r', "\\r")
+                .replace('\t', "\\t");
+            format!("\"{}\"", escaped)
+        }
+    }
+}
+
+/// Format a scalar value, or None if the value isn't a scalar
+fn format_scalar(value: &Value, style: QuoteStyle, in_flow: bool) -> Option<String> {
+    match value {
+        Value::Null => Some(String::new()),
+        Value::Bool(b) => Some(b.to_string()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::String(s) => Some(format_string(s, style, in_flow)),
+        Value::Tagged(tagged) => format_scalar(&tagged.value, style, in_flow),
+        Value::Sequence(_) | Value::Mapping(_) => None,
+    }
+}
+
+/// Render the lines for `key: value`, following the style of the existing entry if any
+fn render_entry(key: &str, value: &Value, existing: Option<&[String]>) -> Result<Vec<String>> {
+    let existing_first = existing.and_then(|lines| lines.first());
+    let existing_value = existing_first
+        .and_then(|line| line.split_once(':'))
+        .map(|(_, rest)| rest.trim())
+        .unwrap_or("");
+    let (existing_scalar, comment) = split_inline_comment(existing_value);
+
+    // Keep the key exactly as written (it may be quoted)
+    let key_text = existing_first
+        .and_then(|line| line.split_once(':'))
+        .map(|(k, _)| k.to_string())
+        .unwrap_or_else(|| key.to_string());
+
+    let with_comment = |line: String| {
+        if comment.is_empty() {
+            line
+        } else {
+            format!("{}{}", line, comment)
+        }
+    };
+
+    if let Some(scalar) = format_scalar(value, quote_style(existing_scalar), false) {
+        let line = if scalar.is_empty() {
+            format!("{}:", key_text)
+        } else {
+            format!("{}: {}", key_text, scalar)
+        };
+        return Ok(vec![with_comment(line)]);
+    }
+
+    if let Value::Sequence(items) = value {
+        let is_block = existing.is_some_and(|lines| {
+            existing_scalar.is_empty() && lines.iter().skip(1).any(|l| l.trim_start().starts_with('-'))
+        });
+        let all_scalars = items.iter().all(|i| format_scalar(i, QuoteStyle::Plain, true).is_some());
+
+        if is_block || !all_scalars {
+            // Reuse the existing item indentation when there is one
+            let indent = existing
+                .and_then(|lines| {
+                    lines
+                        .iter()
+                        .skip(1)
+                        .find(|l| l.trim_start().starts_with('-'))
+                        .map(|l| l[..l.len() - l.trim_start().len()].to_string())
+                })
+                .unwrap_or_else(|| "  ".to_string());
+
+            let mut lines = vec![with_comment(format!("{}:", key_text))];
+            for item in items {
+                match format_scalar(item, QuoteStyle::Plain, false) {
+                    Some(scalar) => lines.push(format!("{}- {}", indent, scalar)),
+                    None => {
+                        let yaml = serde_yaml::to_string(item).context("Failed to serialize value")?;
+                        for (i, line) in yaml.lines().enumerate() {
+                            let prefix = if i == 0 { "- " } else { "  " };
+                            lines.push(format!("{}{}{}", indent, prefix, line));
+                        }
+                    }
+                }
+            }
+            return Ok(lines);
+        }
+
+        let formatted: Vec<String> = items
+            .iter()
+            .filter_map(|i| format_scalar(i, QuoteStyle::Plain, true))
+            .collect();
+        return Ok(vec![with_comment(format!("{}: [{}]", key_text, formatted.join(", ")))]);
+    }
+
+    // Mappings are written in block style
+    let yaml = serde_yaml::to_string(value).context("Failed to serialize value")?;
+    let mut lines = vec![with_comment(format!("{}:", key_text))];
+    lines.extend(yaml.lines().map(|line| format!("  {}", line)));
+    Ok(lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOC: &str = "---\n# Reading notes\ntitle: 'My Book'\ntags: [book, reading]  # keep me\nauthors:\n  - Ann\n  - Bob\nrating: 4\n---\n\n# My Book\n\nBody text.\n";
+
+    #[test]
+    fn test_parse_splits_body() {
+        let editor = FrontmatterEditor::parse(DOC);
+        assert!(editor.has_frontmatter());
+        assert_eq!(editor.body(), "\n# My Book\n\nBody text.\n");
+        assert_eq!(editor.keys(), vec!["title", "tags", "authors", "rating"]);
+        assert_eq!(editor.render(), DOC);
+    }
+
+    #[test]
+    fn test_parse_without_frontmatter() {
+        let editor = FrontmatterEditor::parse("# Just a note\n");
+        assert!(!editor.has_frontmatter());
+        assert_eq!(editor.render(), "# Just a note\n");
+    }
+
+    #[test]
+    fn test_get_values() {
+        let editor = FrontmatterEditor::parse(DOC);
+        assert_eq!(editor.get("title"), Some(Value::String("My Book".to_string())));
+        assert_eq!(editor.get("rating"), Some(Value::Number(4.into())));
+        assert_eq!(editor.get("authors").unwrap().as_sequence().unwrap().len(), 2);
+        assert_eq!(editor.get("missing"), None);
+    }
+
+    #[test]
+    fn test_set_preserves_everything_else() {
+        let mut editor = FrontmatterEditor::parse(DOC);
+        editor.set_str("title", "New Title").unwrap();
+        editor.set("rating", &Value::Number(5.into())).unwrap();
+
+        let expected = DOC
+            .replace("title: 'My Book'", "title: 'New Title'")
+            .replace("rating: 4", "rating: 5");
+        assert_eq!(editor.render(), expected);
+    }
+
+    #[test]
+    fn test_set_list_keeps_flow_and_block_style() {
+        let mut editor = FrontmatterEditor::parse(DOC);
+        let tags: Value = serde_yaml::from_str("[book, finished]").unwrap();
+        let authors: Value = serde_yaml::from_str("[Ann]").unwrap();
+        editor.set("tags", &tags).unwrap();
+        editor.set("authors", &authors).unwrap();
+
+        let rendered = editor.render();
+        assert!(rendered.contains("tags: [book, finished]  # keep me\n"));
+        assert!(rendered.contains("authors:\n  - Ann\nrating: 4\n"));
+    }
+
+    #[test]
+    fn test_set_appends_new_key_before_trailing_comments() {
+        let mut editor = FrontmatterEditor::parse("---\ntags: [a]\n# trailing\n---\nBody\n");
+        editor.set_str("updated", "2026-01-01T00:00:00Z").unwrap();
+        assert_eq!(
+            editor.render(),
+            "---\ntags: [a]\nupdated: 2026-01-01T00:00:00Z\n# trailing\n---\nBody\n"
+        );
+    }
+
+    #[test]
+    fn test_set_creates_frontmatter() {
+        let mut editor = FrontmatterEditor::parse("# Note\n");
+        editor.set_str("status", "active").unwrap();
+        assert_eq!(editor.render(), "---\nstatus: active\n---\n# Note\n");
+    }
+
+    #[test]
+    fn test_preserves_crlf() {
+        let doc = "---\r\ntags: [a]\r\n---\r\nBody\r\n";
+        let mut editor = FrontmatterEditor::parse(doc);
+        editor.set_str("status", "done").unwrap();
+        assert_eq!(editor.render(), "---\r\ntags: [a]\r\nstatus: done\r\n---\r\nBody\r\n");
+    }
+
+    #[test]
+    fn test_quotes_ambiguous_strings() {
+        let mut editor = FrontmatterEditor::parse("---\na: 1\n---\n");
+        editor.set_str("version", "1.0").unwrap();
+        editor.set_str("flag", "yes: no").unwrap();
+        let rendered = editor.render();
+        assert!(rendered.contains("version: '1.0'\n"));
+        assert!(rendered.contains("flag: 'yes: no'\n"));
+
+        let reparsed = FrontmatterEditor::parse(&rendered);
+        assert_eq!(reparsed.get("version"), Some(Value::String("1.0".to_string())));
+    }
+
+    #[test]
+    fn test_remove_key_with_block_value() {
+        let mut editor = FrontmatterEditor::parse(DOC);
+        assert!(editor.remove("authors"));
+        assert!(!editor.remove("authors"));
+        assert!(!editor.render().contains("Ann"));
+        assert!(editor.render().contains("rating: 4"));
+    }
+}
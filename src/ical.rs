@@ -0,0 +1,256 @@
+//! iCalendar export
+//!
+//! Writes tasks as VTODO entries and daily notes as all-day VEVENT entries,
+//! so calendar apps subscribed to the file show deadlines next to meetings.
+//! Output follows RFC 5545: CRLF line endings, long lines folded at 75 bytes.
+
+use crate::note::{Note, Task, TaskStatus};
+use chrono::{DateTime, Days, NaiveDate, Utc};
+use std::path::{Path, PathBuf};
+
+/// An iCalendar file being built up
+pub struct Calendar {
+    /// Notes directory, for `file://` links back to notes
+    link_root: Option<PathBuf>,
+    /// Stamp for entries whose note has no timestamps
+    generated: DateTime<Utc>,
+    lines: Vec<String>,
+    todos: usize,
+    events: usize,
+}
+
+impl Calendar {
+    pub fn new(link_root: Option<PathBuf>, generated: DateTime<Utc>) -> Self {
+        Self { link_root, generated, lines: Vec::new(), todos: 0, events: 0 }
+    }
+
+    /// Number of tasks added
+    pub fn todos(&self) -> usize {
+        self.todos
+    }
+
+    /// Number of daily notes added
+    pub fn events(&self) -> usize {
+        self.events
+    }
+
+    /// Add a task as a VTODO
+    ///
+    /// `note` is the note the task is in; `date` is the day it was written
+    /// down on, for tasks in daily notes.
+    pub fn add_task(&mut self, task: &Task, note: Option<&Note>, date: Option<NaiveDate>) {
+        self.lines.push("BEGIN:VTODO".to_string());
+        self.lines.push(format!("UID:{}@bnotes", task.id));
+        self.lines.push(format!("DTSTAMP:{}", format_timestamp(self.stamp(note))));
+        self.lines.push(format!("SUMMARY:{}", escape(&task.text)));
+        // DUE can't come before DTSTART
+        if let Some(date) = date.filter(|date| task.due.is_none_or(|due| due >= *date)) {
+            self.lines.push(format!("DTSTART;VALUE=DATE:{}", format_date(date)));
+        }
+        if let Some(due) = task.due {
+            self.lines.push(format!("DUE;VALUE=DATE:{}", format_date(due)));
+        }
+        self.lines.push(format!("STATUS:{}", status(&task.status)));
+        if let Some(done) = task.done.filter(|_| task.status == TaskStatus::Completed) {
+            self.lines.push(format!("COMPLETED:{}T000000Z", format_date(done)));
+        }
+        if let Some(priority) = task.priority.as_deref().and_then(priority) {
+            self.lines.push(format!("PRIORITY:{}", priority));
+        }
+        if !task.tags.is_empty() {
+            let tags: Vec<String> = task.tags.iter().map(|tag| escape(tag)).collect();
+            self.lines.push(format!("CATEGORIES:{}", tags.join(",")));
+        }
+        self.push_links(&task.note_title, &task.note_path);
+        self.lines.push("END:VTODO".to_string());
+        self.todos += 1;
+    }
+
+    /// Add a daily note as an all-day VEVENT on its date
+    pub fn add_daily_note(&mut self, note: &Note, date: NaiveDate) {
+        let next_day = date.checked_add_days(Days::new(1)).unwrap_or(date);
+        self.lines.push("BEGIN:VEVENT".to_string());
+        self.lines.push(format!("UID:daily-{}@bnotes", format_date(date)));
+        self.lines.push(format!("DTSTAMP:{}", format_timestamp(self.stamp(Some(note)))));
+        self.lines.push(format!("SUMMARY:{}", escape(&note.title)));
+        self.lines.push(format!("DTSTART;VALUE=DATE:{}", format_date(date)));
+        self.lines.push(format!("DTEND;VALUE=DATE:{}", format_date(next_day)));
+        // A note doesn't make you busy all day
+        self.lines.push("TRANSP:TRANSPARENT".to_string());
+        self.push_links(&note.title, &note.path);
+        self.lines.push("END:VEVENT".to_string());
+        self.events += 1;
+    }
+
+    /// The finished file
+    pub fn to_ics(&self) -> String {
+        let mut ics = String::new();
+        let header = ["BEGIN:VCALENDAR", "VERSION:2.0", "PRODID:-//bnotes//bnotes//EN", "CALSCALE:GREGORIAN"];
+        let lines = header.iter().copied().chain(self.lines.iter().map(String::as_str)).chain(["END:VCALENDAR"]);
+        for line in lines {
+            ics.push_str(&fold(line));
+            ics.push_str("\r\n");
+        }
+        ics
+    }
+
+    /// Entries only change their stamp when their note changes, so a
+    /// re-export of unchanged notes gives the same file
+    fn stamp(&self, note: Option<&Note>) -> DateTime<Utc> {
+        note.and_then(|note| note.updated.or(note.created)).unwrap_or(self.generated)
+    }
+
+    fn push_links(&mut self, title: &str, path: &Path) {
+        self.lines.push(format!("DESCRIPTION:{}", escape(&format!("{} ({})", title, path.display()))));
+        if let Some(root) = &self.link_root {
+            self.lines.push(format!("URL:file://{}", encode_path(&root.join(path))));
+        }
+    }
+}
+
+/// VTODO status for a checkbox; open custom statuses count as in progress
+fn status(status: &TaskStatus) -> &'static str {
+    match status {
+        TaskStatus::Uncompleted => "NEEDS-ACTION",
+        TaskStatus::Completed => "COMPLETED",
+        TaskStatus::Other { open: true, .. } => "IN-PROCESS",
+        TaskStatus::Other { open: false, .. } | TaskStatus::Migrated => "CANCELLED",
+    }
+}
+
+/// RFC 5545 priority: 1 is highest, 5 medium, 9 lowest
+fn priority(priority: &str) -> Option<u8> {
+    match priority.trim().to_uppercase().as_str() {
+        "A" => Some(1),
+        "B" => Some(5),
+        "C" => Some(9),
+        _ => None,
+    }
+}
+
+fn format_date(date: NaiveDate) -> String {
+    date.format("%Y%m%d").to_string()
+}
+
+fn format_timestamp(timestamp: DateTime<Utc>) -> String {
+    timestamp.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// Escape a TEXT value
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | ';' | ',' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Percent-encode a path for a `file://` URL
+fn encode_path(path: &Path) -> String {
+    let mut encoded = String::new();
+    for byte in path.to_string_lossy().bytes() {
+        if byte.is_ascii_alphanumeric() || b"/-._~".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+/// Split a content line into lines of at most 75 bytes, continuations
+/// starting with a space
+fn fold(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len());
+    let mut width = 0;
+    for c in line.chars() {
+        // Continuation lines lose one byte to the leading space
+        if width + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            width = 1;
+        }
+        folded.push(c);
+        width += c.len_utf8();
+    }
+    folded
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_calendar() {
+        let note = Note::parse(
+            Path::new("2026-10-12.md"),
+            "---\nupdated: 2026-10-12T18:30:00Z\n---\n# 2026-10-12\n\n- [ ] (A) Renew passport, visa due:2026-11-01 @errands ^t-pass\n- [x] (C) Pay rent due:2026-10-01 done:2026-10-02\n- [-] Call plumber\n",
+        )
+        .unwrap();
+        let tasks = Task::extract_from_note(&note);
+
+        let generated = DateTime::parse_from_rfc3339("2026-10-18T09:00:00Z").unwrap().to_utc();
+        let mut calendar = Calendar::new(Some(PathBuf::from("/home/me/My Notes")), generated);
+        for task in &tasks {
+            calendar.add_task(task, Some(&note), Some(date("2026-10-12")));
+        }
+        calendar.add_daily_note(&note, date("2026-10-12"));
+        assert_eq!((calendar.todos(), calendar.events()), (3, 1));
+
+        let ics = calendar.to_ics();
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+        let lines: Vec<&str> = ics.split("\r\n").collect();
+
+        let todo = lines.iter().position(|l| *l == "UID:t-pass@bnotes").unwrap();
+        assert_eq!(
+            lines[todo..todo + 9],
+            [
+                "UID:t-pass@bnotes",
+                "DTSTAMP:20261012T183000Z",
+                "SUMMARY:Renew passport\\, visa",
+                "DTSTART;VALUE=DATE:20261012",
+                "DUE;VALUE=DATE:20261101",
+                "STATUS:NEEDS-ACTION",
+                "PRIORITY:1",
+                "CATEGORIES:errands",
+                "DESCRIPTION:2026-10-12 (2026-10-12.md)",
+            ]
+        );
+        assert_eq!(lines[todo + 9], "URL:file:///home/me/My%20Notes/2026-10-12.md");
+
+        // Due before the day it was written down, so no DTSTART
+        assert!(ics.contains("SUMMARY:Pay rent\r\nDUE;VALUE=DATE:20261001\r\nSTATUS:COMPLETED\r\nCOMPLETED:20261002T000000Z\r\nPRIORITY:9\r\n"));
+        assert!(ics.contains("SUMMARY:Call plumber\r\nDTSTART;VALUE=DATE:20261012\r\nSTATUS:CANCELLED\r\n"));
+        assert!(ics.contains("BEGIN:VEVENT\r\nUID:daily-20261012@bnotes\r\n"));
+        assert!(ics.contains("DTSTART;VALUE=DATE:20261012\r\nDTEND;VALUE=DATE:20261013\r\n"));
+    }
+
+    #[test]
+    fn test_fold() {
+        assert_eq!(fold("SUMMARY:short"), "SUMMARY:short");
+
+        let long = format!("SUMMARY:{}", "é".repeat(40));
+        let folded = fold(&long);
+        let lines: Vec<&str> = folded.split("\r\n").collect();
+        assert!(lines.len() > 1);
+        assert!(lines.iter().all(|line| line.len() <= 75));
+        assert!(lines[1..].iter().all(|line| line.starts_with(' ')));
+        assert_eq!(folded.replace("\r\n ", ""), long);
+    }
+}
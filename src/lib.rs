@@ -21,26 +21,58 @@
 //! }
 //! ```
 
+pub mod changes;
 pub mod config;
+pub mod dependencies;
+pub mod frontmatter;
+pub mod ical;
+pub mod lint;
+pub mod merge;
 pub mod note;
 pub mod periodic;
+pub mod properties;
+pub mod query;
+pub mod repair;
 pub mod repository;
+pub mod schema;
+pub mod score;
 pub mod storage;
+pub mod sync;
+pub mod tags;
 mod templates;
+pub mod todotxt;
 
 use anyhow::Context;
-use std::collections::HashSet;
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeSet, HashSet};
 use std::path::{Path, PathBuf};
-use std::time::SystemTime;
 
 /// Result type alias using anyhow::Error
 pub type Result<T> = std::result::Result<T, anyhow::Error>;
 
+/// Hash note content for change detection, ignoring the `updated` field
+///
+/// Saving a note without edits, or a change that only bumps `updated`,
+/// produces the same hash.
+pub fn content_hash(content: &str) -> u64 {
+    let mut editor = frontmatter::FrontmatterEditor::parse(content);
+    editor.remove("updated");
+
+    fnv1a(&editor.render())
+}
+
+/// FNV-1a, so hashes are stable across builds and platforms
+pub(crate) fn fnv1a(s: &str) -> u64 {
+    s.bytes()
+        .fold(0xcbf29ce484222325, |hash, byte| (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3))
+}
+
 /// Capture the current state of a note file for change detection
-/// Returns modification time that can be compared to detect changes
-pub fn capture_note_state(path: &Path) -> Result<SystemTime> {
-    let metadata = std::fs::metadata(path)?;
-    metadata.modified().context("Failed to get modification time")
+/// Returns a content hash that can be compared to detect real edits
+pub fn capture_note_state(path: &Path) -> Result<u64> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(content_hash(&content))
 }
 
 /// Task sort order - comma-separated list of fields
@@ -51,8 +83,11 @@ pub struct TaskSortOrder {
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum SortField {
+    /// Computed score, highest first (see [`score`])
+    Score,
     Urgency,
     Priority,
+    Note,
     Id,
 }
 
@@ -62,10 +97,12 @@ impl TaskSortOrder {
         let fields: Result<Vec<_>> = s
             .split(',')
             .map(|f| match f.trim() {
+                "score" => Ok(SortField::Score),
                 "urgency" => Ok(SortField::Urgency),
                 "priority" => Ok(SortField::Priority),
+                "note" => Ok(SortField::Note),
                 "id" => Ok(SortField::Id),
-                unknown => anyhow::bail!("Unknown sort field: {}. Valid fields: urgency, priority, id", unknown),
+                unknown => anyhow::bail!("Unknown sort field: {}. Valid fields: score, urgency, priority, note, id", unknown),
             })
             .collect();
 
@@ -76,11 +113,25 @@ impl TaskSortOrder {
 impl Default for TaskSortOrder {
     fn default() -> Self {
         Self {
-            fields: vec![SortField::Urgency, SortField::Priority, SortField::Id]
+            fields: vec![SortField::Urgency, SortField::Priority, SortField::Note]
         }
     }
 }
 
+/// Timestamps to fill into a note that is missing them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DateBackfill {
+    pub path: PathBuf,
+    pub title: String,
+    /// New `created` value, if the note has none
+    pub created: Option<DateTime<Utc>>,
+    /// New `updated` value, if the note has none
+    pub updated: Option<DateTime<Utc>>,
+}
+
+/// Looks up when a note was started
+type NoteDates = dyn Fn(&Path) -> Option<DateTime<Utc>>;
+
 /// Main library API for BNotes
 ///
 /// This struct provides the primary interface for interacting with notes.
@@ -88,13 +139,27 @@ impl Default for TaskSortOrder {
 pub struct BNotes {
     config: config::LibraryConfig,
     repo: repository::Repository,
+    /// When a note without a `created` date was started, if known
+    note_dates: Option<Box<NoteDates>>,
 }
 
 impl BNotes {
     /// Create a new BNotes instance with the given configuration and storage
     pub fn new(config: config::LibraryConfig, storage: Box<dyn storage::Storage>) -> Self {
         let repo = repository::Repository::new(storage);
-        Self { config, repo }
+        Self { config, repo, note_dates: None }
+    }
+
+    /// Look up when notes were started, for notes without a `created` date
+    ///
+    /// Task scores use this for the age of a note, for example with the
+    /// date of the note's first commit.
+    pub fn with_note_dates<F>(mut self, dates: F) -> Self
+    where
+        F: Fn(&Path) -> Option<DateTime<Utc>> + 'static,
+    {
+        self.note_dates = Some(Box::new(dates));
+        self
     }
 
     /// Create BNotes by loading configuration from storage
@@ -103,7 +168,18 @@ impl BNotes {
         Ok(Self::new(config, storage))
     }
 
+    /// Create BNotes with the configuration in storage, or defaults if there is none
+    ///
+    /// A config file with errors is reported rather than ignored.
+    pub fn open(storage: Box<dyn storage::Storage>) -> Result<Self> {
+        let config = config::LibraryConfig::load_if_present(&*storage)?;
+        Ok(Self::new(config, storage))
+    }
+
     /// Create BNotes with default configuration
+    ///
+    /// A config file that can't be loaded is ignored; use [`BNotes::open`]
+    /// to have its errors reported.
     pub fn with_defaults(storage: Box<dyn storage::Storage>) -> Self {
         let config = config::LibraryConfig::load_or_default(&*storage);
         Self::new(config, storage)
@@ -114,15 +190,61 @@ impl BNotes {
         self.repo.search(query)
     }
 
+    /// Search notes by query, keeping only notes matching the tag filters
+    ///
+    /// Tag filters use the hierarchical syntax described in [`tags::TagFilter`].
+    pub fn search_with_tags(&self, query: &str, tags: &[String]) -> Result<Vec<repository::SearchMatch>> {
+        let filter = tags::TagFilter::parse(tags)?;
+        let mut results = self.repo.search(query)?;
+        results.retain(|m| filter.matches(&m.note.tags));
+        Ok(results)
+    }
+
     /// List all notes, optionally filtered by tags
+    ///
+    /// Tag filters are hierarchical (`work` matches `work/meetings`), and support
+    /// negation (`!archive`) and alternatives (`work,personal`).
     pub fn list_notes(&self, tags: &[String]) -> Result<Vec<note::Note>> {
         if tags.is_empty() {
             self.repo.discover_notes()
         } else {
-            self.repo.filter_by_tags(tags)
+            let filter = tags::TagFilter::parse(tags)?;
+            self.repo.filter_by_tags(&filter)
         }
     }
 
+    /// List notes filtered by tags and frontmatter properties
+    ///
+    /// All property filters must match. Notes are sorted by the given property
+    /// (missing values last), or by title when no sort is given.
+    pub fn query_notes(
+        &self,
+        tags: &[String],
+        filters: &[properties::PropertyFilter],
+        sort: Option<&properties::PropertySort>,
+    ) -> Result<Vec<note::Note>> {
+        let mut notes = self.list_notes(tags)?;
+        notes.retain(|note| filters.iter().all(|f| f.matches(note)));
+
+        match sort {
+            Some(sort) => notes.sort_by(|a, b| sort.compare(a, b)),
+            None => notes.sort_by(|a, b| a.title.cmp(&b.title)),
+        }
+
+        Ok(notes)
+    }
+
+    /// Build the tag hierarchy of all note and task tags
+    pub fn list_tags(&self) -> Result<tags::TagTree> {
+        let notes = self.repo.discover_notes()?;
+        let tasks = note::extract_tasks_from_notes(&notes, &self.config.task_vocabulary());
+
+        Ok(tags::TagTree::build(
+            notes.iter().map(|n| n.tags.as_slice()),
+            tasks.iter().map(|t| t.tags.as_slice()),
+        ))
+    }
+
     /// Find a note by title (case-insensitive)
     pub fn find_note_by_title(&self, title: &str) -> Result<Vec<note::Note>> {
         self.repo.find_by_title(title)
@@ -133,7 +255,7 @@ impl BNotes {
     /// Returns (outbound_links, inbound_links) where each is a set of note titles
     pub fn get_note_links(&self, title: &str) -> Result<(HashSet<String>, HashSet<String>)> {
         let all_notes = self.repo.discover_notes()?;
-        let graph = repository::LinkGraph::build(&all_notes);
+        let graph = repository::LinkGraph::build(&all_notes, &self.config.task_vocabulary());
 
         let outbound = graph
             .outbound
@@ -150,10 +272,24 @@ impl BNotes {
         Ok((outbound, inbound))
     }
 
+    /// Get the tasks whose text links to a note
+    pub fn get_linking_tasks(&self, title: &str) -> Result<Vec<note::Task>> {
+        let all_notes = self.repo.discover_notes()?;
+        let graph = repository::LinkGraph::build(&all_notes, &self.config.task_vocabulary());
+        let Some(ids) = graph.task_links.get(title) else {
+            return Ok(Vec::new());
+        };
+
+        Ok(note::extract_tasks_from_notes(&all_notes, &self.config.task_vocabulary())
+            .into_iter()
+            .filter(|task| ids.contains(&task.id))
+            .collect())
+    }
+
     /// Get the full link graph for all notes
     pub fn get_link_graph(&self) -> Result<repository::LinkGraph> {
         let all_notes = self.repo.discover_notes()?;
-        Ok(repository::LinkGraph::build(&all_notes))
+        Ok(repository::LinkGraph::build(&all_notes, &self.config.task_vocabulary()))
     }
 
     /// Compare urgency levels: !!! < !! < ! < None
@@ -198,46 +334,66 @@ impl BNotes {
         self.repo.create_note(title, template_dir, template_name)
     }
 
-    /// List all tasks, optionally filtered by tags and status
+    /// List all tasks, optionally filtered by the tags of their notes and by status
+    ///
+    /// Tag filters use the same syntax as [`BNotes::list_notes`]. To filter on
+    /// the tasks' own `@tags`, use [`BNotes::query_tasks`] with [`query::TaskQuery::tags`].
     ///
-    /// Status can be Some("open"), Some("completed"), Some("migrated"), Some("all"), or None for all tasks
+    /// Status can be Some("open") for any open status, Some("closed"), Some("all"), a status name
+    /// from the task vocabulary like Some("done") or Some("cancelled"), or None for all tasks
     pub fn list_tasks(&self, tags: &[String], status: Option<&str>, sort_order: TaskSortOrder) -> Result<Vec<note::Task>> {
-        // Get notes, optionally filtered by tags
-        let notes = if tags.is_empty() {
-            self.repo.discover_notes()?
-        } else {
-            self.repo.filter_by_tags(tags)?
+        let query = match status {
+            Some(status) => query::TaskQuery::status(status),
+            None => query::TaskQuery::default(),
         };
-
-        // Extract tasks from all notes
-        let mut tasks = note::extract_tasks_from_notes(&notes);
-
-        // Filter by status if specified
-        if let Some(status_filter) = status {
-            if status_filter.eq_ignore_ascii_case("all") {
-                // No filtering
-            } else if status_filter.eq_ignore_ascii_case("open") {
-                tasks.retain(|task| task.status == note::TaskStatus::Uncompleted);
-            } else if status_filter.eq_ignore_ascii_case("completed") || status_filter.eq_ignore_ascii_case("done") {
-                tasks.retain(|task| task.status == note::TaskStatus::Completed);
-            } else if status_filter.eq_ignore_ascii_case("migrated") {
-                tasks.retain(|task| task.status == note::TaskStatus::Migrated);
-            } else {
-                anyhow::bail!("Invalid status filter: {}. Use 'open', 'completed', 'migrated', or 'all'.", status_filter);
-            }
+        let mut tasks = self.query_tasks(&query, sort_order)?;
+        if !tags.is_empty() {
+            let paths: HashSet<PathBuf> = self.list_notes(tags)?.into_iter().map(|note| note.path).collect();
+            tasks.retain(|task| paths.contains(&task.note_path));
         }
+        Ok(tasks)
+    }
+
+    /// List the tasks matching a query (see [`query`] for the syntax)
+    pub fn query_tasks(&self, query: &query::TaskQuery, sort_order: TaskSortOrder) -> Result<Vec<note::Task>> {
+        let vocabulary = self.config.task_vocabulary();
+        // Reject unknown statuses even when there are no tasks
+        query.validate(&vocabulary)?;
+
+        let notes = self.repo.discover_notes()?;
+        let tasks = note::extract_tasks_from_notes(&notes, &vocabulary);
+
+        // Dependencies and scores need every task, so references into other notes resolve
+        let dependencies = dependencies::DependencyGraph::build(tasks.clone());
+        let scorer = sort_order
+            .fields
+            .contains(&SortField::Score)
+            .then(|| self.scorer_for(&notes, tasks.clone()));
+
+        let ctx = query::QueryContext {
+            vocabulary: &vocabulary,
+            today: chrono::Local::now().date_naive(),
+            dependencies: Some(&dependencies),
+        };
+        let tasks: Vec<note::Task> = tasks.into_iter().filter(|task| query.matches(task, &ctx)).collect();
 
         // Sort based on provided sort order
-        tasks.sort_by(|a, b| {
+        let mut scored: Vec<(f64, note::Task)> = tasks
+            .into_iter()
+            .map(|task| (scorer.as_ref().map_or(0.0, |scorer| scorer.score(&task)), task))
+            .collect();
+        scored.sort_by(|(a_score, a), (b_score, b)| {
             for field in &sort_order.fields {
                 let cmp = match field {
+                    SortField::Score => b_score.total_cmp(a_score),
                     SortField::Urgency => Self::compare_urgency(&a.urgency, &b.urgency),
                     SortField::Priority => Self::compare_priority(&a.priority, &b.priority),
-                    SortField::Id => {
-                        // Sort by note title first, then by index
+                    SortField::Note => {
+                        // Sort by note title first, then by position in the note
                         a.note_title.cmp(&b.note_title)
                             .then_with(|| a.index.cmp(&b.index))
                     }
+                    SortField::Id => a.id.cmp(&b.id),
                 };
                 if cmp != std::cmp::Ordering::Equal {
                     return cmp;
@@ -245,10 +401,247 @@ impl BNotes {
             }
             std::cmp::Ordering::Equal
         });
+        let tasks = scored.into_iter().map(|(_, task)| task).collect();
 
         Ok(tasks)
     }
 
+    /// Dependencies between all tasks, for working out which are blocked
+    pub fn task_dependencies(&self) -> Result<dependencies::DependencyGraph> {
+        let notes = self.repo.discover_notes()?;
+        let tasks = note::extract_tasks_from_notes(&notes, &self.config.task_vocabulary());
+        Ok(dependencies::DependencyGraph::build(tasks))
+    }
+
+    /// Scorer for ranking tasks by what to do next, as of today
+    pub fn task_scorer(&self) -> Result<score::TaskScorer> {
+        let notes = self.repo.discover_notes()?;
+        let tasks = note::extract_tasks_from_notes(&notes, &self.config.task_vocabulary());
+        Ok(self.scorer_for(&notes, tasks))
+    }
+
+    fn scorer_for(&self, notes: &[Note], tasks: Vec<note::Task>) -> score::TaskScorer {
+        let today = chrono::Local::now().date_naive();
+        // Only look up dates for notes whose tasks get scored
+        let task_notes: HashSet<PathBuf> = tasks.iter().map(|task| task.note_path.clone()).collect();
+        let dependencies = dependencies::DependencyGraph::build(tasks);
+        let scorer = score::TaskScorer::new(self.config.task_score.clone(), notes, dependencies, today);
+        match &self.note_dates {
+            Some(dates) => scorer.with_fallback_dates(notes, |note| {
+                task_notes.contains(&note.path).then(|| dates(&note.path)).flatten().map(|date| date.date_naive())
+            }),
+            None => scorer,
+        }
+    }
+
+    /// Find a task by its ID (with or without the leading `^`)
+    ///
+    /// A migrated task keeps its anchor, so the same ID can be on the old and
+    /// the new copy. The copy that isn't marked migrated wins. Hash IDs can be
+    /// shortened to a unique prefix of at least six digits, like `h-3fa91c`.
+    pub fn find_task(&self, id: &str) -> Result<note::Task> {
+        let id = id.trim_start_matches('^');
+        let notes = self.repo.discover_notes()?;
+        let tasks = note::extract_tasks_from_notes(&notes, &self.config.task_vocabulary());
+
+        let mut matches: Vec<note::Task> = tasks.iter().filter(|task| task.id == id).cloned().collect();
+        if matches.is_empty() && id.starts_with("h-") && id.len() >= 8 {
+            matches = tasks.into_iter().filter(|task| task.id.starts_with(id)).collect();
+            let ids: BTreeSet<&str> = matches.iter().map(|task| task.id.as_str()).collect();
+            if ids.len() > 1 {
+                let ids: Vec<&str> = ids.into_iter().collect();
+                anyhow::bail!("Task ID {} is ambiguous: {}", id, ids.join(", "));
+            }
+        }
+
+        if matches.len() > 1 {
+            matches.retain(|task| task.status != note::TaskStatus::Migrated);
+        }
+        match matches.len() {
+            0 => anyhow::bail!("No task with ID {}", id),
+            1 => Ok(matches.remove(0)),
+            _ => {
+                let notes: Vec<String> = matches.iter().map(|t| t.note_path.display().to_string()).collect();
+                anyhow::bail!("Task ID {} is used in several notes: {}", id, notes.join(", "))
+            }
+        }
+    }
+
+    /// List the tasks matching a query that exports should include, in note order
+    ///
+    /// Migrated tasks are left out: they live on in the note they moved to.
+    pub fn exportable_tasks(&self, query: &query::TaskQuery) -> Result<Vec<note::Task>> {
+        let query = query.clone().and(query::TaskQuery::status("migrated").negate());
+        self.query_tasks(&query, TaskSortOrder::parse("note")?)
+    }
+
+    /// Build a todo.txt file of the tasks matching a query
+    pub fn export_todotxt(&self, query: &query::TaskQuery) -> Result<String> {
+        let tasks = self.exportable_tasks(query)?;
+        Ok(tasks.iter().map(|task| todotxt::to_line(task) + "\n").collect())
+    }
+
+    /// Append the tasks in a todo.txt file to a note, returning how many were added
+    pub fn import_todotxt(&self, note_path: &Path, todotxt: &str) -> Result<usize> {
+        let lines: Vec<String> = todotxt
+            .lines()
+            .filter_map(todotxt::parse_line)
+            .map(|task| task.to_markdown_line())
+            .collect();
+        if lines.is_empty() {
+            return Ok(0);
+        }
+
+        let content = self.repo.storage.read_to_string(note_path)?;
+        let mut updated = content.trim_end().to_string();
+        // Continue a task list the note ends with, otherwise start a new one
+        let last_line = updated.lines().last().unwrap_or_default().trim_start();
+        if !updated.is_empty() && !last_line.starts_with("- [") {
+            updated.push('\n');
+        }
+        for line in &lines {
+            if !updated.is_empty() {
+                updated.push('\n');
+            }
+            updated.push_str(line);
+        }
+        updated.push('\n');
+
+        self.repo.storage.write(note_path, &updated)?;
+        Ok(lines.len())
+    }
+
+    /// Build an iCalendar file of dated tasks, and optionally daily notes
+    ///
+    /// Tasks matching the query become VTODO entries when they have a `due:`
+    /// or `done:` date or sit in a daily note. With `link_root`, entries
+    /// link to their note as a `file://` URL under it.
+    pub fn export_ics(
+        &self,
+        query: &query::TaskQuery,
+        daily_notes: bool,
+        link_root: Option<PathBuf>,
+    ) -> Result<ical::Calendar> {
+        let tasks = self.exportable_tasks(query)?;
+        let notes = self.repo.discover_notes()?;
+        let daily_date = |path: &Path| {
+            let stem = path.file_stem()?.to_str()?;
+            chrono::NaiveDate::parse_from_str(stem, "%Y-%m-%d").ok()
+        };
+
+        let mut calendar = ical::Calendar::new(link_root, chrono::Utc::now());
+        for task in &tasks {
+            let date = daily_date(&task.note_path);
+            if task.due.is_none() && task.done.is_none() && date.is_none() {
+                continue;
+            }
+            let note = notes.iter().find(|note| note.path == task.note_path);
+            calendar.add_task(task, note, date);
+        }
+
+        if daily_notes {
+            let mut dailies: Vec<(chrono::NaiveDate, &note::Note)> =
+                notes.iter().filter_map(|note| daily_date(&note.path).map(|date| (date, note))).collect();
+            dailies.sort_by_key(|(date, _)| *date);
+            for (date, note) in dailies {
+                calendar.add_daily_note(note, date);
+            }
+        }
+
+        Ok(calendar)
+    }
+
+    /// Change the checkbox of a task, returning the task as it was
+    pub fn set_task_status(&self, id: &str, status: note::TaskStatus) -> Result<note::Task> {
+        let task = self.find_task(id)?;
+        self.rewrite_task_lines(&task.note_path, |number, line| {
+            if number == task.line { note::set_checkbox(line, &status) } else { None }
+        })?;
+        Ok(task)
+    }
+
+    /// Give a task a block anchor so its ID survives edits to its text
+    ///
+    /// Returns the task's anchor, which is its existing one if it has one.
+    pub fn anchor_task(&self, id: &str) -> Result<String> {
+        let task = self.find_task(id)?;
+        if let Some(anchor) = task.anchor {
+            return Ok(anchor);
+        }
+        let mut anchors = self.anchors_for(std::slice::from_ref(&task))?;
+        Ok(anchors.remove(0))
+    }
+
+    /// Give every task without a block anchor one
+    ///
+    /// Returns the number of tasks anchored.
+    pub fn anchor_all_tasks(&self) -> Result<usize> {
+        let notes = self.repo.discover_notes()?;
+        let unanchored: Vec<note::Task> = note::extract_tasks_from_notes(&notes, &self.config.task_vocabulary())
+            .into_iter()
+            .filter(|task| task.anchor.is_none())
+            .collect();
+        Ok(self.anchors_for(&unanchored)?.len())
+    }
+
+    /// Write new anchors onto the given tasks, unique across all notes
+    fn anchors_for(&self, tasks: &[note::Task]) -> Result<Vec<String>> {
+        let notes = self.repo.discover_notes()?;
+        let mut taken: HashSet<String> = note::extract_tasks_from_notes(&notes, &self.config.task_vocabulary())
+            .into_iter()
+            .filter_map(|task| task.anchor)
+            .collect();
+
+        let mut by_note: std::collections::BTreeMap<&Path, Vec<(usize, String)>> = Default::default();
+        let mut anchors = Vec::new();
+        for task in tasks {
+            let anchor = Self::new_anchor(task, &taken);
+            taken.insert(anchor.clone());
+            by_note.entry(&task.note_path).or_default().push((task.line, anchor.clone()));
+            anchors.push(anchor);
+        }
+
+        for (path, lines) in by_note {
+            self.rewrite_task_lines(path, |number, line| {
+                lines
+                    .iter()
+                    .find(|(task_line, _)| *task_line == number)
+                    .map(|(_, anchor)| note::add_anchor(line, anchor))
+            })?;
+        }
+        Ok(anchors)
+    }
+
+    /// Pick an anchor like `t-4f2a` that no other task uses
+    fn new_anchor(task: &note::Task, taken: &HashSet<String>) -> String {
+        (0u64..)
+            .map(|salt| {
+                // Widen the anchor if short ones keep colliding
+                let digits = 4 + 2 * (salt / 64) as usize;
+                let hash = fnv1a(&format!("{}\n{}\n{}", task.note_path.display(), task.text, salt));
+                format!("t-{:0width$x}", hash % 16u64.pow(digits as u32), width = digits)
+            })
+            .find(|anchor| !taken.contains(anchor))
+            .expect("anchor space is unbounded")
+    }
+
+    /// Rewrite lines of a note, by 1-based line number
+    fn rewrite_task_lines<F>(&self, note_path: &Path, edit: F) -> Result<()>
+    where
+        F: Fn(usize, &str) -> Option<String>,
+    {
+        let content = self.repo.storage.read_to_string(note_path)?;
+        let updated: String = content
+            .split_inclusive('\n')
+            .enumerate()
+            .map(|(i, line)| edit(i + 1, line).unwrap_or_else(|| line.to_string()))
+            .collect();
+        if updated != content {
+            self.repo.storage.write(note_path, &updated)?;
+        }
+        Ok(())
+    }
+
     /// Open or create a periodic note for a given period
     ///
     /// Returns the relative path to the periodic note
@@ -363,20 +756,20 @@ impl BNotes {
         None
     }
 
-    /// Mark all uncompleted tasks as migrated in a note
-    fn mark_tasks_migrated(&self, note_path: &Path) -> Result<()> {
-        let content = self.repo.storage.read_to_string(note_path)?;
-        let updated_content = content.replace("- [ ]", "- [>]");
-        self.repo.storage.write(note_path, &updated_content)?;
-        Ok(())
+    /// Mark the given tasks as migrated in the note they came from
+    fn mark_tasks_migrated(&self, note_path: &Path, tasks: &[note::Task]) -> Result<()> {
+        let lines: HashSet<usize> = tasks.iter().map(|task| task.line).collect();
+        self.rewrite_task_lines(note_path, |number, line| {
+            if lines.contains(&number) { note::set_checkbox(line, &note::TaskStatus::Migrated) } else { None }
+        })
     }
 
     /// Build the migrated tasks section from a list of tasks
     /// Returns just the task list without heading (heading should be in template)
-    fn build_migrated_section(tasks: &[note::Task]) -> String {
-        tasks
+    fn build_migrated_section(subtrees: &[(&note::Task, usize)]) -> String {
+        subtrees
             .iter()
-            .map(|task| task.to_markdown_line())
+            .map(|(task, depth)| format!("{}{}", "  ".repeat(*depth), task.to_markdown_line()))
             .collect::<Vec<_>>()
             .join("\n")
     }
@@ -407,19 +800,13 @@ impl BNotes {
         let is_current_week = period == periodic::Weekly::current();
         let should_migrate_check = is_current_week || !should_prompt;
 
-        // Find previous weekly note and extract uncompleted tasks
-        let (previous_note, uncompleted_tasks) = if should_migrate_check {
+        // Find previous weekly note and extract its tasks
+        let (previous_note, previous_tasks) = if should_migrate_check {
             if let Some(prev_path) = self.find_previous_weekly_note(period) {
                 // Read and parse the previous note
                 let content = self.repo.storage.read_to_string(&prev_path)?;
                 let prev_note = note::Note::parse(&prev_path, &content)?;
-                let all_tasks = note::Task::extract_from_note(&prev_note);
-                let uncompleted: Vec<_> = all_tasks
-                    .into_iter()
-                    .filter(|t| t.status == note::TaskStatus::Uncompleted)
-                    .collect();
-
-                (Some(prev_path), uncompleted)
+                (Some(prev_path), note::Task::extract_with_vocabulary(&prev_note, &self.config.task_vocabulary()))
             } else {
                 (None, Vec::new())
             }
@@ -427,6 +814,14 @@ impl BNotes {
             (None, Vec::new())
         };
 
+        // Open tasks move with their subtasks
+        let subtrees = note::migration_subtrees(&previous_tasks);
+        let uncompleted_tasks: Vec<note::Task> = subtrees
+            .iter()
+            .filter(|(task, _)| task.status.is_incomplete())
+            .map(|(task, _)| (*task).clone())
+            .collect();
+
         // Prompt for migration if needed
         let should_migrate = if should_prompt && !uncompleted_tasks.is_empty() {
             if let Some(ref prev_path) = previous_note {
@@ -463,7 +858,7 @@ impl BNotes {
 
         // Build migrated tasks section if migrating
         let (content, migrated_count) = if should_migrate {
-            let migrated_section = Self::build_migrated_section(&uncompleted_tasks);
+            let migrated_section = Self::build_migrated_section(&subtrees);
             let count = uncompleted_tasks.len();
 
             let content = if self.repo.storage.exists(&full_template_path) {
@@ -494,10 +889,10 @@ impl BNotes {
         };
 
         // Mark tasks as migrated in the previous note if migration happened
-        if migrated_count > 0 {
-            if let Some(prev_path) = previous_note {
-                self.mark_tasks_migrated(&prev_path)?;
-            }
+        if migrated_count > 0
+            && let Some(prev_path) = previous_note
+        {
+            self.mark_tasks_migrated(&prev_path, &uncompleted_tasks)?;
         }
 
         // Write the new note
@@ -506,118 +901,231 @@ impl BNotes {
         Ok((note_path, migrated_count))
     }
 
+    /// Run the built-in lint rules at the levels set in the config
+    pub fn lint(&self) -> Result<lint::LintReport> {
+        self.lint_with(&lint::Linter::with_builtin_rules())
+    }
+
     /// Run health checks on the note collection
     ///
     /// Returns a report of potential issues including broken links, missing metadata,
-    /// duplicate titles, and orphaned notes
+    /// duplicate titles, orphaned notes, and frontmatter that doesn't match the
+    /// configured note types. [`BNotes::lint`] runs these and more checks, at the
+    /// levels set in the config.
     pub fn check_health(&self) -> Result<repository::HealthReport> {
-        let notes = self.repo.discover_notes()?;
-        Ok(repository::check_health(&notes))
+        let (notes, load_errors) = self.repo.load_notes()?;
+        let skipped = self.repo.discover_skipped_files()?;
+        Ok(lint::health_report(&notes, &skipped, &load_errors, &self.config))
     }
 
-    /// Parse frontmatter from note content
-    /// Returns (frontmatter, body_content) where body is everything after frontmatter
-    fn parse_frontmatter(&self, content: &str) -> Result<(Option<note::Frontmatter>, String)> {
-        use pulldown_cmark::{Event, MetadataBlockKind, Options, Parser, Tag, TagEnd};
+    /// Run a custom set of lint rules at the levels set in the config
+    pub fn lint_with(&self, linter: &lint::Linter) -> Result<lint::LintReport> {
+        let (notes, load_errors) = self.repo.load_notes()?;
+        let skipped = self.repo.discover_skipped_files()?;
+        Ok(linter.run_with_files(&notes, &skipped, &load_errors, &self.config))
+    }
 
-        let mut options = Options::empty();
-        options.insert(Options::ENABLE_YAML_STYLE_METADATA_BLOCKS);
+    /// Edit a note's frontmatter in place
+    ///
+    /// The closure patches keys through a [`frontmatter::FrontmatterEditor`], which
+    /// keeps comments, key order and formatting of everything it doesn't touch.
+    /// The note is only written if its content changed. Returns whether it did.
+    pub fn edit_frontmatter<F>(&self, note_path: &Path, edit: F) -> Result<bool>
+    where
+        F: FnOnce(&mut frontmatter::FrontmatterEditor) -> Result<()>,
+    {
+        let content = self.repo.storage().read_to_string(note_path)?;
+        let mut editor = frontmatter::FrontmatterEditor::parse(&content);
+        edit(&mut editor)?;
 
-        let parser = Parser::new_ext(content, options);
-        let mut in_metadata = false;
-        let mut yaml_content = String::new();
-        let mut found_metadata = false;
+        let new_content = editor.render();
+        if new_content == content {
+            return Ok(false);
+        }
 
-        for event in parser {
-            match event {
-                Event::Start(Tag::MetadataBlock(MetadataBlockKind::YamlStyle)) => {
-                    in_metadata = true;
-                    found_metadata = true;
-                }
-                Event::End(TagEnd::MetadataBlock(MetadataBlockKind::YamlStyle)) => {
-                    in_metadata = false;
-                }
-                Event::Text(text) if in_metadata => {
-                    yaml_content.push_str(&text);
-                }
-                _ => {}
+        self.repo.storage().write(note_path, &new_content)?;
+        Ok(true)
+    }
+
+    /// Update the 'updated' timestamp in a note's frontmatter
+    pub fn update_note_timestamp(&self, note_path: &Path) -> Result<()> {
+        self.set_updated_now(note_path)?;
+        Ok(())
+    }
+
+    /// Update the 'updated' timestamp if the note changed since `previous`
+    ///
+    /// `previous` is the earlier content of the note (for example the version
+    /// in the last git commit), or None if the note is new. Differences in the
+    /// `updated` field alone don't count as a change, and a note whose `updated`
+    /// already differs from `previous` is left alone so repeated calls are
+    /// no-ops. Returns whether the timestamp was updated.
+    pub fn touch_note(&self, note_path: &Path, previous: Option<&str>) -> Result<bool> {
+        if let Some(previous) = previous {
+            let current = self.repo.storage().read_to_string(note_path)?;
+            if content_hash(&current) == content_hash(previous) {
+                return Ok(false);
+            }
+
+            let updated = |content: &str| frontmatter::FrontmatterEditor::parse(content).get("updated");
+            if updated(&current) != updated(previous) {
+                return Ok(false);
             }
         }
 
-        let frontmatter = if found_metadata && !yaml_content.is_empty() {
-            match serde_yaml::from_str::<note::Frontmatter>(&yaml_content) {
-                Ok(fm) => Some(fm),
-                Err(e) => {
-                    anyhow::bail!("Failed to parse frontmatter: {}", e);
-                }
+        self.set_updated_now(note_path)
+    }
+
+    /// Set 'updated' to the current time, skipping notes without frontmatter
+    fn set_updated_now(&self, note_path: &Path) -> Result<bool> {
+        use chrono::{SecondsFormat, Utc};
+
+        let now = Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true);
+        self.edit_frontmatter(note_path, |editor| {
+            if editor.has_frontmatter() {
+                editor.set_str("updated", &now)?;
             }
-        } else {
-            None
-        };
+            Ok(())
+        })
+    }
 
-        // Now find where the body starts by locating the closing ---
-        let body = if found_metadata {
-            // Find the frontmatter boundaries manually to correctly extract body
-            if content.starts_with("---\n") || content.starts_with("---\r\n") {
-                let after_opening = if content.starts_with("---\r\n") { 5 } else { 4 };
-
-                // Find closing ---
-                if let Some(pos) = content[after_opening..].find("\n---\n") {
-                    let body_start = after_opening + pos + 5; // Skip past "\n---\n"
-                    if body_start < content.len() {
-                        &content[body_start..]
-                    } else {
-                        ""
-                    }
-                } else if let Some(pos) = content[after_opening..].find("\n---\r\n") {
-                    let body_start = after_opening + pos + 6; // Skip past "\n---\r\n"
-                    if body_start < content.len() {
-                        &content[body_start..]
-                    } else {
-                        ""
-                    }
-                } else {
-                    content // Malformed, return everything
-                }
-            } else {
-                content
+    /// Find notes missing `created` or `updated` and work out what to fill in
+    ///
+    /// `dates` returns the (first, last) change times of a note, for example
+    /// from its git history, or None if they are unknown. Only missing fields
+    /// are filled; existing timestamps are never overwritten.
+    pub fn plan_date_backfill<F>(&self, mut dates: F) -> Result<Vec<DateBackfill>>
+    where
+        F: FnMut(&note::Note) -> Result<Option<(DateTime<Utc>, DateTime<Utc>)>>,
+    {
+        let mut backfills = Vec::new();
+
+        for note in self.repo.discover_notes()? {
+            if note.created.is_some() && note.updated.is_some() {
+                continue;
             }
-        } else {
-            content
-        };
 
-        Ok((frontmatter, body.to_string()))
+            let Some((first, last)) = dates(&note)? else {
+                continue;
+            };
+
+            backfills.push(DateBackfill {
+                created: note.created.is_none().then_some(first),
+                updated: note.updated.is_none().then_some(last),
+                path: note.path,
+                title: note.title,
+            });
+        }
+
+        Ok(backfills)
     }
 
-    /// Update the 'updated' timestamp in a note's frontmatter
-    pub fn update_note_timestamp(&self, note_path: &Path) -> Result<()> {
-        use chrono::Utc;
+    /// Write planned `created`/`updated` timestamps into note frontmatter
+    pub fn apply_date_backfill(&self, backfills: &[DateBackfill]) -> Result<()> {
+        use chrono::SecondsFormat;
 
-        // Read the note file
-        let content = self.repo.storage().read_to_string(note_path)?;
+        for backfill in backfills {
+            self.edit_frontmatter(&backfill.path, |editor| {
+                if let Some(created) = backfill.created {
+                    editor.set_str("created", &created.to_rfc3339_opts(SecondsFormat::Secs, true))?;
+                }
+                if let Some(updated) = backfill.updated {
+                    editor.set_str("updated", &updated.to_rfc3339_opts(SecondsFormat::Secs, true))?;
+                }
+                Ok(())
+            })?;
+        }
 
-        // Parse to extract frontmatter and body
-        let (frontmatter_opt, body) = self.parse_frontmatter(&content)?;
+        Ok(())
+    }
 
-        // Skip if no frontmatter
-        let mut frontmatter = match frontmatter_opt {
-            Some(fm) => fm,
-            None => return Ok(()), // No frontmatter, nothing to update
-        };
+    /// Set a frontmatter property on a note
+    ///
+    /// The value is parsed as YAML, so `4` is stored as a number and `[a, b]` as a list.
+    pub fn set_note_property(&self, note_path: &Path, key: &str, value: &str) -> Result<bool> {
+        let value: serde_yaml::Value = serde_yaml::from_str(value)
+            .unwrap_or_else(|_| serde_yaml::Value::String(value.to_string()));
 
-        // Update the 'updated' field with current UTC timestamp
-        frontmatter.updated = Some(Utc::now());
+        self.edit_frontmatter(note_path, |editor| editor.set(key, &value))
+    }
 
-        // Serialize frontmatter back to YAML
-        let yaml = serde_yaml::to_string(&frontmatter)?;
+    /// Remove a frontmatter property from a note
+    pub fn unset_note_property(&self, note_path: &Path, key: &str) -> Result<bool> {
+        self.edit_frontmatter(note_path, |editor| {
+            editor.remove(key);
+            Ok(())
+        })
+    }
 
-        // Reconstruct file: frontmatter + body
-        let new_content = format!("---\n{}---\n{}", yaml, body);
+    /// Rename a tag (and every tag nested under it) across all notes
+    ///
+    /// Frontmatter tags are patched in place and `@tag` markers in tasks
+    /// are rewritten. Returns the paths of the notes that changed.
+    pub fn rename_tag(&self, old: &str, new: &str) -> Result<Vec<PathBuf>> {
+        let new_normalized = tags::normalize(new);
+        if tags::normalize(old).is_empty() || new_normalized.is_empty() {
+            anyhow::bail!("Tag names cannot be empty");
+        }
 
-        // Write back to file
-        self.repo.storage().write(note_path, &new_content)?;
+        let vocabulary = self.config.task_vocabulary();
+        let mut changed = Vec::new();
+
+        for note in self.repo.discover_notes()? {
+            let note_changed = self.edit_frontmatter(&note.path, |editor| {
+                if note.tags.iter().any(|t| tags::tag_matches(t, old)) {
+                    let mut renamed: Vec<String> = Vec::new();
+                    for tag in &note.tags {
+                        let tag = tags::rename(tag, old, new).unwrap_or_else(|| tag.clone());
+                        if !renamed.iter().any(|t| tags::normalize(t) == tags::normalize(&tag)) {
+                            renamed.push(tag);
+                        }
+                    }
 
-        Ok(())
+                    // Keep comma-separated string tags as a string
+                    let value = match editor.get("tags") {
+                        Some(serde_yaml::Value::String(_)) => serde_yaml::Value::String(renamed.join(", ")),
+                        _ => serde_yaml::Value::Sequence(
+                            renamed.into_iter().map(serde_yaml::Value::String).collect(),
+                        ),
+                    };
+                    editor.set("tags", &value)?;
+                }
+
+                let body = tags::rename_task_tags(editor.body(), old, new, &vocabulary);
+                editor.set_body(body);
+                Ok(())
+            })?;
+
+            if note_changed {
+                changed.push(note.path.clone());
+            }
+        }
+
+        Ok(changed)
+    }
+
+    /// Find problems that `doctor --fix` can repair
+    pub fn plan_repairs(&self) -> Result<repair::RepairPlan> {
+        let notes = self.repo.discover_notes()?;
+        Ok(repair::plan(&notes, &self.config.task_vocabulary()))
+    }
+
+    /// Show what a fix would do to a note, as (current content, fixed content)
+    pub fn preview_fix(&self, note_path: &Path, fix: &repair::Fix) -> Result<(String, String)> {
+        let content = self.repo.storage().read_to_string(note_path)?;
+        let fixed = fix.apply(&content);
+        Ok((content, fixed))
+    }
+
+    /// Apply a fix to a note; returns whether it changed anything
+    pub fn apply_fix(&self, note_path: &Path, fix: &repair::Fix) -> Result<bool> {
+        let (content, fixed) = self.preview_fix(note_path, fix)?;
+        if content == fixed {
+            return Ok(false);
+        }
+
+        self.repo.storage().write(note_path, &fixed)?;
+        Ok(true)
     }
 
     /// Get the library configuration
@@ -627,11 +1135,22 @@ impl BNotes {
 }
 
 // Re-export main types for convenience
-pub use config::{LibraryConfig, PeriodicConfig};
-pub use note::{Frontmatter, Note, Task};
+pub use changes::{ChangeSummary, NoteChange};
+pub use config::{AutoSyncConfig, AutoSyncMode, LibraryConfig, PeriodicConfig, TaskScoreConfig};
+pub use dependencies::{Blocker, DependencyGraph};
+pub use frontmatter::FrontmatterEditor;
+pub use ical::Calendar;
+pub use merge::{merge_notes, MergeResult};
+pub use note::{Frontmatter, Note, Task, TaskRef};
 pub use periodic::{Daily, PeriodType, Quarterly, Weekly};
+pub use properties::{PropertyFilter, PropertySort, PropertyValue};
+pub use query::{QueryContext, TaskQuery};
+pub use repair::{Fix, RepairKind, RepairPlan};
+pub use lint::{Diagnostic, LintReport, Linter, Rule, RuleLevel};
 pub use repository::{HealthReport, LinkGraph, MatchLocation, SearchMatch};
+pub use score::TaskScorer;
 pub use storage::{MemoryStorage, RealStorage, Storage};
+pub use tags::{TagFilter, TagTree};
 
 #[cfg(test)]
 mod tests {
@@ -707,6 +1226,145 @@ tags: [personal]
         assert_eq!(results[0].title, "Work Note");
     }
 
+    #[test]
+    fn test_bnotes_list_with_hierarchical_tags() {
+        let storage = Box::new(MemoryStorage::new());
+        storage
+            .write(Path::new("meeting.md"), "---\ntags: [work/meetings]\n---\n\n# Meeting")
+            .unwrap();
+        storage
+            .write(Path::new("old.md"), "---\ntags: [work, archive]\n---\n\n# Old")
+            .unwrap();
+        storage
+            .write(Path::new("home.md"), "---\ntags: [home]\n---\n\n# Home")
+            .unwrap();
+
+        let bnotes = BNotes::with_defaults(storage);
+
+        let mut titles: Vec<String> = bnotes
+            .list_notes(&["work".to_string()])
+            .unwrap()
+            .into_iter()
+            .map(|n| n.title)
+            .collect();
+        titles.sort();
+        assert_eq!(titles, vec!["Meeting", "Old"]);
+
+        let results = bnotes
+            .list_notes(&["work,home".to_string(), "!archive".to_string()])
+            .unwrap();
+        let mut titles: Vec<String> = results.into_iter().map(|n| n.title).collect();
+        titles.sort();
+        assert_eq!(titles, vec!["Home", "Meeting"]);
+    }
+
+    #[test]
+    fn test_bnotes_query_tasks_with_tags() {
+        let storage = Box::new(MemoryStorage::new());
+        storage
+            .write(
+                Path::new("tasks.md"),
+                "# Tasks\n\n- [ ] Client call @work/clientA\n- [ ] Laundry @home\n- [ ] Review @work @waiting\n",
+            )
+            .unwrap();
+
+        let bnotes = BNotes::with_defaults(storage);
+        let query = query::TaskQuery::tags(&["work".to_string(), "!waiting".to_string()]).unwrap();
+        let tasks = bnotes.query_tasks(&query, TaskSortOrder::default()).unwrap();
+
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].text, "Client call");
+    }
+
+    #[test]
+    fn test_bnotes_list_tasks_with_tags() {
+        let storage = Box::new(MemoryStorage::new());
+        storage
+            .write(Path::new("work.md"), "---\ntags: [work/clientA]\n---\n# Work\n\n- [ ] Call\n- [x] Invoice\n")
+            .unwrap();
+        storage
+            .write(Path::new("home.md"), "---\ntags: [home]\n---\n# Home\n\n- [ ] Laundry @work\n")
+            .unwrap();
+
+        let bnotes = BNotes::with_defaults(storage);
+        let texts = |status: Option<&str>| -> Vec<String> {
+            bnotes
+                .list_tasks(&["work".to_string()], status, TaskSortOrder::default())
+                .unwrap()
+                .into_iter()
+                .map(|t| t.text)
+                .collect()
+        };
+
+        // Note tags, not task tags
+        assert_eq!(texts(None), vec!["Call", "Invoice"]);
+        assert_eq!(texts(Some("open")), vec!["Call"]);
+    }
+
+    #[test]
+    fn test_bnotes_query_notes_by_properties() {
+        let storage = Box::new(MemoryStorage::new());
+        storage
+            .write(Path::new("a.md"), "---\nstatus: active\nrating: 5\n---\n\n# Alpha")
+            .unwrap();
+        storage
+            .write(Path::new("b.md"), "---\nstatus: active\nrating: 3\n---\n\n# Beta")
+            .unwrap();
+        storage
+            .write(Path::new("c.md"), "---\nstatus: done\nrating: 10\n---\n\n# Gamma")
+            .unwrap();
+
+        let bnotes = BNotes::with_defaults(storage);
+        let filters = vec![PropertyFilter::parse("status=active").unwrap()];
+        let sort = PropertySort::parse("-rating").unwrap();
+        let notes = bnotes.query_notes(&[], &filters, Some(&sort)).unwrap();
+
+        let titles: Vec<&str> = notes.iter().map(|n| n.title.as_str()).collect();
+        assert_eq!(titles, vec!["Alpha", "Beta"]);
+    }
+
+    #[test]
+    fn test_bnotes_check_health_schema_violations() {
+        let storage = Box::new(MemoryStorage::new());
+        storage
+            .write(
+                Path::new(".bnotes/config.toml"),
+                "[types.person]\nrequired = [\"email\"]\nfields.company = \"string\"\n",
+            )
+            .unwrap();
+        storage
+            .write(Path::new("ann.md"), "---\ntags: [person]\ncompany: 42\n---\n\n# Ann")
+            .unwrap();
+
+        let bnotes = BNotes::from_storage(storage).unwrap();
+        let report = bnotes.check_health().unwrap();
+
+        assert_eq!(report.schema_violations.len(), 2);
+        assert!(report.schema_violations.iter().all(|v| v.note_title == "Ann" && v.note_type == "person"));
+        assert!(report.has_issues());
+    }
+
+    #[test]
+    fn test_bnotes_lint_schema_violations() {
+        let storage = Box::new(MemoryStorage::new());
+        storage
+            .write(
+                Path::new(".bnotes/config.toml"),
+                "[types.person]\nrequired = [\"email\"]\nfields.company = \"string\"\n",
+            )
+            .unwrap();
+        storage
+            .write(Path::new("ann.md"), "---\ntags: [person]\ncompany: 42\n---\n\n# Ann")
+            .unwrap();
+
+        let bnotes = BNotes::from_storage(storage).unwrap();
+        let report = bnotes.lint().unwrap();
+        let schema: Vec<_> = report.diagnostics.iter().filter(|d| d.rule == "schema").collect();
+
+        assert_eq!(schema.len(), 2);
+        assert!(schema.iter().all(|d| d.title == "Ann" && d.message.starts_with("Type 'person'")));
+    }
+
     #[test]
     fn test_bnotes_find_by_title() {
         let storage = Box::new(MemoryStorage::new());
@@ -944,7 +1602,7 @@ title: Note 2
     }
 
     #[test]
-    fn test_bnotes_list_tasks_sorted_by_id() {
+    fn test_bnotes_list_tasks_sorted_by_note() {
         let storage = Box::new(MemoryStorage::new());
 
         // Create notes with various priority tasks
@@ -977,9 +1635,9 @@ title: B Note
             .unwrap();
 
         let bnotes = BNotes::with_defaults(storage);
-        let tasks = bnotes.list_tasks(&[], None, TaskSortOrder::parse("id").unwrap()).unwrap();
+        let tasks = bnotes.list_tasks(&[], None, TaskSortOrder::parse("note").unwrap()).unwrap();
 
-        // Should be sorted by ID (filename#index), ignoring priority
+        // Should be sorted by note title and position, ignoring priority
         assert_eq!(tasks.len(), 2);
         assert_eq!(tasks[0].note_title, "A Note");
         assert_eq!(tasks[0].index, 1);
@@ -989,6 +1647,129 @@ title: B Note
         assert_eq!(tasks[1].priority, Some("A".to_string()));
     }
 
+    #[test]
+    fn test_bnotes_list_tasks_sorted_by_score() {
+        let storage = Box::new(MemoryStorage::new());
+        storage
+            .write(Path::new(".bnotes/config.toml"), "[task_score.tags]\nsomeday = -10\n")
+            .unwrap();
+        storage
+            .write(
+                Path::new("plan.md"),
+                "# Plan\n\n- [ ] !!! Urgent but someday @someday\n- [ ] Overdue due:2020-01-01\n- [ ] ! (A) Soonish\n- [ ] Plain\n",
+            )
+            .unwrap();
+
+        let bnotes = BNotes::with_defaults(storage);
+        let tasks = bnotes.list_tasks(&[], None, TaskSortOrder::parse("score").unwrap()).unwrap();
+        let texts: Vec<&str> = tasks.iter().map(|t| t.text.as_str()).collect();
+        assert_eq!(texts, vec!["Overdue", "Soonish", "Plain", "Urgent but someday"]);
+
+        let scorer = bnotes.task_scorer().unwrap();
+        assert_eq!(scorer.score(&tasks[0]), 12.0);
+    }
+
+    #[test]
+    fn test_import_todotxt() {
+        let storage = Box::new(MemoryStorage::new());
+        storage.write(Path::new("inbox.md"), "# Inbox\n\nStuff to sort.\n").unwrap();
+        let bnotes = BNotes::with_defaults(storage);
+
+        let added = bnotes
+            .import_todotxt(Path::new("inbox.md"), "(B) Buy milk @errands\n\nx 2026-10-01 Call bank\n")
+            .unwrap();
+        assert_eq!(added, 2);
+        bnotes.import_todotxt(Path::new("inbox.md"), "Plan trip +travel\n").unwrap();
+
+        assert_eq!(
+            bnotes.repo.storage.read_to_string(Path::new("inbox.md")).unwrap(),
+            "# Inbox\n\nStuff to sort.\n\n- [ ] (B) Buy milk @errands\n- [x] Call bank done:2026-10-01\n- [ ] Plan trip @project/travel\n"
+        );
+    }
+
+    #[test]
+    fn test_export_todotxt() {
+        let storage = Box::new(MemoryStorage::new());
+        storage
+            .write(Path::new("todo.md"), "# Todo\n\n- [ ] (A) Call bank @errands\n- [>] Moved on\n- [x] Pay rent\n")
+            .unwrap();
+        let bnotes = BNotes::with_defaults(storage);
+
+        let todotxt = bnotes.export_todotxt(&TaskQuery::default()).unwrap();
+        let lines: Vec<&str> = todotxt.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("(A) Call bank"));
+        assert!(lines[1].starts_with("x "));
+
+        assert_eq!(bnotes.export_todotxt(&TaskQuery::parse("@work").unwrap()).unwrap(), "");
+    }
+
+    #[test]
+    fn test_export_ics() {
+        let storage = Box::new(MemoryStorage::new());
+        storage
+            .write(Path::new("2026-10-12.md"), "# 2026-10-12\n\n- [ ] Water plants\n- [>] Moved on\n")
+            .unwrap();
+        storage
+            .write(Path::new("projects.md"), "# Projects\n\n- [ ] Ship it due:2026-11-01 @work\n- [ ] Someday\n")
+            .unwrap();
+        let bnotes = BNotes::with_defaults(storage);
+
+        let calendar = bnotes.export_ics(&TaskQuery::default(), true, None).unwrap();
+        assert_eq!((calendar.todos(), calendar.events()), (2, 1));
+        let ics = calendar.to_ics();
+        assert!(ics.contains("SUMMARY:Water plants\r\n"));
+        assert!(ics.contains("SUMMARY:Ship it\r\n"));
+        assert!(!ics.contains("Someday"));
+        assert!(!ics.contains("URL:"));
+
+        let calendar = bnotes.export_ics(&TaskQuery::parse("@work").unwrap(), false, None).unwrap();
+        assert_eq!((calendar.todos(), calendar.events()), (1, 0));
+    }
+
+    #[test]
+    fn test_task_ids_survive_edits() {
+        let storage = Box::new(MemoryStorage::new());
+        storage
+            .write(Path::new("todo.md"), "# Todo\n\n- [ ] Write spec\n- [ ] Review ^t-beef\n")
+            .unwrap();
+        let bnotes = BNotes::with_defaults(storage);
+
+        let ids = |bnotes: &BNotes| -> Vec<String> {
+            let order = TaskSortOrder::parse("note").unwrap();
+            bnotes.list_tasks(&[], None, order).unwrap().into_iter().map(|t| t.id).collect()
+        };
+        let before = ids(&bnotes);
+        assert!(before[0].starts_with("h-"));
+        assert_eq!(before[1], "t-beef");
+
+        // Inserting a task above and completing one keeps both IDs
+        bnotes.repo.storage.write(
+            Path::new("todo.md"),
+            "# Todo\n\n- [ ] New first\n- [x] Write spec\n- [ ] Review ^t-beef\n",
+        ).unwrap();
+        assert_eq!(&ids(&bnotes)[1..], before.as_slice());
+
+        let task = bnotes.set_task_status("^t-beef", note::TaskStatus::Completed).unwrap();
+        assert_eq!(task.text, "Review");
+        assert!(bnotes.repo.storage.read_to_string(Path::new("todo.md")).unwrap().ends_with("- [x] Review ^t-beef\n"));
+
+        // Anchoring keeps the text and makes the ID independent of it
+        let anchor = bnotes.anchor_task(&before[0]).unwrap();
+        assert!(anchor.starts_with("t-") && anchor.len() == 6);
+        assert_eq!(bnotes.find_task(&anchor).unwrap().text, "Write spec");
+        assert_eq!(bnotes.anchor_all_tasks().unwrap(), 1);
+        assert_eq!(bnotes.anchor_all_tasks().unwrap(), 0);
+        assert!(bnotes.find_task("h-nothere").is_err());
+
+        // Hash IDs are 40 bits and can be shortened to a unique prefix
+        bnotes.repo.storage.write(Path::new("later.md"), "# Later\n\n- [ ] Fresh\n").unwrap();
+        let first = ids(&bnotes)[0].clone();
+        assert_eq!(first.len(), 12);
+        assert_eq!(bnotes.find_task(&first[..8]).unwrap().text, "Fresh");
+        assert!(bnotes.find_task(&first[..5]).is_err());
+    }
+
     #[test]
     fn test_task_sort_order_parse() {
         let order = TaskSortOrder::parse("urgency,priority,id").unwrap();
@@ -1116,6 +1897,79 @@ title: B Note
         assert!(old_content.contains("- [>] Already migrated task")); // Was already migrated, still marked
     }
 
+    #[test]
+    fn test_weekly_migration_carries_subtrees() {
+        use periodic::Weekly;
+
+        let storage = Box::new(MemoryStorage::new());
+        storage.write(Path::new("2026-W03.md"), "# 2026-W03
+
+## Tasks
+- [ ] Launch
+  - [x] Spec
+  - [ ] Build
+- [x] Shipped
+  - [ ] Leftover
+").unwrap();
+
+        let bnotes = BNotes::with_defaults(storage);
+        let week4 = Weekly::from_date_str("2026-W04").unwrap();
+        let (note_path, migrated_count) = bnotes.create_weekly_with_migration(week4, None, false).unwrap();
+        assert_eq!(migrated_count, 3);
+
+        let content = bnotes.repo.storage.read_to_string(&note_path).unwrap();
+        assert!(content.contains("- [ ] Launch\n  - [x] Spec\n  - [ ] Build\n- [ ] Leftover"));
+        assert!(!content.contains("Shipped"));
+
+        let old_content = bnotes.repo.storage.read_to_string(Path::new("2026-W03.md")).unwrap();
+        assert!(old_content.contains("- [>] Launch\n  - [x] Spec\n  - [>] Build\n- [x] Shipped\n  - [>] Leftover"));
+    }
+
+    #[test]
+    fn test_task_status_vocabulary() {
+        use periodic::Weekly;
+
+        let storage = Box::new(MemoryStorage::new());
+        storage.write(Path::new(".bnotes/config.toml"), "[task_statuses]\n\"!\" = { name = \"blocked\" }\n").unwrap();
+        storage.write(Path::new("2026-W03.md"), "# 2026-W03
+
+- [/] Drafting
+- [?] Waiting on review
+- [-] Dropped idea
+- [!] Stuck
+- [~] Not a task
+").unwrap();
+        let bnotes = BNotes::with_defaults(storage);
+
+        let texts = |status: &str| -> Vec<String> {
+            bnotes.list_tasks(&[], Some(status), TaskSortOrder::parse("note").unwrap())
+                .unwrap()
+                .into_iter()
+                .map(|t| t.text)
+                .collect()
+        };
+        assert_eq!(texts("all").len(), 4);
+        assert_eq!(texts("open"), vec!["Drafting", "Waiting on review", "Stuck"]);
+        assert_eq!(texts("cancelled"), vec!["Dropped idea"]);
+        assert_eq!(texts("blocked"), vec!["Stuck"]);
+        assert_eq!(texts("/"), vec!["Drafting"]);
+        assert!(bnotes.list_tasks(&[], Some("bogus"), TaskSortOrder::default()).is_err());
+
+        // Configured markers count as tasks when linking to notes
+        bnotes.repo.storage.write(Path::new("plan.md"), "# Plan\n").unwrap();
+        bnotes.repo.storage.write(Path::new("asks.md"), "# Asks\n\n- [!] Unblock [[Plan]]\n").unwrap();
+        let linking: Vec<String> = bnotes.get_linking_tasks("Plan").unwrap().into_iter().map(|t| t.text).collect();
+        assert_eq!(linking, vec!["Unblock [[Plan]]"]);
+
+        // Only open-like statuses are migrated, and they keep their marker
+        let week4 = Weekly::from_date_str("2026-W04").unwrap();
+        let (note_path, migrated_count) = bnotes.create_weekly_with_migration(week4, None, false).unwrap();
+        assert_eq!(migrated_count, 3);
+        let content = bnotes.repo.storage.read_to_string(&note_path).unwrap();
+        assert!(content.contains("- [/] Drafting\n- [?] Waiting on review\n- [!] Stuck"));
+        assert!(!content.contains("Dropped idea"));
+    }
+
     #[test]
     fn test_weekly_migration_no_previous_note() {
         use periodic::{PeriodType, Weekly};
@@ -1229,27 +2083,121 @@ Content here.
     }
 
     #[test]
-    fn test_frontmatter_parsing_correctness() {
+    fn test_update_timestamp_preserves_formatting() {
         let storage = Box::new(MemoryStorage::new());
+        let original = "---\n# Project notes\ntitle: 'Roadmap'\ntags:\n  - work\nupdated: 2024-01-01T00:00:00Z # auto\nrating: 4\n---\n\n# Roadmap\n";
+        storage.write(Path::new("roadmap.md"), original).unwrap();
+
         let bnotes = BNotes::with_defaults(storage);
+        bnotes.update_note_timestamp(Path::new("roadmap.md")).unwrap();
 
-        let content = r#"---
-tags: [test, example]
-created: 2024-01-01T00:00:00Z
----
+        let content = bnotes.repo.storage.read_to_string(Path::new("roadmap.md")).unwrap();
+        assert!(content.starts_with("---\n# Project notes\ntitle: 'Roadmap'\ntags:\n  - work\nupdated: "));
+        assert!(content.ends_with(" # auto\nrating: 4\n---\n\n# Roadmap\n"));
+        assert!(!content.contains("2024-01-01T00:00:00Z"));
+    }
 
-# Test Note
+    #[test]
+    fn test_set_and_unset_note_property() {
+        let storage = Box::new(MemoryStorage::new());
+        storage.write(Path::new("book.md"), "---\ntags: [book]\n---\n# Book\n").unwrap();
+
+        let bnotes = BNotes::with_defaults(storage);
+        assert!(bnotes.set_note_property(Path::new("book.md"), "rating", "4").unwrap());
+        assert!(!bnotes.set_note_property(Path::new("book.md"), "rating", "4").unwrap());
+
+        let note = &bnotes.find_note_by_title("Book").unwrap()[0];
+        assert_eq!(note.property("rating"), Some(PropertyValue::Number(4.0)));
+
+        assert!(bnotes.unset_note_property(Path::new("book.md"), "rating").unwrap());
+        let content = bnotes.repo.storage.read_to_string(Path::new("book.md")).unwrap();
+        assert_eq!(content, "---\ntags: [book]\n---\n# Book\n");
+    }
+
+    #[test]
+    fn test_rename_tag() {
+        let storage = Box::new(MemoryStorage::new());
+        storage.write(Path::new("a.md"), "---\ntags: [work/meetings, home]\n---\n# A\n\n- [ ] Prep @work/meetings\n").unwrap();
+        storage.write(Path::new("b.md"), "---\ntags: \"work, job\"\n---\n# B\n").unwrap();
+        storage.write(Path::new("c.md"), "---\ntags: [workshop]\n---\n# C\n").unwrap();
+
+        let bnotes = BNotes::with_defaults(storage);
+        let changed = bnotes.rename_tag("work", "job").unwrap();
+        assert_eq!(changed.len(), 2);
+
+        let a = bnotes.repo.storage.read_to_string(Path::new("a.md")).unwrap();
+        assert_eq!(a, "---\ntags: [job/meetings, home]\n---\n# A\n\n- [ ] Prep @job/meetings\n");
+
+        let b = bnotes.repo.storage.read_to_string(Path::new("b.md")).unwrap();
+        assert_eq!(b, "---\ntags: \"job\"\n---\n# B\n");
+
+        let c = bnotes.repo.storage.read_to_string(Path::new("c.md")).unwrap();
+        assert_eq!(c, "---\ntags: [workshop]\n---\n# C\n");
+    }
+
+    #[test]
+    fn test_content_hash_ignores_updated() {
+        let a = "---\ntags: [a]\nupdated: 2024-01-01T00:00:00Z\n---\n# A\n";
+        let b = "---\ntags: [a]\nupdated: 2025-06-01T12:00:00Z\n---\n# A\n";
+        let c = "---\ntags: [a]\nupdated: 2024-01-01T00:00:00Z\n---\n# A\n\nMore\n";
+
+        assert_eq!(content_hash(a), content_hash(b));
+        assert_ne!(content_hash(a), content_hash(c));
+    }
+
+    #[test]
+    fn test_touch_note() {
+        let storage = Box::new(MemoryStorage::new());
+        let previous = "---\nupdated: 2024-01-01T00:00:00Z\n---\n# A\n";
+        storage.write(Path::new("a.md"), "---\nupdated: 2024-01-01T00:00:00Z\n---\n# A\n").unwrap();
+
+        let bnotes = BNotes::with_defaults(storage);
+
+        // Unchanged content leaves the timestamp alone
+        assert!(!bnotes.touch_note(Path::new("a.md"), Some(previous)).unwrap());
+
+        // Edited content bumps it
+        bnotes.repo.storage.write(Path::new("a.md"), "---\nupdated: 2024-01-01T00:00:00Z\n---\n# A\n\nEdited\n").unwrap();
+        assert!(bnotes.touch_note(Path::new("a.md"), Some(previous)).unwrap());
+        let content = bnotes.repo.storage.read_to_string(Path::new("a.md")).unwrap();
+        assert!(!content.contains("2024-01-01"));
+
+        // Already touched since `previous`
+        assert!(!bnotes.touch_note(Path::new("a.md"), Some(previous)).unwrap());
+
+        // New notes always get a timestamp
+        bnotes.repo.storage.write(Path::new("b.md"), "---\ntags: [b]\n---\n# B\n").unwrap();
+        assert!(bnotes.touch_note(Path::new("b.md"), None).unwrap());
+    }
+
+    #[test]
+    fn test_date_backfill() {
+        let storage = Box::new(MemoryStorage::new());
+        storage.write(Path::new("bare.md"), "# Bare\n").unwrap();
+        storage.write(Path::new("half.md"), "---\ncreated: 2023-05-01T00:00:00Z\n---\n# Half\n").unwrap();
+        storage.write(Path::new("full.md"), "---\ncreated: 2023-05-01T00:00:00Z\nupdated: 2023-06-01T00:00:00Z\n---\n# Full\n").unwrap();
+
+        let bnotes = BNotes::with_defaults(storage);
+        let first = "2024-01-01T09:00:00Z".parse().unwrap();
+        let last = "2024-02-01T09:00:00Z".parse().unwrap();
+
+        let mut backfills = bnotes.plan_date_backfill(|_| Ok(Some((first, last)))).unwrap();
+        backfills.sort_by(|a, b| a.title.cmp(&b.title));
+        assert_eq!(backfills.len(), 2);
+        assert_eq!(backfills[0].title, "Bare");
+        assert_eq!(backfills[0].created, Some(first));
+        assert_eq!(backfills[1].title, "Half");
+        assert_eq!(backfills[1].created, None);
+        assert_eq!(backfills[1].updated, Some(last));
+
+        bnotes.apply_date_backfill(&backfills).unwrap();
 
-Body content here.
-More content.
-"#;
+        let bare = bnotes.repo.storage.read_to_string(Path::new("bare.md")).unwrap();
+        assert_eq!(bare, "---\ncreated: 2024-01-01T09:00:00Z\nupdated: 2024-02-01T09:00:00Z\n---\n# Bare\n");
 
-        let (frontmatter, body) = bnotes.parse_frontmatter(content).unwrap();
+        let half = bnotes.repo.storage.read_to_string(Path::new("half.md")).unwrap();
+        assert_eq!(half, "---\ncreated: 2023-05-01T00:00:00Z\nupdated: 2024-02-01T09:00:00Z\n---\n# Half\n");
 
-        assert!(frontmatter.is_some(), "Should parse frontmatter");
-        let fm = frontmatter.unwrap();
-        assert_eq!(fm.tags, vec!["test", "example"]);
-        assert!(fm.created.is_some());
-        assert_eq!(body.trim(), "# Test Note\n\nBody content here.\nMore content.");
+        assert!(bnotes.plan_date_backfill(|_| Ok(Some((first, last)))).unwrap().is_empty());
     }
 }
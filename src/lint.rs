@@ -0,0 +1,1138 @@
+//! Configurable lint rules for note collections
+//!
+//! Each check `doctor` runs is a [`Rule`] with an id like `broken-link`. Rules
+//! can be turned off or given another severity in the `[lint]` table of
+//! `.bnotes/config.toml`:
+//!
+//! ```toml
+//! [lint]
+//! orphan = "off"
+//! missing-tags = "off"
+//! missing-frontmatter = "error"
+//! ```
+//!
+//! A note can opt out of rules with `lint-ignore` in its frontmatter, either a
+//! list of rule ids or `all`.
+
+use crate::config::LibraryConfig;
+use crate::dependencies::DependencyGraph;
+use crate::frontmatter::FrontmatterEditor;
+use crate::note::{extract_tasks_from_notes, parse_datetime, Frontmatter, Note, TaskVocabulary};
+use crate::periodic::{Daily, PeriodType, Quarterly, Weekly};
+use crate::repository::{extract_wiki_links, HealthReport, LinkGraph, NoteLoadError};
+use crate::schema;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Frontmatter key listing rules a note opts out of
+pub const SUPPRESS_KEY: &str = "lint-ignore";
+
+/// How seriously a rule's findings are taken
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleLevel {
+    Off,
+    #[serde(alias = "warn")]
+    Warning,
+    Error,
+}
+
+/// A problem found in a note
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Diagnostic {
+    pub rule: String,
+    pub severity: RuleLevel,
+    pub path: PathBuf,
+    pub title: String,
+    /// 1-based line in the note's file, when the problem has one
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+/// A problem reported by a rule, before its severity is applied
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    pub path: PathBuf,
+    pub title: String,
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl Finding {
+    pub fn new(note: &Note, message: impl Into<String>) -> Self {
+        Self {
+            path: note.path.clone(),
+            title: note.title.clone(),
+            line: None,
+            message: message.into(),
+        }
+    }
+
+    /// A problem with a file that isn't loaded as a note
+    pub fn for_file(path: &Path, message: impl Into<String>) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            title: path.file_stem().and_then(|s| s.to_str()).unwrap_or_default().to_string(),
+            line: None,
+            message: message.into(),
+        }
+    }
+
+    pub fn at_line(mut self, line: usize) -> Self {
+        self.line = Some(line);
+        self
+    }
+}
+
+/// What rules get to look at
+pub struct LintContext<'a> {
+    pub notes: &'a [Note],
+    /// Files in the notes directory that weren't loaded as notes
+    pub skipped_files: &'a [PathBuf],
+    /// `.md` files that couldn't be loaded
+    pub load_errors: &'a [NoteLoadError],
+    pub graph: LinkGraph,
+    pub config: &'a LibraryConfig,
+}
+
+/// A check run over the whole note collection
+pub trait Rule {
+    /// Identifier used in config and `lint-ignore`, e.g. `broken-link`
+    fn id(&self) -> &'static str;
+
+    /// One-line description of what the rule checks
+    fn description(&self) -> &'static str;
+
+    /// Level used when the config doesn't set one
+    fn default_level(&self) -> RuleLevel;
+
+    fn check(&self, ctx: &LintContext) -> Vec<Finding>;
+}
+
+/// Result of running a [`Linter`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LintReport {
+    pub notes_checked: usize,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl LintReport {
+    pub fn error_count(&self) -> usize {
+        self.diagnostics.iter().filter(|d| d.severity == RuleLevel::Error).count()
+    }
+
+    pub fn warning_count(&self) -> usize {
+        self.diagnostics.iter().filter(|d| d.severity == RuleLevel::Warning).count()
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.error_count() > 0
+    }
+}
+
+/// A set of rules to run
+pub struct Linter {
+    rules: Vec<Box<dyn Rule>>,
+}
+
+impl Default for Linter {
+    fn default() -> Self {
+        Self::with_builtin_rules()
+    }
+}
+
+impl Linter {
+    /// A linter without any rules
+    pub fn new() -> Self {
+        Self { rules: Vec::new() }
+    }
+
+    /// A linter with every rule that ships with bnotes
+    pub fn with_builtin_rules() -> Self {
+        let mut linter = Self::new();
+        linter.add_rule(Box::new(BrokenLinks));
+        linter.add_rule(Box::new(DuplicateTitles));
+        linter.add_rule(Box::new(SchemaViolations));
+        linter.add_rule(Box::new(UnreadableNotes));
+        linter.add_rule(Box::new(InvalidFrontmatter));
+        linter.add_rule(Box::new(InvalidDates));
+        linter.add_rule(Box::new(DependencyCycles));
+        linter.add_rule(Box::new(MissingTags));
+        linter.add_rule(Box::new(MissingFrontmatter));
+        linter.add_rule(Box::new(Orphans));
+        linter.add_rule(Box::new(MalformedTasks));
+        linter.add_rule(Box::new(EmptyNotes));
+        linter.add_rule(Box::new(PeriodicFilenames));
+        linter.add_rule(Box::new(MarkdownExtensions));
+        linter
+    }
+
+    pub fn add_rule(&mut self, rule: Box<dyn Rule>) {
+        self.rules.push(rule);
+    }
+
+    pub fn rules(&self) -> impl Iterator<Item = &dyn Rule> {
+        self.rules.iter().map(|rule| rule.as_ref())
+    }
+
+    /// Level a rule runs at under the given config
+    pub fn level(rule: &dyn Rule, config: &LibraryConfig) -> RuleLevel {
+        config.lint.get(rule.id()).copied().unwrap_or_else(|| rule.default_level())
+    }
+
+    /// Run every enabled rule, dropping findings suppressed by the notes themselves
+    pub fn run(&self, notes: &[Note], config: &LibraryConfig) -> LintReport {
+        self.run_with_files(notes, &[], &[], config)
+    }
+
+    /// Like [`Linter::run`], also checking files that weren't loaded as notes
+    /// and `.md` files that failed to load
+    pub fn run_with_files(
+        &self,
+        notes: &[Note],
+        skipped_files: &[PathBuf],
+        load_errors: &[NoteLoadError],
+        config: &LibraryConfig,
+    ) -> LintReport {
+        let ctx = LintContext {
+            notes,
+            skipped_files,
+            load_errors,
+            graph: LinkGraph::build(notes, &config.task_vocabulary()),
+            config,
+        };
+
+        let suppressed: HashMap<&PathBuf, Vec<String>> =
+            notes.iter().map(|note| (&note.path, suppressed_rules(note))).collect();
+        let is_suppressed = |finding: &Finding, rule: &str| {
+            suppressed
+                .get(&finding.path)
+                .is_some_and(|rules| rules.iter().any(|r| r == rule || r == "all"))
+        };
+
+        let mut diagnostics = Vec::new();
+        for rule in &self.rules {
+            let severity = Self::level(rule.as_ref(), config);
+            if severity == RuleLevel::Off {
+                continue;
+            }
+
+            for finding in rule.check(&ctx) {
+                if is_suppressed(&finding, rule.id()) {
+                    continue;
+                }
+                diagnostics.push(Diagnostic {
+                    rule: rule.id().to_string(),
+                    severity,
+                    path: finding.path,
+                    title: finding.title,
+                    line: finding.line,
+                    message: finding.message,
+                });
+            }
+        }
+
+        LintReport {
+            notes_checked: notes.len(),
+            diagnostics,
+        }
+    }
+}
+
+/// Rule ids listed in a note's `lint-ignore` (a list or comma-separated string)
+fn suppressed_rules(note: &Note) -> Vec<String> {
+    match note.properties.get(SUPPRESS_KEY) {
+        Some(serde_yaml::Value::Sequence(items)) => items
+            .iter()
+            .filter_map(|item| item.as_str())
+            .map(|s| s.trim().to_lowercase())
+            .collect(),
+        Some(serde_yaml::Value::String(s)) => s.split(',').map(|r| r.trim().to_lowercase()).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// 1-based line of the first occurrence of `needle`
+fn line_of(content: &str, needle: &str) -> Option<usize> {
+    content.lines().position(|line| line.contains(needle)).map(|i| i + 1)
+}
+
+/// 1-based line of a top-level frontmatter key
+fn frontmatter_key_line(content: &str, key: &str) -> Option<usize> {
+    let prefix = format!("{}:", key);
+    content
+        .lines()
+        .enumerate()
+        .skip(1)
+        .take_while(|(_, line)| !matches!(line.trim_end(), "---" | "..."))
+        .find(|(_, line)| line.starts_with(&prefix))
+        .map(|(i, _)| i + 1)
+}
+
+/// Numbered lines of a note's body, skipping frontmatter and fenced code
+fn body_lines(content: &str) -> Vec<(usize, &str)> {
+    let mut lines = content.lines().enumerate().map(|(i, line)| (i + 1, line)).peekable();
+
+    if lines.peek().is_some_and(|(_, line)| line.trim_end() == "---")
+        && FrontmatterEditor::parse(content).has_frontmatter()
+    {
+        lines.next();
+        for (_, line) in lines.by_ref() {
+            if matches!(line.trim_end(), "---" | "...") {
+                break;
+            }
+        }
+    }
+
+    let mut in_fence = false;
+    lines
+        .filter(|(_, line)| {
+            let trimmed = line.trim_start();
+            if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+                in_fence = !in_fence;
+                return false;
+            }
+            !in_fence
+        })
+        .collect()
+}
+
+/// serde_yaml error text without its location, which is relative to the YAML block
+fn yaml_error_message(error: &serde_yaml::Error) -> String {
+    let message = error.to_string();
+    match message.find(" at line ") {
+        Some(pos) => message[..pos].to_string(),
+        None => message,
+    }
+}
+
+// ============================================================================
+// Built-in rules
+// ============================================================================
+
+/// Wiki links to titles no note has
+pub struct BrokenLinks;
+
+impl Rule for BrokenLinks {
+    fn id(&self) -> &'static str {
+        "broken-link"
+    }
+
+    fn description(&self) -> &'static str {
+        "Wiki links must point at an existing note"
+    }
+
+    fn default_level(&self) -> RuleLevel {
+        RuleLevel::Error
+    }
+
+    fn check(&self, ctx: &LintContext) -> Vec<Finding> {
+        let titles: HashSet<String> = ctx.notes.iter().map(|note| note.title.to_lowercase()).collect();
+
+        let mut findings = Vec::new();
+        for note in ctx.notes {
+            for target in extract_wiki_links(&note.content) {
+                if titles.contains(&target.to_lowercase()) {
+                    continue;
+                }
+                let link = format!("[[{}]]", target);
+                let mut finding = Finding::new(note, format!("Broken link {}", link));
+                if let Some(line) = line_of(&note.content, &link) {
+                    finding = finding.at_line(line);
+                }
+                findings.push(finding);
+            }
+        }
+        findings
+    }
+}
+
+/// Several notes with the same title, which makes links to it ambiguous
+pub struct DuplicateTitles;
+
+impl Rule for DuplicateTitles {
+    fn id(&self) -> &'static str {
+        "duplicate-title"
+    }
+
+    fn description(&self) -> &'static str {
+        "Every note must have a unique title"
+    }
+
+    fn default_level(&self) -> RuleLevel {
+        RuleLevel::Error
+    }
+
+    fn check(&self, ctx: &LintContext) -> Vec<Finding> {
+        let mut by_title: HashMap<String, Vec<&Note>> = HashMap::new();
+        for note in ctx.notes {
+            by_title.entry(note.title.to_lowercase()).or_default().push(note);
+        }
+
+        ctx.notes
+            .iter()
+            .filter_map(|note| {
+                let others: Vec<String> = by_title[&note.title.to_lowercase()]
+                    .iter()
+                    .filter(|other| other.path != note.path)
+                    .map(|other| other.path.display().to_string())
+                    .collect();
+                if others.is_empty() {
+                    return None;
+                }
+                Some(Finding::new(
+                    note,
+                    format!("Title '{}' is also used by {}", note.title, others.join(", ")),
+                ))
+            })
+            .collect()
+    }
+}
+
+/// Frontmatter that doesn't match the note's configured type
+pub struct SchemaViolations;
+
+impl Rule for SchemaViolations {
+    fn id(&self) -> &'static str {
+        "schema"
+    }
+
+    fn description(&self) -> &'static str {
+        "Frontmatter must match the note's type schema"
+    }
+
+    fn default_level(&self) -> RuleLevel {
+        RuleLevel::Error
+    }
+
+    fn check(&self, ctx: &LintContext) -> Vec<Finding> {
+        ctx.notes
+            .iter()
+            .flat_map(|note| {
+                schema::validate_notes(std::slice::from_ref(note), &ctx.config.types)
+                    .into_iter()
+                    .map(move |violation| {
+                        Finding::new(note, format!("Type '{}': {}", violation.note_type, violation.kind))
+                    })
+            })
+            .collect()
+    }
+}
+
+/// `.md` files that couldn't be read or parsed, so no other rule sees them
+pub struct UnreadableNotes;
+
+impl Rule for UnreadableNotes {
+    fn id(&self) -> &'static str {
+        "unreadable-note"
+    }
+
+    fn description(&self) -> &'static str {
+        "Markdown files must be readable UTF-8 text to be loaded as notes"
+    }
+
+    fn default_level(&self) -> RuleLevel {
+        RuleLevel::Error
+    }
+
+    fn check(&self, ctx: &LintContext) -> Vec<Finding> {
+        ctx.load_errors
+            .iter()
+            .map(|error| {
+                let finding = Finding::for_file(&error.path, error.message.clone());
+                match error.line {
+                    Some(line) => finding.at_line(line),
+                    None => finding,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Frontmatter that isn't valid YAML or has keys of the wrong shape
+pub struct InvalidFrontmatter;
+
+impl Rule for InvalidFrontmatter {
+    fn id(&self) -> &'static str {
+        "invalid-frontmatter"
+    }
+
+    fn description(&self) -> &'static str {
+        "Frontmatter must parse, or the note loses its title, tags and dates"
+    }
+
+    fn default_level(&self) -> RuleLevel {
+        RuleLevel::Error
+    }
+
+    fn check(&self, ctx: &LintContext) -> Vec<Finding> {
+        ctx.notes
+            .iter()
+            .filter_map(|note| {
+                let editor = FrontmatterEditor::parse(&note.content);
+                if !editor.has_frontmatter() {
+                    return None;
+                }
+
+                match serde_yaml::from_str::<serde_yaml::Value>(&editor.yaml()) {
+                    Err(e) => {
+                        // The YAML starts on the line after the opening `---`
+                        let line = e.location().map(|loc| loc.line() + 1).unwrap_or(1);
+                        Some(
+                            Finding::new(note, format!("Frontmatter is not valid YAML: {}", yaml_error_message(&e)))
+                                .at_line(line),
+                        )
+                    }
+                    Ok(serde_yaml::Value::Null) => None,
+                    Ok(serde_yaml::Value::Mapping(mut mapping)) => {
+                        // Bad dates are reported by `invalid-date`
+                        mapping.remove("created");
+                        mapping.remove("updated");
+                        serde_yaml::from_value::<Frontmatter>(serde_yaml::Value::Mapping(mapping))
+                            .err()
+                            .map(|e| Finding::new(note, format!("Invalid frontmatter: {}", e)).at_line(1))
+                    }
+                    Ok(_) => Some(Finding::new(note, "Frontmatter is not a list of key: value pairs").at_line(1)),
+                }
+            })
+            .collect()
+    }
+}
+
+/// `created` and `updated` values that aren't dates
+pub struct InvalidDates;
+
+impl Rule for InvalidDates {
+    fn id(&self) -> &'static str {
+        "invalid-date"
+    }
+
+    fn description(&self) -> &'static str {
+        "Dates must be RFC3339 or YYYY-MM-DD"
+    }
+
+    fn default_level(&self) -> RuleLevel {
+        RuleLevel::Error
+    }
+
+    fn check(&self, ctx: &LintContext) -> Vec<Finding> {
+        let mut findings = Vec::new();
+        for note in ctx.notes {
+            let editor = FrontmatterEditor::parse(&note.content);
+            for key in ["created", "updated"] {
+                let valid = match editor.get(key) {
+                    None | Some(serde_yaml::Value::Null) => continue,
+                    Some(serde_yaml::Value::String(s)) => parse_datetime(&s).is_some(),
+                    Some(_) => false,
+                };
+                if valid {
+                    continue;
+                }
+
+                let raw = editor.entry_lines(key).and_then(|lines| lines.first().cloned()).unwrap_or_default();
+                let value = raw.split_once(':').map(|(_, v)| v.trim()).unwrap_or_default();
+                let mut finding = Finding::new(note, format!("Invalid {} date '{}'", key, value));
+                if let Some(line) = frontmatter_key_line(&note.content, key) {
+                    finding = finding.at_line(line);
+                }
+                findings.push(finding);
+            }
+        }
+        findings
+    }
+}
+
+/// Tasks that wait on each other, directly or through other tasks
+pub struct DependencyCycles;
+
+impl Rule for DependencyCycles {
+    fn id(&self) -> &'static str {
+        "dependency-cycle"
+    }
+
+    fn description(&self) -> &'static str {
+        "Task dependencies must not form a cycle"
+    }
+
+    fn default_level(&self) -> RuleLevel {
+        RuleLevel::Error
+    }
+
+    fn check(&self, ctx: &LintContext) -> Vec<Finding> {
+        let tasks = extract_tasks_from_notes(ctx.notes, &ctx.config.task_vocabulary());
+        let graph = DependencyGraph::build(tasks);
+
+        graph
+            .cycles()
+            .into_iter()
+            .map(|cycle| {
+                let first = cycle[0];
+                let mut steps: Vec<String> = cycle.iter().map(|task| format!("{} (^{})", task.text, task.id)).collect();
+                steps.push(first.text.clone());
+                Finding {
+                    path: first.note_path.clone(),
+                    title: first.note_title.clone(),
+                    line: Some(first.line),
+                    message: format!("Dependency cycle: {}", steps.join(" -> ")),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Notes without tags
+pub struct MissingTags;
+
+impl Rule for MissingTags {
+    fn id(&self) -> &'static str {
+        "missing-tags"
+    }
+
+    fn description(&self) -> &'static str {
+        "Every note should have at least one tag"
+    }
+
+    fn default_level(&self) -> RuleLevel {
+        RuleLevel::Warning
+    }
+
+    fn check(&self, ctx: &LintContext) -> Vec<Finding> {
+        ctx.notes
+            .iter()
+            .filter(|note| note.tags.is_empty())
+            .map(|note| Finding::new(note, "No tags"))
+            .collect()
+    }
+}
+
+/// Notes with no tags and no dates
+pub struct MissingFrontmatter;
+
+impl Rule for MissingFrontmatter {
+    fn id(&self) -> &'static str {
+        "missing-frontmatter"
+    }
+
+    fn description(&self) -> &'static str {
+        "Every note should have frontmatter with tags or dates"
+    }
+
+    fn default_level(&self) -> RuleLevel {
+        RuleLevel::Warning
+    }
+
+    fn check(&self, ctx: &LintContext) -> Vec<Finding> {
+        ctx.notes
+            .iter()
+            .filter(|note| note.tags.is_empty() && note.created.is_none() && note.updated.is_none())
+            .map(|note| Finding::new(note, "No frontmatter").at_line(1))
+            .collect()
+    }
+}
+
+/// Notes with no links in or out and no tags
+pub struct Orphans;
+
+impl Rule for Orphans {
+    fn id(&self) -> &'static str {
+        "orphan"
+    }
+
+    fn description(&self) -> &'static str {
+        "Every note should be linked or tagged"
+    }
+
+    fn default_level(&self) -> RuleLevel {
+        RuleLevel::Warning
+    }
+
+    fn check(&self, ctx: &LintContext) -> Vec<Finding> {
+        let titles: Vec<String> = ctx.notes.iter().map(|note| note.title.clone()).collect();
+        let orphaned = ctx.graph.orphaned_notes(&titles);
+
+        ctx.notes
+            .iter()
+            .filter(|note| note.tags.is_empty() && orphaned.contains(&note.title))
+            .map(|note| Finding::new(note, "No links and no tags"))
+            .collect()
+    }
+}
+
+/// List items that look like tasks but aren't parsed as one the way they were meant
+pub struct MalformedTasks;
+
+impl Rule for MalformedTasks {
+    fn id(&self) -> &'static str {
+        "malformed-task"
+    }
+
+    fn description(&self) -> &'static str {
+        "Task checkboxes must be well-formed"
+    }
+
+    fn default_level(&self) -> RuleLevel {
+        RuleLevel::Warning
+    }
+
+    fn check(&self, ctx: &LintContext) -> Vec<Finding> {
+        let vocabulary = ctx.config.task_vocabulary();
+        ctx.notes
+            .iter()
+            .flat_map(|note| {
+                let vocabulary = &vocabulary;
+                body_lines(&note.content)
+                    .into_iter()
+                    .filter_map(move |(number, line)| {
+                        task_problem(line, vocabulary).map(|problem| Finding::new(note, problem).at_line(number))
+                    })
+            })
+            .collect()
+    }
+}
+
+/// What's wrong with a task-like list item, if anything
+fn task_problem(line: &str, vocabulary: &TaskVocabulary) -> Option<String> {
+    let item = line.trim_start();
+    let rest = match item.strip_prefix(['-', '*', '+']) {
+        Some(rest) => rest,
+        None => {
+            let digits = item.len() - item.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+            if digits == 0 {
+                return None;
+            }
+            item[digits..].strip_prefix(['.', ')'])?
+        }
+    };
+
+    let spaced = rest.starts_with([' ', '\t']);
+    let mut chars = rest.trim_start().chars();
+    if chars.next() != Some('[') {
+        return None;
+    }
+    let status = chars.next()?;
+    if chars.next() != Some(']') {
+        return None;
+    }
+    let text = chars.as_str();
+
+    if !spaced {
+        Some("Missing space between list marker and checkbox".to_string())
+    } else if vocabulary.status(status).is_none() {
+        Some(format!("Unknown task status '[{}]'", status))
+    } else if !text.is_empty() && !text.starts_with([' ', '\t']) {
+        Some("Missing space after checkbox".to_string())
+    } else if text.trim().is_empty() {
+        Some("Task has no text".to_string())
+    } else {
+        None
+    }
+}
+
+/// Notes with nothing after their frontmatter
+pub struct EmptyNotes;
+
+impl Rule for EmptyNotes {
+    fn id(&self) -> &'static str {
+        "empty-note"
+    }
+
+    fn description(&self) -> &'static str {
+        "Notes should have content"
+    }
+
+    fn default_level(&self) -> RuleLevel {
+        RuleLevel::Warning
+    }
+
+    fn check(&self, ctx: &LintContext) -> Vec<Finding> {
+        ctx.notes
+            .iter()
+            .filter(|note| FrontmatterEditor::parse(&note.content).body().trim().is_empty())
+            .map(|note| Finding::new(note, "Note is empty"))
+            .collect()
+    }
+}
+
+/// Filenames that look like daily, weekly or quarterly notes but aren't named like them
+pub struct PeriodicFilenames;
+
+impl Rule for PeriodicFilenames {
+    fn id(&self) -> &'static str {
+        "periodic-filename"
+    }
+
+    fn description(&self) -> &'static str {
+        "Periodic notes must be named YYYY-MM-DD, YYYY-Www or YYYY-Qn"
+    }
+
+    fn default_level(&self) -> RuleLevel {
+        RuleLevel::Warning
+    }
+
+    fn check(&self, ctx: &LintContext) -> Vec<Finding> {
+        ctx.notes
+            .iter()
+            .filter_map(|note| {
+                let stem = note.path.file_stem()?.to_str()?;
+                let message = match periodic_name(stem)? {
+                    Some(expected) if expected == stem => return None,
+                    Some(expected) => format!("'{}' looks like a periodic note; expected '{}.md'", stem, expected),
+                    None => format!("'{}' looks like a periodic note but isn't a valid date, week or quarter", stem),
+                };
+                Some(Finding::new(note, message))
+            })
+            .collect()
+    }
+}
+
+/// The periodic note identifier a filename stem means, if it looks like one
+///
+/// Returns `None` for ordinary names and `Some(None)` for date-like names that
+/// don't name a real period, like `2026-02-30`.
+fn periodic_name(stem: &str) -> Option<Option<String>> {
+    if !stem.starts_with(|c: char| c.is_ascii_digit())
+        || !stem.chars().all(|c| c.is_ascii_digit() || "-_. WwQq".contains(c))
+    {
+        return None;
+    }
+
+    // Split into digit runs and W/Q markers, dropping separators
+    let mut tokens: Vec<String> = Vec::new();
+    for c in stem.chars() {
+        match c {
+            '0'..='9' => match tokens.last_mut() {
+                Some(last) if last.starts_with(|c: char| c.is_ascii_digit()) => last.push(c),
+                _ => tokens.push(c.to_string()),
+            },
+            'W' | 'w' | 'Q' | 'q' => tokens.push(c.to_ascii_uppercase().to_string()),
+            _ => tokens.push(String::new()),
+        }
+    }
+    tokens.retain(|token| !token.is_empty());
+
+    let tokens: Vec<&str> = tokens.iter().map(String::as_str).collect();
+    let number = |s: &str| s.parse::<u32>().ok();
+    let daily = |y: &str, m: &str, d: &str| {
+        NaiveDate::from_ymd_opt(y.parse().ok()?, number(m)?, number(d)?).map(|date| Daily::from_date(date).identifier())
+    };
+
+    let name = match tokens.as_slice() {
+        [date] if date.len() == 8 => daily(&date[..4], &date[4..6], &date[6..]),
+        [y, "W", w] if y.len() == 4 => NaiveDate::from_isoywd_opt(y.parse().ok()?, number(w)?, chrono::Weekday::Mon)
+            .map(|monday| Weekly::from_date(monday).identifier()),
+        [y, "Q", q] if y.len() == 4 => number(q)
+            .filter(|q| (1..=4).contains(q))
+            .and_then(|q| NaiveDate::from_ymd_opt(y.parse().ok()?, q * 3, 1))
+            .map(|date| Quarterly::from_date(date).identifier()),
+        [y, m, d] if y.len() == 4 && m.len() <= 2 && d.len() <= 2 => daily(y, m, d),
+        [_, _, y] if y.len() == 4 => None,
+        _ => return None,
+    };
+    Some(name)
+}
+
+/// Markdown files with an extension other than `.md`, which are never loaded
+pub struct MarkdownExtensions;
+
+/// Extensions commonly used for markdown besides `md`
+const MARKDOWN_EXTENSIONS: &[&str] = &["md", "markdown", "mdown", "mkd", "mkdn", "mdwn", "mdtxt", "mdtext"];
+
+impl Rule for MarkdownExtensions {
+    fn id(&self) -> &'static str {
+        "markdown-extension"
+    }
+
+    fn description(&self) -> &'static str {
+        "Markdown files must use the .md extension to be loaded as notes"
+    }
+
+    fn default_level(&self) -> RuleLevel {
+        RuleLevel::Warning
+    }
+
+    fn check(&self, ctx: &LintContext) -> Vec<Finding> {
+        ctx.skipped_files
+            .iter()
+            .filter_map(|path| {
+                let extension = path.extension()?.to_str()?;
+                if !MARKDOWN_EXTENSIONS.contains(&extension.to_lowercase().as_str()) {
+                    return None;
+                }
+                Some(Finding::for_file(
+                    path,
+                    format!("'.{}' files aren't loaded as notes; rename it to .md", extension),
+                ))
+            })
+            .collect()
+    }
+}
+
+// ============================================================================
+// Health report
+// ============================================================================
+
+/// Run the rules behind a [`HealthReport`] at their built-in behavior
+///
+/// Rule levels and `lint-ignore` don't apply, as they never did to the health
+/// check; note types and task statuses come from `config`.
+pub(crate) fn health_report(
+    notes: &[Note],
+    skipped_files: &[PathBuf],
+    load_errors: &[NoteLoadError],
+    config: &LibraryConfig,
+) -> HealthReport {
+    let ctx = LintContext {
+        notes,
+        skipped_files,
+        load_errors,
+        graph: LinkGraph::build(notes, &config.task_vocabulary()),
+        config,
+    };
+    let titles = |rule: &dyn Rule| -> Vec<String> { rule.check(&ctx).into_iter().map(|f| f.title).collect() };
+
+    let mut duplicate_titles: HashMap<String, Vec<String>> = HashMap::new();
+    for finding in DuplicateTitles.check(&ctx) {
+        duplicate_titles
+            .entry(finding.title.to_lowercase())
+            .or_default()
+            .push(finding.path.display().to_string());
+    }
+
+    HealthReport {
+        broken_links: ctx.graph.broken_links(notes),
+        notes_without_tags: titles(&MissingTags),
+        notes_without_frontmatter: titles(&MissingFrontmatter),
+        duplicate_titles,
+        orphaned_notes: titles(&Orphans),
+        schema_violations: schema::validate_notes(notes, &config.types),
+        unreadable_notes: UnreadableNotes.check(&ctx),
+        invalid_frontmatter: InvalidFrontmatter.check(&ctx),
+        invalid_dates: InvalidDates.check(&ctx),
+        malformed_tasks: MalformedTasks.check(&ctx),
+        empty_notes: EmptyNotes.check(&ctx),
+        misnamed_periodic_notes: PeriodicFilenames.check(&ctx),
+        misnamed_markdown_files: MarkdownExtensions.check(&ctx),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn note(path: &str, content: &str) -> Note {
+        Note::parse(Path::new(path), content).unwrap()
+    }
+
+    fn rules(report: &LintReport) -> Vec<&str> {
+        report.diagnostics.iter().map(|d| d.rule.as_str()).collect()
+    }
+
+    #[test]
+    fn test_builtin_rules() {
+        let notes = vec![
+            note("a.md", "---\ntags: [x]\n---\n# Same\n\nSee [[Missing]].\n"),
+            note("b.md", "---\ntags: [x]\n---\n# Same\n"),
+            note("loose.md", "# Loose\n"),
+        ];
+
+        let report = Linter::with_builtin_rules().run(&notes, &LibraryConfig::default());
+        assert_eq!(report.notes_checked, 3);
+
+        let broken = &report.diagnostics[0];
+        assert_eq!(broken.rule, "broken-link");
+        assert_eq!(broken.path, PathBuf::from("a.md"));
+        assert_eq!(broken.line, Some(6));
+        assert_eq!(broken.severity, RuleLevel::Error);
+
+        assert_eq!(
+            rules(&report),
+            vec!["broken-link", "duplicate-title", "duplicate-title", "missing-tags", "missing-frontmatter", "orphan"]
+        );
+        assert_eq!(report.error_count(), 3);
+        assert_eq!(report.warning_count(), 3);
+    }
+
+    #[test]
+    fn test_config_levels() {
+        let notes = vec![note("loose.md", "# Loose\n")];
+        let mut config = LibraryConfig::default();
+        config.lint.insert("orphan".to_string(), RuleLevel::Off);
+        config.lint.insert("missing-tags".to_string(), RuleLevel::Error);
+
+        let report = Linter::with_builtin_rules().run(&notes, &config);
+        assert_eq!(rules(&report), vec!["missing-tags", "missing-frontmatter"]);
+        assert!(report.has_errors());
+    }
+
+    #[test]
+    fn test_suppression_in_frontmatter() {
+        let notes = vec![
+            note("a.md", "---\nlint-ignore: [orphan, missing-tags]\ncreated: 2026-01-01T00:00:00Z\n---\n# A\n"),
+            note("b.md", "---\nlint-ignore: all\n---\n# B\n\n[[Nowhere]]\n"),
+        ];
+
+        let report = Linter::with_builtin_rules().run(&notes, &LibraryConfig::default());
+        assert!(report.diagnostics.is_empty(), "{:?}", report.diagnostics);
+    }
+
+    #[test]
+    fn test_schema_rule() {
+        let config: LibraryConfig = toml::from_str("[types.person]\nrequired = [\"email\"]\n").unwrap();
+        let notes = vec![note("ann.md", "---\ntags: [person]\n---\n# Ann\n")];
+
+        let report = Linter::with_builtin_rules().run(&notes, &config);
+        assert_eq!(rules(&report), vec!["schema"]);
+        assert!(report.diagnostics[0].message.starts_with("Type 'person': "));
+    }
+
+    #[test]
+    fn test_malformed_content_rules() {
+        let notes = vec![
+            note("bad-yaml.md", "---\ntags: [x\ntitle: Broken\n---\n# Bad\n\nText.\n"),
+            note("dates.md", "---\ntags: [x]\ncreated: yesterday\nupdated: 2026-01-02\n---\n# Dates\n\nText.\n"),
+            note(
+                "tasks.md",
+                "---\ntags: [x]\n---\n# Tasks\n\n- [ ]!!! fix\n- [x]\n- [~] odd\n-[ ] tight\n- [ ] fine\n\n```\n- [?] code\n```\n",
+            ),
+            note(
+                "deps.md",
+                "---\ntags: [x]\n---\n# Deps\n\n- [ ] Build blocked-by:^t-ship ^t-build\n- [ ] Ship after:[[Deps]]#1 ^t-ship\n",
+            ),
+            note("blank.md", "---\ntags: [x]\n---\n\n"),
+            note("2026-1-5.md", "---\ntags: [x]\n---\nDay.\n"),
+            note("2026-02-30.md", "---\ntags: [x]\n---\nDay.\n"),
+            note("2026-W03.md", "---\ntags: [x]\n---\nWeek.\n"),
+        ];
+        let skipped = vec![PathBuf::from("draft.markdown"), PathBuf::from("image.png")];
+        let mut config = LibraryConfig::default();
+        for rule in ["missing-tags", "missing-frontmatter", "orphan"] {
+            config.lint.insert(rule.to_string(), RuleLevel::Off);
+        }
+
+        let report = Linter::with_builtin_rules().run_with_files(&notes, &skipped, &[], &config);
+        let found: Vec<(&str, &str, Option<usize>)> = report
+            .diagnostics
+            .iter()
+            .map(|d| (d.rule.as_str(), d.path.to_str().unwrap(), d.line))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("invalid-frontmatter", "bad-yaml.md", Some(3)),
+                ("invalid-date", "dates.md", Some(3)),
+                ("dependency-cycle", "deps.md", Some(6)),
+                ("malformed-task", "tasks.md", Some(6)),
+                ("malformed-task", "tasks.md", Some(7)),
+                ("malformed-task", "tasks.md", Some(8)),
+                ("malformed-task", "tasks.md", Some(9)),
+                ("empty-note", "blank.md", None),
+                ("periodic-filename", "2026-1-5.md", None),
+                ("periodic-filename", "2026-02-30.md", None),
+                ("markdown-extension", "draft.markdown", None),
+            ]
+        );
+        assert_eq!(report.diagnostics[1].message, "Invalid created date 'yesterday'");
+        assert_eq!(
+            report.diagnostics[2].message,
+            "Dependency cycle: Build (^t-build) -> Ship (^t-ship) -> Build"
+        );
+        assert_eq!(report.diagnostics[5].message, "Unknown task status '[~]'");
+        assert!(report.diagnostics[8].message.contains("expected '2026-01-05.md'"));
+    }
+
+    #[test]
+    fn test_periodic_name() {
+        assert_eq!(periodic_name("Meeting notes"), None);
+        assert_eq!(periodic_name("2026-01-05"), Some(Some("2026-01-05".to_string())));
+        assert_eq!(periodic_name("20260105"), Some(Some("2026-01-05".to_string())));
+        assert_eq!(periodic_name("2026_w3"), Some(Some("2026-W03".to_string())));
+        assert_eq!(periodic_name("2026q2"), Some(Some("2026-Q2".to_string())));
+        assert_eq!(periodic_name("2026-Q5"), Some(None));
+        assert_eq!(periodic_name("05-01-2026"), Some(None));
+    }
+
+    #[test]
+    fn test_health_check_no_issues() {
+        let notes = vec![
+            note("note1.md", "---\ntags: [test]\n---\n\n# Note 1\n\nContent with [[Note 2]] link"),
+            note("note2.md", "---\ntags: [test]\n---\n\n# Note 2\n\nContent"),
+        ];
+
+        let report = health_report(&notes, &[], &[], &LibraryConfig::default());
+        assert!(!report.has_issues());
+        assert_eq!(report.issue_count(), 0);
+    }
+
+    #[test]
+    fn test_health_check_broken_links() {
+        let notes = vec![note("note1.md", "---\ntags: [test]\n---\n\n# Note 1\n\nContent with [[Missing Note]] link")];
+
+        let report = health_report(&notes, &[], &[], &LibraryConfig::default());
+        assert!(report.has_issues());
+        assert_eq!(report.broken_links.len(), 1);
+        assert!(report.broken_links.contains_key("Note 1"));
+    }
+
+    #[test]
+    fn test_health_check_missing_frontmatter() {
+        let notes = vec![note("note1.md", "# Note 1\n\nContent without frontmatter")];
+
+        let report = health_report(&notes, &[], &[], &LibraryConfig::default());
+        assert!(report.has_issues());
+        assert_eq!(report.notes_without_frontmatter.len(), 1);
+        assert_eq!(report.notes_without_tags.len(), 1);
+    }
+
+    #[test]
+    fn test_health_check_duplicate_titles() {
+        let notes = vec![
+            note("note1.md", "---\ntags: [test]\n---\n\n# Same Title"),
+            note("subfolder/note2.md", "---\ntags: [test]\n---\n\n# Same Title"),
+        ];
+
+        let report = health_report(&notes, &[], &[], &LibraryConfig::default());
+        assert!(report.has_issues());
+        assert_eq!(report.duplicate_titles.len(), 1);
+        assert_eq!(report.duplicate_titles["same title"], vec!["note1.md", "subfolder/note2.md"]);
+    }
+
+    #[test]
+    fn test_health_check_malformed_content() {
+        let notes = vec![
+            note("bad.md", "---
+tags: [test
+---
+
+# Bad"),
+            note("tasks.md", "---
+tags: [test]
+---
+
+# Tasks
+
+- [~] Unknown status"),
+            note("empty.md", "---
+tags: [test]
+---
+"),
+        ];
+        let skipped = vec![PathBuf::from("old.markdown")];
+        let load_errors = vec![NoteLoadError {
+            path: PathBuf::from("binary.md"),
+            line: Some(3),
+            message: "Not valid UTF-8 text".to_string(),
+        }];
+
+        let report = health_report(&notes, &skipped, &load_errors, &LibraryConfig::default());
+        assert_eq!(report.unreadable_notes.len(), 1);
+        assert_eq!(report.unreadable_notes[0].line, Some(3));
+        assert_eq!(report.invalid_frontmatter.len(), 1);
+        assert_eq!(report.malformed_tasks.len(), 1);
+        assert_eq!(report.empty_notes.len(), 1);
+        assert_eq!(report.misnamed_markdown_files.len(), 1);
+        assert!(report.issue_count() >= 5);
+    }
+}
@@ -1,7 +1,7 @@
 mod cli;
 
 use anyhow::{Context, Result};
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 use std::path::PathBuf;
 use termcolor::ColorChoice;
 
@@ -54,6 +54,10 @@ enum Commands {
         /// Search query
         query: String,
 
+        /// Only search notes with these tags (hierarchical; '!tag' excludes, 'a,b' matches either)
+        #[arg(long = "tag")]
+        tags: Vec<String>,
+
         /// Maximum matches to show per note
         #[arg(long, default_value = "3")]
         limit: usize,
@@ -77,33 +81,88 @@ enum Commands {
     /// List open tasks (alias for 'task list --status open')
     #[command(alias = "t")]
     Tasks {
+        /// Query expression, e.g. "(@work or @oncall) and due<=+3d" (see README)
+        query: Vec<String>,
+
         /// Filter by note name (supports * wildcard)
         #[arg(long)]
         note: Option<String>,
 
-        /// Filter by tag (can be specified multiple times)
+        /// Filter by tag (can be specified multiple times; '!tag' excludes, 'a,b' matches either)
         #[arg(long = "tag")]
         tags: Vec<String>,
 
-        /// Filter by status (open, done, all)
+        /// Filter by status (open, closed, all, or a status name like done or cancelled)
         #[arg(long, default_value = "open")]
         status: String,
 
-        /// Sort order: comma-separated fields (urgency, priority, id)
-        #[arg(long, default_value = "urgency,priority,id")]
+        /// Sort order: comma-separated fields (score, urgency, priority, note, id)
+        #[arg(long, default_value = "urgency,priority,note")]
         sort_order: String,
     },
 
     /// Check for issues in the note collection
-    Doctor,
+    Doctor {
+        /// Repair issues before checking, asking before each change (optionally only one kind)
+        #[arg(long, value_enum, value_name = "KIND")]
+        fix: Option<Option<DoctorFix>>,
+
+        /// Apply fixes without asking (fixes that need a choice are skipped)
+        #[arg(short, long, requires = "fix")]
+        yes: bool,
+
+        /// Show what --fix would change without writing anything
+        #[arg(long, requires = "fix")]
+        dry_run: bool,
+
+        /// Report format (exits non-zero when any rule reports an error)
+        #[arg(long, value_enum, default_value = "text", conflicts_with = "fix")]
+        format: cli::commands::ReportFormat,
+    },
+
+    /// Fill in missing created/updated timestamps from git history
+    BackfillDates {
+        /// Show what would change without writing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Update the 'updated' timestamp of notes whose content changed since the last commit
+    Touch {
+        /// Notes to check (defaults to all notes changed since the last commit)
+        paths: Vec<PathBuf>,
+    },
 
     /// Sync notes with git remote (commit, pull, push)
     Sync {
         /// Custom commit message
         #[arg(long, short)]
         message: Option<String>,
+
+        /// Show the commit message that would be used without touching the repo
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Set up the markdown-aware merge driver for this repository instead of syncing
+        #[arg(long, conflicts_with_all = ["message", "dry_run"])]
+        install_merge_driver: bool,
     },
 
+    /// Merge two versions of a note (used by git, see 'sync --install-merge-driver')
+    MergeDriver {
+        /// Common ancestor version (%O)
+        base: PathBuf,
+
+        /// Our version (%A), overwritten with the result
+        ours: PathBuf,
+
+        /// Their version (%B)
+        theirs: PathBuf,
+    },
+
+    /// Show uncommitted note changes and sync state
+    Status,
+
     /// Pull changes from git remote
     Pull,
 
@@ -115,6 +174,14 @@ enum Commands {
     #[command(subcommand)]
     Task(TaskCommands),
 
+    /// Tag management commands
+    #[command(subcommand)]
+    Tag(TagCommands),
+
+    /// Export notes and tasks to other formats
+    #[command(subcommand)]
+    Export(ExportCommands),
+
     /// Daily note management
     Daily {
         /// Date (YYYY-MM-DD format) or 'prev'/'next'
@@ -168,13 +235,51 @@ enum Commands {
     },
 }
 
+#[derive(Clone, Copy, ValueEnum)]
+enum DoctorFix {
+    /// Add minimal frontmatter to notes without any
+    Frontmatter,
+    /// Point broken links at the closest existing title
+    Links,
+    /// Rename notes that share a title
+    Titles,
+    /// Tag or link notes that have neither
+    Orphans,
+    /// Fill in missing created/updated timestamps from git history
+    Timestamps,
+}
+
+impl DoctorFix {
+    fn repair_kind(self) -> Option<bnotes::RepairKind> {
+        match self {
+            DoctorFix::Frontmatter => Some(bnotes::RepairKind::Frontmatter),
+            DoctorFix::Links => Some(bnotes::RepairKind::Links),
+            DoctorFix::Titles => Some(bnotes::RepairKind::Titles),
+            DoctorFix::Orphans => Some(bnotes::RepairKind::Orphans),
+            DoctorFix::Timestamps => None,
+        }
+    }
+}
+
 #[derive(Subcommand)]
 enum NoteCommands {
     /// List all notes
     List {
-        /// Filter by tags
+        /// Filter by tags (hierarchical; '!tag' excludes, 'a,b' matches either)
         #[arg(long = "tag")]
         tags: Vec<String>,
+
+        /// Filter by frontmatter property, e.g. 'status=active', 'rating>=4', 'due<2026-11-01'
+        #[arg(long = "where", value_name = "EXPR")]
+        filters: Vec<String>,
+
+        /// Sort by property (prefix with '-' for descending)
+        #[arg(long, value_name = "PROPERTY", allow_hyphen_values = true)]
+        sort: Option<String>,
+
+        /// Show a table with these properties as columns (comma-separated)
+        #[arg(long, value_delimiter = ',')]
+        columns: Vec<String>,
     },
 
     /// Display a note
@@ -183,6 +288,55 @@ enum NoteCommands {
         title: String,
     },
 
+    /// Set a frontmatter property on a note (value is parsed as YAML)
+    Set {
+        /// Note title
+        title: String,
+
+        /// Property name
+        key: String,
+
+        /// Property value, e.g. 'active', '4' or '[a, b]'
+        value: String,
+    },
+
+    /// Remove a frontmatter property from a note
+    Unset {
+        /// Note title
+        title: String,
+
+        /// Property name
+        key: String,
+    },
+
+    /// List the commits that changed a note
+    History {
+        /// Note title
+        title: String,
+    },
+
+    /// Show changes to a note since a revision (default: uncommitted changes)
+    Diff {
+        /// Note title
+        title: String,
+
+        /// Git revision to compare against (commit hash, HEAD~2, ...)
+        rev: Option<String>,
+    },
+
+    /// Restore a note to its version at a revision
+    Restore {
+        /// Note title
+        title: String,
+
+        /// Git revision to restore from (commit hash, HEAD~2, ...)
+        rev: String,
+
+        /// Overwrite uncommitted changes to the note
+        #[arg(long)]
+        force: bool,
+    },
+
     /// Show links to and from a note
     Links {
         /// Note title
@@ -197,21 +351,160 @@ enum NoteCommands {
 enum TaskCommands {
     /// List tasks across all notes
     List {
+        /// Query expression, e.g. "(@work or @oncall) and due<=+3d" (see README)
+        query: Vec<String>,
+
         /// Filter by note name (supports * wildcard)
         #[arg(long)]
         note: Option<String>,
 
-        /// Filter by tags
+        /// Filter by tags (hierarchical; '!tag' excludes, 'a,b' matches either)
         #[arg(long = "tag")]
         tags: Vec<String>,
 
-        /// Filter by status (open or done)
+        /// Filter by status (open, closed, all, or a status name like done or cancelled)
         #[arg(long)]
         status: Option<String>,
 
-        /// Sort order: comma-separated fields (urgency, priority, id)
-        #[arg(long, default_value = "urgency,priority,id")]
+        /// Sort order: comma-separated fields (score, urgency, priority, note, id)
+        #[arg(long, default_value = "urgency,priority,note")]
         sort_order: String,
+
+        /// Show subtasks indented under their parent task
+        #[arg(long)]
+        tree: bool,
+
+        /// Hide subtasks of completed tasks
+        #[arg(long)]
+        hide_completed_subtasks: bool,
+
+        /// Only show blocked tasks, with what each is waiting on
+        #[arg(long)]
+        blocked: bool,
+
+        /// Show each task's computed score
+        #[arg(long)]
+        show_score: bool,
+
+        /// Split tasks into sections
+        #[arg(long, value_enum)]
+        group_by: Option<cli::commands::TaskGroupBy>,
+
+        /// Show at most this many tasks
+        #[arg(long)]
+        limit: Option<usize>,
+
+        /// Columns to show, in order (comma-separated)
+        #[arg(long, value_enum, value_delimiter = ',')]
+        columns: Vec<cli::commands::TaskColumn>,
+
+        /// Leave out the note column when grouping by note
+        #[arg(long)]
+        compact: bool,
+    },
+
+    /// List open tasks that aren't waiting on other tasks
+    Next {
+        /// Query expression, e.g. "(@work or @oncall) and due<=+3d" (see README)
+        query: Vec<String>,
+
+        /// Filter by note name (supports * wildcard)
+        #[arg(long)]
+        note: Option<String>,
+
+        /// Filter by tags (hierarchical; '!tag' excludes, 'a,b' matches either)
+        #[arg(long = "tag")]
+        tags: Vec<String>,
+
+        /// Sort order: comma-separated fields (score, urgency, priority, note, id)
+        #[arg(long, default_value = "score,note")]
+        sort_order: String,
+
+        /// Show each task's computed score
+        #[arg(long)]
+        show_score: bool,
+
+        /// Show at most this many tasks
+        #[arg(long)]
+        limit: Option<usize>,
+    },
+
+    /// Print tasks in another format, e.g. todo.txt
+    Export {
+        /// Query expression selecting the tasks to export (see README)
+        query: Vec<String>,
+
+        /// Output format
+        #[arg(long, value_enum, default_value = "todotxt")]
+        format: cli::commands::TaskFormat,
+    },
+
+    /// Append tasks from a file, e.g. a todo.txt, to a note
+    Import {
+        /// File to import
+        file: PathBuf,
+
+        /// Note to add the tasks to
+        #[arg(long)]
+        into: String,
+
+        /// Input format
+        #[arg(long, value_enum, default_value = "todotxt")]
+        format: cli::commands::TaskFormat,
+    },
+
+    /// Mark tasks as done by ID
+    Done {
+        /// Task IDs as shown by 'task list'
+        #[arg(required = true)]
+        ids: Vec<String>,
+    },
+
+    /// Give tasks a block anchor (^t-4f2a) so their IDs survive edits
+    Anchor {
+        /// Task IDs as shown by 'task list'
+        #[arg(required_unless_present = "all", conflicts_with = "all")]
+        ids: Vec<String>,
+
+        /// Anchor every task that doesn't have one yet
+        #[arg(long)]
+        all: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum TagCommands {
+    /// List all tags used by notes and tasks
+    List {
+        /// Show tags as a hierarchy with rolled-up counts
+        #[arg(long)]
+        tree: bool,
+    },
+
+    /// Rename a tag and all tags nested under it, in notes and tasks
+    Rename {
+        /// Tag to rename
+        old: String,
+
+        /// New tag name
+        new: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum ExportCommands {
+    /// Write dated tasks (and daily notes) as an iCalendar file
+    Ics {
+        /// Query expression selecting the tasks to export (see README)
+        query: Vec<String>,
+
+        /// File to write (defaults to standard output)
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+
+        /// Also add each daily note as an all-day event
+        #[arg(long)]
+        daily_notes: bool,
     },
 }
 
@@ -234,33 +527,93 @@ fn main() -> Result<()> {
     let notes_dir = resolve_notes_dir(cli_args.notes_dir)?;
 
     match cli_args.command {
-        Commands::Search { query, limit } => {
-            cli::commands::search(&notes_dir, &query, limit, cli_args.color)?;
+        Commands::Search { query, tags, limit } => {
+            cli::commands::search(&notes_dir, &query, &tags, limit, cli_args.color)?;
         }
         Commands::Edit { title, template, print_path } => {
             cli::commands::edit(&notes_dir, &title, template, print_path)?;
         }
-        Commands::Tasks { note, tags, status, sort_order } => {
+        Commands::Tasks { query, note, tags, status, sort_order } => {
             let sort_order = bnotes::TaskSortOrder::parse(&sort_order)
                 .context("Invalid sort order")?;
-            cli::commands::task_list(&notes_dir, &tags, Some(status), note.as_deref(), sort_order, cli_args.color)?;
+            let query = cli::commands::task_query(&query, &tags, Some(&status), note.as_deref())?;
+            let display = cli::commands::TaskListDisplay::default();
+            cli::commands::task_list(&notes_dir, query, sort_order, display, cli_args.color)?;
+        }
+        Commands::Doctor { fix, yes, dry_run, format } => {
+            match fix {
+                Some(Some(DoctorFix::Timestamps)) => {
+                    cli::commands::backfill_dates(&notes_dir, dry_run, cli_args.color)?;
+                    println!();
+                }
+                Some(kind) => {
+                    let only = kind.and_then(DoctorFix::repair_kind);
+                    cli::commands::doctor_fix(&notes_dir, only, yes, dry_run, cli_args.color)?;
+                    println!();
+                }
+                None => {}
+            }
+            if !cli::commands::doctor(&notes_dir, format, cli_args.color)? {
+                std::process::exit(1);
+            }
+        }
+        Commands::BackfillDates { dry_run } => {
+            cli::commands::backfill_dates(&notes_dir, dry_run, cli_args.color)?;
+        }
+        Commands::Touch { paths } => {
+            cli::commands::touch(&notes_dir, &paths, cli_args.color)?;
+        }
+        Commands::Sync { message, dry_run, install_merge_driver } => {
+            if install_merge_driver {
+                cli::commands::install_merge_driver(&notes_dir, cli_args.color)?;
+            } else {
+                cli::commands::sync(&notes_dir, message, dry_run, cli_args.color)?;
+            }
         }
-        Commands::Doctor => {
-            cli::commands::doctor(&notes_dir, cli_args.color)?;
+        Commands::MergeDriver { base, ours, theirs } => {
+            // Git treats a non-zero exit as "conflicts left in the file"
+            if !cli::commands::merge_driver(&notes_dir, &base, &ours, &theirs)? {
+                std::process::exit(1);
+            }
         }
-        Commands::Sync { message } => {
-            cli::commands::sync(&notes_dir, message, cli_args.color)?;
+        Commands::Status => {
+            cli::commands::status(&notes_dir, cli_args.color)?;
         }
         Commands::Pull => {
             cli::commands::pull(&notes_dir, cli_args.color)?;
         }
         Commands::Note(note_cmd) => match note_cmd {
-            NoteCommands::List { tags } => {
-                cli::commands::note_list(&notes_dir, &tags, cli_args.color)?;
+            NoteCommands::List { tags, filters, sort, columns } => {
+                let filters = filters
+                    .iter()
+                    .map(|f| bnotes::PropertyFilter::parse(f))
+                    .collect::<Result<Vec<_>>>()
+                    .context("Invalid --where filter")?;
+                let sort = sort
+                    .as_deref()
+                    .map(bnotes::PropertySort::parse)
+                    .transpose()
+                    .context("Invalid sort property")?;
+                cli::commands::note_list(&notes_dir, &tags, &filters, sort.as_ref(), &columns, cli_args.color)?;
             }
             NoteCommands::Show { title } => {
                 cli::commands::note_show(&notes_dir, &title)?;
             }
+            NoteCommands::Set { title, key, value } => {
+                cli::commands::note_set(&notes_dir, &title, &key, &value, cli_args.color)?;
+            }
+            NoteCommands::Unset { title, key } => {
+                cli::commands::note_unset(&notes_dir, &title, &key, cli_args.color)?;
+            }
+            NoteCommands::History { title } => {
+                cli::commands::note_history(&notes_dir, &title, cli_args.color)?;
+            }
+            NoteCommands::Diff { title, rev } => {
+                cli::commands::note_diff(&notes_dir, &title, rev.as_deref(), cli_args.color)?;
+            }
+            NoteCommands::Restore { title, rev, force } => {
+                cli::commands::note_restore(&notes_dir, &title, &rev, force, cli_args.color)?;
+            }
             NoteCommands::Links { title } => {
                 cli::commands::note_links(&notes_dir, &title, cli_args.color)?;
             }
@@ -269,10 +622,70 @@ fn main() -> Result<()> {
             }
         },
         Commands::Task(task_cmd) => match task_cmd {
-            TaskCommands::List { note, tags, status, sort_order } => {
+            TaskCommands::List {
+                query,
+                note,
+                tags,
+                status,
+                sort_order,
+                tree,
+                hide_completed_subtasks,
+                blocked,
+                show_score,
+                group_by,
+                limit,
+                columns,
+                compact,
+            } => {
+                let sort_order = bnotes::TaskSortOrder::parse(&sort_order)
+                    .context("Invalid sort order")?;
+                let display = cli::commands::TaskListDisplay {
+                    tree,
+                    hide_completed_subtasks,
+                    blocked,
+                    show_score,
+                    group_by,
+                    limit,
+                    columns,
+                    compact,
+                    ..Default::default()
+                };
+                let query = cli::commands::task_query(&query, &tags, status.as_deref(), note.as_deref())?;
+                cli::commands::task_list(&notes_dir, query, sort_order, display, cli_args.color)?;
+            }
+            TaskCommands::Next { query, note, tags, sort_order, show_score, limit } => {
                 let sort_order = bnotes::TaskSortOrder::parse(&sort_order)
                     .context("Invalid sort order")?;
-                cli::commands::task_list(&notes_dir, &tags, status, note.as_deref(), sort_order, cli_args.color)?;
+                let display = cli::commands::TaskListDisplay { hide_blocked: true, show_score, limit, ..Default::default() };
+                let query = cli::commands::task_query(&query, &tags, Some("open"), note.as_deref())?;
+                cli::commands::task_list(&notes_dir, query, sort_order, display, cli_args.color)?;
+            }
+            TaskCommands::Export { query, format } => {
+                let query = bnotes::TaskQuery::parse(&query.join(" "))?;
+                cli::commands::task_export(&notes_dir, query, format)?;
+            }
+            TaskCommands::Import { file, into, format } => {
+                cli::commands::task_import(&notes_dir, &file, &into, format, cli_args.color)?;
+            }
+            TaskCommands::Done { ids } => {
+                cli::commands::task_done(&notes_dir, &ids, cli_args.color)?;
+            }
+            TaskCommands::Anchor { ids, all } => {
+                cli::commands::task_anchor(&notes_dir, &ids, all, cli_args.color)?;
+            }
+        },
+        Commands::Tag(tag_cmd) => match tag_cmd {
+            TagCommands::List { tree } => {
+                cli::commands::tag_list(&notes_dir, tree, cli_args.color)?;
+            }
+            TagCommands::Rename { old, new } => {
+                cli::commands::tag_rename(&notes_dir, &old, &new, cli_args.color)?;
+            }
+        },
+        Commands::Export(export_cmd) => match export_cmd {
+            ExportCommands::Ics { query, output, daily_notes } => {
+                let query = bnotes::TaskQuery::parse(&query.join(" "))?;
+                cli::commands::export_ics(&notes_dir, query, output.as_deref(), daily_notes, cli_args.color)?;
             }
         },
         Commands::Daily {
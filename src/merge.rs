@@ -0,0 +1,347 @@
+//! Markdown-aware three-way merge
+//!
+//! Git's line-based merge conflicts on the edits notes typically get on two
+//! machines: both sides bump `updated`, add a tag, or tick off a task. This
+//! merges notes structurally instead:
+//!
+//! - frontmatter is merged key by key: tags are unioned, `updated` takes the
+//!   newest value, `created` the oldest, and other keys take whichever side
+//!   changed them
+//! - task lines are matched by their text, and a task closed on either side
+//!   (completed, or any closed status in the [`TaskVocabulary`]) stays closed
+//! - everything else gets a regular line-level merge
+
+use crate::frontmatter::FrontmatterEditor;
+use crate::note::TaskVocabulary;
+use crate::tags::normalize;
+use chrono::{DateTime, Utc};
+use diffy::{ConflictStyle, MergeOptions};
+use serde_yaml::Value;
+use std::collections::HashMap;
+
+/// Result of merging a note
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergeResult {
+    /// Merged content, with conflict markers where the sides couldn't be reconciled
+    pub content: String,
+    /// Whether any conflict markers were written
+    pub has_conflicts: bool,
+}
+
+/// Merge two versions of a note given their common ancestor
+///
+/// Only checkbox markers in `vocabulary` are treated as tasks.
+pub fn merge_notes(base: &str, ours: &str, theirs: &str, vocabulary: &TaskVocabulary) -> MergeResult {
+    let base_doc = FrontmatterEditor::parse(base);
+    let mut ours_doc = FrontmatterEditor::parse(ours);
+    let theirs_doc = FrontmatterEditor::parse(theirs);
+
+    let mut has_conflicts = merge_frontmatter(&base_doc, &mut ours_doc, &theirs_doc);
+
+    // Agree on task statuses first (in all three versions) so status changes
+    // never conflict line by line
+    let statuses = resolve_task_statuses(base_doc.body(), ours_doc.body(), theirs_doc.body(), vocabulary);
+    let base_body = apply_task_statuses(base_doc.body(), &statuses, vocabulary);
+    let ours_body = apply_task_statuses(ours_doc.body(), &statuses, vocabulary);
+    let theirs_body = apply_task_statuses(theirs_doc.body(), &statuses, vocabulary);
+
+    let mut options = MergeOptions::new();
+    options.set_conflict_style(ConflictStyle::Merge);
+    let body = match options.merge(&base_body, &ours_body, &theirs_body) {
+        Ok(body) => body,
+        Err(body) => {
+            has_conflicts = true;
+            body
+        }
+    };
+
+    ours_doc.set_body(body);
+
+    MergeResult {
+        content: ours_doc.render(),
+        has_conflicts,
+    }
+}
+
+// ============================================================================
+// Frontmatter
+// ============================================================================
+
+/// Merge frontmatter of `theirs` into `ours`; returns whether there were conflicts
+fn merge_frontmatter(base: &FrontmatterEditor, ours: &mut FrontmatterEditor, theirs: &FrontmatterEditor) -> bool {
+    let mut keys = ours.keys();
+    for key in theirs.keys().into_iter().chain(base.keys()) {
+        if !keys.contains(&key) {
+            keys.push(key);
+        }
+    }
+
+    let mut has_conflicts = false;
+
+    for key in keys {
+        let b = base.get(&key);
+        let o = ours.get(&key);
+        let t = theirs.get(&key);
+
+        if o == t {
+            continue;
+        }
+
+        let merged = match key.as_str() {
+            "tags" => Some(Some(merge_tags(b.as_ref(), o.as_ref(), t.as_ref()))),
+            "updated" => Some(pick_date(o.clone(), t.clone(), true)),
+            "created" => Some(pick_date(o.clone(), t.clone(), false)),
+            _ if o == b => Some(t.clone()),
+            _ if t == b => None,
+            _ => {
+                has_conflicts = true;
+                let ours_lines = ours.entry_lines(&key).unwrap_or_default();
+                let theirs_lines = theirs.entry_lines(&key).unwrap_or_default();
+
+                let mut lines = vec!["<<<<<<< ours".to_string()];
+                lines.extend(ours_lines);
+                lines.push("=======".to_string());
+                lines.extend(theirs_lines);
+                lines.push(">>>>>>> theirs".to_string());
+                ours.set_entry_lines(&key, lines);
+                None
+            }
+        };
+
+        match merged {
+            // Keep ours as it is
+            None => {}
+            Some(None) => {
+                ours.remove(&key);
+            }
+            Some(Some(value)) if Some(&value) == o.as_ref() => {}
+            Some(Some(value)) => {
+                // Take their lines verbatim for keys only they have, to keep their formatting
+                if o.is_none()
+                    && Some(&value) == t.as_ref()
+                    && let Some(lines) = theirs.entry_lines(&key)
+                {
+                    ours.set_entry_lines(&key, lines);
+                } else if ours.set(&key, &value).is_err() {
+                    has_conflicts = true;
+                }
+            }
+        }
+    }
+
+    has_conflicts
+}
+
+/// Read a tags value (list or comma-separated string) as a list
+fn tag_list(value: Option<&Value>) -> Vec<String> {
+    match value {
+        Some(Value::Sequence(items)) => items
+            .iter()
+            .filter_map(|item| match item {
+                Value::String(s) => Some(s.clone()),
+                Value::Number(n) => Some(n.to_string()),
+                _ => None,
+            })
+            .collect(),
+        Some(Value::String(s)) => s
+            .split(',')
+            .map(|t| t.trim().to_string())
+            .filter(|t| !t.is_empty())
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Union tags from both sides, dropping tags either side removed since the base
+fn merge_tags(base: Option<&Value>, ours: Option<&Value>, theirs: Option<&Value>) -> Value {
+    let base_tags = tag_list(base);
+    let ours_tags = tag_list(ours);
+    let theirs_tags = tag_list(theirs);
+
+    let contains = |list: &[String], tag: &str| list.iter().any(|t| normalize(t) == normalize(tag));
+    let removed = |tag: &str| {
+        contains(&base_tags, tag) && (!contains(&ours_tags, tag) || !contains(&theirs_tags, tag))
+    };
+
+    let mut merged: Vec<String> = Vec::new();
+    for tag in ours_tags.iter().chain(&theirs_tags) {
+        if !removed(tag) && !contains(&merged, tag) {
+            merged.push(tag.clone());
+        }
+    }
+
+    // Keep comma-separated string tags as a string
+    if matches!(ours, Some(Value::String(_))) {
+        Value::String(merged.join(", "))
+    } else {
+        Value::Sequence(merged.into_iter().map(Value::String).collect())
+    }
+}
+
+/// Pick the newest (or oldest) of two timestamp values
+fn pick_date(ours: Option<Value>, theirs: Option<Value>, newest: bool) -> Option<Value> {
+    let parse = |value: &Option<Value>| {
+        value
+            .as_ref()
+            .and_then(|v| v.as_str())
+            .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+            .map(|d| d.with_timezone(&Utc))
+    };
+
+    match (parse(&ours), parse(&theirs)) {
+        (Some(o), Some(t)) => {
+            if (t > o) == newest {
+                theirs
+            } else {
+                ours
+            }
+        }
+        (None, Some(_)) => theirs,
+        _ => ours.or(theirs),
+    }
+}
+
+// ============================================================================
+// Tasks
+// ============================================================================
+
+/// Split a task line into (prefix up to the checkbox, status char, rest)
+fn split_task_line(line: &str) -> Option<(&str, char, &str)> {
+    let trimmed = line.trim_start();
+    let indent = line.len() - trimmed.len();
+
+    let after_marker = trimmed.strip_prefix("- [").or_else(|| trimmed.strip_prefix("* ["))?;
+    let status = after_marker.chars().next()?;
+    let rest = after_marker[status.len_utf8()..].strip_prefix(']')?;
+
+    Some((&line[..indent + 3], status, rest))
+}
+
+/// Identity of a task line: the line with its status blanked out
+fn task_identity(line: &str, vocabulary: &TaskVocabulary) -> Option<(String, char)> {
+    let (prefix, status, rest) = split_task_line(line)?;
+    vocabulary.status(status)?;
+    Some((format!("{} ]{}", prefix, rest.trim_end()), status))
+}
+
+/// Collect the status of every task in a body, keyed by identity
+fn task_statuses(body: &str, vocabulary: &TaskVocabulary) -> HashMap<String, char> {
+    let mut statuses = HashMap::new();
+    for line in body.lines() {
+        if let Some((identity, status)) = task_identity(line, vocabulary) {
+            statuses.entry(identity).or_insert(status);
+        }
+    }
+    statuses
+}
+
+/// Decide the merged status of every task that exists on both sides
+fn resolve_task_statuses(
+    base: &str,
+    ours: &str,
+    theirs: &str,
+    vocabulary: &TaskVocabulary,
+) -> HashMap<String, char> {
+    let base = task_statuses(base, vocabulary);
+    let theirs = task_statuses(theirs, vocabulary);
+    let is_closed = |c: char| vocabulary.status(c).is_some_and(|status| !status.is_incomplete());
+
+    task_statuses(ours, vocabulary)
+        .into_iter()
+        .filter_map(|(identity, o)| {
+            let t = *theirs.get(&identity)?;
+            // Closed wins, otherwise take whichever side changed the status
+            let status = if is_closed(o) {
+                o
+            } else if is_closed(t) || base.get(&identity) == Some(&o) {
+                t
+            } else {
+                o
+            };
+            Some((identity, status))
+        })
+        .collect()
+}
+
+/// Rewrite task lines to use the resolved statuses
+fn apply_task_statuses(body: &str, statuses: &HashMap<String, char>, vocabulary: &TaskVocabulary) -> String {
+    body.split_inclusive('\n')
+        .map(|line| {
+            let Some((identity, status)) = task_identity(line, vocabulary) else {
+                return line.to_string();
+            };
+            match statuses.get(&identity) {
+                Some(&resolved) if resolved != status => {
+                    let (prefix, _, rest) = split_task_line(line).expect("task line");
+                    format!("{}{}]{}", prefix, resolved, rest)
+                }
+                _ => line.to_string(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_frontmatter_keys() {
+        let base = "---\ntags: [a, old]\nstatus: draft\nupdated: 2026-01-01T00:00:00Z\n---\n# Note\n";
+        let ours = "---\ntags: [a, old, ours]\nstatus: review\nupdated: 2026-01-03T00:00:00Z\n---\n# Note\n";
+        let theirs = "---\ntags: [a, theirs]\nstatus: draft\nupdated: 2026-01-02T00:00:00Z\nrating: 4\n---\n# Note\n";
+
+        let result = merge_notes(base, ours, theirs, &TaskVocabulary::default());
+        assert!(!result.has_conflicts);
+        assert_eq!(
+            result.content,
+            "---\ntags: [a, ours, theirs]\nstatus: review\nupdated: 2026-01-03T00:00:00Z\nrating: 4\n---\n# Note\n"
+        );
+    }
+
+    #[test]
+    fn test_merge_frontmatter_conflict() {
+        let base = "---\nstatus: draft\n---\n# Note\n";
+        let ours = "---\nstatus: review\n---\n# Note\n";
+        let theirs = "---\nstatus: done\n---\n# Note\n";
+
+        let result = merge_notes(base, ours, theirs, &TaskVocabulary::default());
+        assert!(result.has_conflicts);
+        assert!(result.content.contains("<<<<<<< ours\nstatus: review\n=======\nstatus: done\n>>>>>>> theirs\n"));
+    }
+
+    #[test]
+    fn test_merge_tasks_prefers_completed() {
+        let base = "# Note\n\n- [ ] one\n- [ ] two\n";
+        let ours = "# Note\n\n- [x] one\n- [ ] two\n- [ ] three\n";
+        let theirs = "# Note\n\n- [ ] one\n- [>] two\n";
+
+        let result = merge_notes(base, ours, theirs, &TaskVocabulary::default());
+        assert!(!result.has_conflicts);
+        assert_eq!(result.content, "# Note\n\n- [x] one\n- [>] two\n- [ ] three\n");
+    }
+
+    #[test]
+    fn test_merge_tasks_prefers_configured_closed_status() {
+        let mut vocabulary = TaskVocabulary::default();
+        vocabulary.insert('d', "delegated", false, None);
+        let base = "# Note\n\n- [ ] one\n";
+        let ours = "# Note\n\n- [/] one\n";
+        let theirs = "# Note\n\n- [d] one\n";
+
+        let result = merge_notes(base, ours, theirs, &vocabulary);
+        assert!(!result.has_conflicts);
+        assert_eq!(result.content, "# Note\n\n- [d] one\n");
+    }
+
+    #[test]
+    fn test_merge_body_conflict() {
+        let base = "# Note\n\nline\n";
+        let ours = "# Note\n\nours\n";
+        let theirs = "# Note\n\ntheirs\n";
+
+        let result = merge_notes(base, ours, theirs, &TaskVocabulary::default());
+        assert!(result.has_conflicts);
+        assert!(result.content.contains("<<<<<<< ours\nours\n=======\ntheirs\n>>>>>>> theirs\n"));
+    }
+}
@@ -8,8 +8,11 @@ use anyhow::Result;
 use chrono::{DateTime, NaiveDate, TimeZone, Utc};
 use pulldown_cmark::{Event, MetadataBlockKind, Options, Parser, Tag, TagEnd};
 use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 
+use crate::properties::PropertyValue;
+
 // ============================================================================
 // Frontmatter
 // ============================================================================
@@ -46,26 +49,25 @@ where
     let opt: Option<String> = Option::deserialize(deserializer)?;
     match opt {
         None => Ok(None),
-        Some(s) => {
-            // Try parsing as RFC3339 first
-            if let Ok(dt) = DateTime::parse_from_rfc3339(&s) {
-                return Ok(Some(dt.with_timezone(&Utc)));
-            }
-
-            // Try parsing as YYYY-MM-DD
-            if let Ok(date) = NaiveDate::parse_from_str(&s, "%Y-%m-%d") {
-                // Convert to DateTime at midnight UTC
-                if let Some(dt) = date.and_hms_opt(0, 0, 0) {
-                    return Ok(Some(Utc.from_utc_datetime(&dt)));
-                }
-            }
-
-            Err(Error::custom(format!(
+        Some(s) => parse_datetime(&s).map(Some).ok_or_else(|| {
+            Error::custom(format!(
                 "expected datetime in RFC3339 or YYYY-MM-DD format, got: {}",
                 s
-            )))
-        }
+            ))
+        }),
+    }
+}
+
+/// Parse a frontmatter date, either RFC3339 or YYYY-MM-DD (midnight UTC)
+pub(crate) fn parse_datetime(s: &str) -> Option<DateTime<Utc>> {
+    // Try parsing as RFC3339 first
+    if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
+        return Some(dt.with_timezone(&Utc));
     }
+
+    // Try parsing as YYYY-MM-DD
+    let date = NaiveDate::parse_from_str(s, "%Y-%m-%d").ok()?;
+    date.and_hms_opt(0, 0, 0).map(|dt| Utc.from_utc_datetime(&dt))
 }
 
 /// Helper function to check if a serde_yaml::Value is empty (null or empty mapping)
@@ -100,13 +102,15 @@ pub struct Note {
     pub tags: Vec<String>,
     pub created: Option<DateTime<Utc>>,
     pub updated: Option<DateTime<Utc>>,
+    /// Frontmatter fields other than title, tags, created and updated
+    pub properties: BTreeMap<String, serde_yaml::Value>,
     pub content: String,
 }
 
 impl Note {
     /// Parse a note from content
     pub fn parse(path: &Path, content: &str) -> Result<Self> {
-        let (frontmatter, body) = Self::extract_frontmatter(path, content)?;
+        let (frontmatter, body) = Self::extract_frontmatter(content)?;
 
         // Determine title: frontmatter > first H1 > filename
         let title = frontmatter
@@ -128,18 +132,47 @@ impl Note {
         let created = frontmatter.as_ref().and_then(|fm| fm.created);
         let updated = frontmatter.as_ref().and_then(|fm| fm.updated);
 
+        let properties = frontmatter
+            .as_ref()
+            .and_then(|fm| fm.extra.as_mapping())
+            .map(|mapping| {
+                mapping
+                    .iter()
+                    .filter_map(|(k, v)| k.as_str().map(|k| (k.to_string(), v.clone())))
+                    .collect()
+            })
+            .unwrap_or_default();
+
         Ok(Self {
             path: path.to_path_buf(),
             title,
             tags,
             created,
             updated,
+            properties,
             content: content.to_string(),
         })
     }
 
+    /// Look up a typed property by name
+    ///
+    /// Besides arbitrary frontmatter fields this exposes the built-in
+    /// `title`, `tags`, `created`, `updated` and `path` properties.
+    pub fn property(&self, key: &str) -> Option<PropertyValue> {
+        match key {
+            "title" => Some(PropertyValue::String(self.title.clone())),
+            "path" => Some(PropertyValue::String(self.path.display().to_string())),
+            "tags" if !self.tags.is_empty() => Some(PropertyValue::List(
+                self.tags.iter().cloned().map(PropertyValue::String).collect(),
+            )),
+            "created" => self.created.map(|dt| PropertyValue::Date(dt.date_naive())),
+            "updated" => self.updated.map(|dt| PropertyValue::Date(dt.date_naive())),
+            _ => self.properties.get(key).and_then(PropertyValue::from_yaml),
+        }
+    }
+
     /// Extract frontmatter and body from content using pulldown-cmark's built-in parsing
-    fn extract_frontmatter(path: &Path, content: &str) -> Result<(Option<Frontmatter>, String)> {
+    fn extract_frontmatter(content: &str) -> Result<(Option<Frontmatter>, String)> {
         let mut options = Options::empty();
         options.insert(Options::ENABLE_YAML_STYLE_METADATA_BLOCKS);
 
@@ -165,14 +198,8 @@ impl Note {
         }
 
         let frontmatter = if found_metadata && !yaml_content.is_empty() {
-            match serde_yaml::from_str::<Frontmatter>(&yaml_content) {
-                Ok(fm) => Some(fm),
-                Err(e) => {
-                    // Log warning but continue
-                    eprintln!("Warning: Failed to parse frontmatter in {}: {}", path.display(), e);
-                    None
-                }
-            }
+            // Bad frontmatter is reported by the `invalid-frontmatter` lint rule
+            serde_yaml::from_str::<Frontmatter>(&yaml_content).ok()
         } else {
             None
         };
@@ -233,22 +260,165 @@ pub enum TaskStatus {
     Uncompleted,   // - [ ]
     Completed,     // - [x] or [X]
     Migrated,      // - [>]
+    /// Any other marker from the configured [`TaskVocabulary`], e.g. `[-]` or `[/]`
+    Other { marker: char, open: bool },
 }
 
 impl TaskStatus {
-    /// Parse status from checkbox character
-    fn from_checkbox_char(c: char) -> Option<Self> {
-        match c {
-            ' ' => Some(TaskStatus::Uncompleted),
-            'x' | 'X' => Some(TaskStatus::Completed),
-            '>' => Some(TaskStatus::Migrated),
-            _ => None,
+    /// The character written between the checkbox brackets
+    pub fn checkbox_char(&self) -> char {
+        match self {
+            TaskStatus::Uncompleted => ' ',
+            TaskStatus::Completed => 'x',
+            TaskStatus::Migrated => '>',
+            TaskStatus::Other { marker, .. } => *marker,
         }
     }
 
     /// Check if this status represents an incomplete task
     pub fn is_incomplete(&self) -> bool {
-        matches!(self, TaskStatus::Uncompleted)
+        matches!(self, TaskStatus::Uncompleted | TaskStatus::Other { open: true, .. })
+    }
+}
+
+/// How a checkbox marker is named and shown
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatusInfo {
+    pub name: String,
+    pub open: bool,
+    /// Color name for `task list`, e.g. "green" or "dim"
+    pub color: Option<String>,
+}
+
+/// The checkbox markers that make a list item a task
+///
+/// ` `, `x`/`X` and `>` always mean open, completed and migrated, though their
+/// names and colors can be changed. Any other marker is a
+/// [`TaskStatus::Other`] with the open/closed meaning it's configured with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaskVocabulary {
+    statuses: BTreeMap<char, StatusInfo>,
+}
+
+impl Default for TaskVocabulary {
+    fn default() -> Self {
+        let mut vocabulary = Self { statuses: BTreeMap::new() };
+        for (marker, name, open, color) in [
+            (' ', "open", true, None),
+            ('x', "done", false, Some("green")),
+            ('X', "done", false, Some("green")),
+            ('>', "migrated", false, Some("yellow")),
+            ('/', "in-progress", true, Some("cyan")),
+            ('?', "waiting", true, Some("magenta")),
+            ('-', "cancelled", false, Some("dim")),
+        ] {
+            vocabulary.insert(marker, name, open, color.map(str::to_string));
+        }
+        vocabulary
+    }
+}
+
+impl TaskVocabulary {
+    /// Add or replace a marker; the built-in markers keep their open/closed meaning
+    pub fn insert(&mut self, marker: char, name: &str, open: bool, color: Option<String>) {
+        let open = match marker {
+            ' ' => true,
+            'x' | 'X' | '>' => false,
+            _ => open,
+        };
+        self.statuses.insert(marker, StatusInfo { name: name.to_string(), open, color });
+    }
+
+    /// The status a checkbox marker stands for, if it's a known one
+    pub fn status(&self, marker: char) -> Option<TaskStatus> {
+        let info = self.statuses.get(&marker)?;
+        Some(match marker {
+            ' ' => TaskStatus::Uncompleted,
+            'x' | 'X' => TaskStatus::Completed,
+            '>' => TaskStatus::Migrated,
+            _ => TaskStatus::Other { marker, open: info.open },
+        })
+    }
+
+    pub fn info(&self, status: &TaskStatus) -> Option<&StatusInfo> {
+        self.statuses.get(&status.checkbox_char())
+    }
+
+    /// Name of a status, e.g. "done"
+    pub fn name(&self, status: &TaskStatus) -> String {
+        self.info(status)
+            .map(|info| info.name.clone())
+            .unwrap_or_else(|| status.checkbox_char().to_string())
+    }
+
+    /// Every marker with its status info
+    pub fn markers(&self) -> impl Iterator<Item = (char, &StatusInfo)> {
+        self.statuses.iter().map(|(marker, info)| (*marker, info))
+    }
+
+    /// Check a task against a `--status` filter
+    ///
+    /// `all`, `open` (any open status) and `closed` are always available;
+    /// anything else is a status name or a marker character.
+    pub fn matches_filter(&self, status: &TaskStatus, filter: &str) -> Result<bool> {
+        let filter = filter.trim().to_lowercase();
+        match filter.as_str() {
+            "all" => return Ok(true),
+            "open" => return Ok(status.is_incomplete()),
+            "closed" => return Ok(!status.is_incomplete()),
+            "completed" => return Ok(*status == TaskStatus::Completed),
+            _ => {}
+        }
+
+        let mut chars = filter.chars();
+        let marker = match (chars.next(), chars.next()) {
+            (Some(c), None) => Some(c),
+            _ => None,
+        };
+        let known = self.markers().any(|(m, info)| Some(m) == marker || info.name.to_lowercase() == filter);
+        if !known {
+            let mut names: Vec<String> = self.markers().map(|(_, info)| info.name.clone()).collect();
+            names.dedup();
+            anyhow::bail!(
+                "Invalid status filter: {}. Use 'open', 'closed', 'all', or one of: {}.",
+                filter,
+                names.join(", ")
+            );
+        }
+
+        let marker_char = status.checkbox_char();
+        Ok(Some(marker_char) == marker
+            || self.info(status).is_some_and(|info| info.name.to_lowercase() == filter))
+    }
+}
+
+/// A task another task waits on
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum TaskRef {
+    /// `blocked-by:^t-4f2a`: a task ID
+    Id(String),
+    /// `after:[[Note]]#2`: the nth task of a note
+    Position { note: String, index: usize },
+}
+
+impl TaskRef {
+    /// Parse the value of an `after:` attribute
+    fn parse_after(value: &str) -> Option<TaskRef> {
+        if let Some(id) = value.strip_prefix('^') {
+            return (!id.is_empty()).then(|| TaskRef::Id(id.to_string()));
+        }
+        let (note, index) = value.strip_prefix("[[")?.split_once("]]#")?;
+        let index: usize = index.parse().ok()?;
+        (!note.trim().is_empty() && index > 0).then(|| TaskRef::Position { note: note.trim().to_string(), index })
+    }
+}
+
+impl std::fmt::Display for TaskRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TaskRef::Id(id) => write!(f, "^{}", id),
+            TaskRef::Position { note, index } => write!(f, "[[{}]]#{}", note, index),
+        }
     }
 }
 
@@ -257,11 +427,41 @@ pub struct Task {
     pub note_path: PathBuf,
     pub note_title: String,
     pub index: usize, // 1-based index within the note
+    /// Stable identifier: the block anchor if the task has one, otherwise a content hash
+    pub id: String,
+    pub anchor: Option<String>,  // Block anchor without the ^, e.g. "t-4f2a"
+    pub line: usize,  // 1-based line of the checkbox in the note's file
+    pub column: usize,  // 1-based column of the checkbox's `[`
+    /// Markdown after the checkbox as written, without the anchor
+    pub source: String,
+    /// ID of the task this one is nested under
+    pub parent: Option<String>,
+    pub depth: usize,  // Number of tasks above this one in its subtree
+    /// Completed and total subtasks, at any depth, if the task has any
+    pub progress: Option<(usize, usize)>,
+    /// Whether a task above this one in its subtree is completed
+    pub under_completed: bool,
     pub status: TaskStatus,
     pub text: String,
     pub priority: Option<String>,
     pub urgency: Option<String>,  // !!!, !!, !
     pub tags: Vec<String>,  // Tags extracted from task text (lowercase, without @ prefix)
+    /// Tasks this one waits on, from `blocked-by:` and `after:` attributes
+    pub dependencies: Vec<TaskRef>,
+    /// Due date, from a `due:YYYY-MM-DD` attribute
+    pub due: Option<NaiveDate>,
+    /// Completion date, from a `done:YYYY-MM-DD` attribute
+    pub done: Option<NaiveDate>,
+    /// Headings the task sits under, e.g. ["# Plan", "## Backlog"]
+    pub breadcrumb: Vec<String>,
+}
+
+/// `key:value` attributes parsed out of a task's text
+#[derive(Debug, Default)]
+struct TaskAttributes {
+    dependencies: Vec<TaskRef>,
+    due: Option<NaiveDate>,
+    done: Option<NaiveDate>,
 }
 
 impl Task {
@@ -299,6 +499,53 @@ impl Task {
         (tags, text)
     }
 
+    /// Pull `blocked-by:^id`, `after:[[Note]]#n`, `due:YYYY-MM-DD` and `done:YYYY-MM-DD` attributes out of text
+    /// Returns (attributes, remaining_text)
+    ///
+    /// `blocked-by:` takes comma-separated IDs; `after:` takes a note position
+    /// or an ID. Values that don't parse are left in the text.
+    fn parse_attributes(text: &str) -> (TaskAttributes, String) {
+        let mut attributes = TaskAttributes::default();
+        let mut kept: Vec<String> = Vec::new();
+        let mut words = text.split_whitespace();
+
+        while let Some(word) = words.next() {
+            if let Some(ids) = word.strip_prefix("blocked-by:") {
+                let ids: Vec<&str> = ids.split(',').map(|id| id.trim_start_matches('^')).collect();
+                if !ids.iter().any(|id| id.is_empty()) {
+                    attributes.dependencies.extend(ids.into_iter().map(|id| TaskRef::Id(id.to_string())));
+                    continue;
+                }
+            } else if let Some(value) = word.strip_prefix("after:") {
+                // Note titles may contain spaces: gather words up to the closing ]]
+                let mut value = value.to_string();
+                if value.starts_with("[[") {
+                    while !value.contains("]]") {
+                        let Some(next) = words.next() else { break };
+                        value.push(' ');
+                        value.push_str(next);
+                    }
+                }
+                match TaskRef::parse_after(&value) {
+                    Some(dependency) => attributes.dependencies.push(dependency),
+                    None => kept.push(format!("after:{}", value)),
+                }
+                continue;
+            } else if let Some((key @ ("due" | "done"), date)) = word.split_once(':')
+                && let Ok(date) = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            {
+                match key {
+                    "due" => attributes.due = Some(date),
+                    _ => attributes.done = Some(date),
+                }
+                continue;
+            }
+            kept.push(word.to_string());
+        }
+
+        (attributes, kept.join(" "))
+    }
+
     /// Parse urgency and priority from task text
     /// Format: [urgency] [(priority)] task text
     /// Urgency: !!!, !!, ! (must have space after)
@@ -338,68 +585,204 @@ impl Task {
         (urgency, priority, task_text)
     }
 
-    /// Extract all tasks from a note
-    pub fn extract_from_note(note: &Note) -> Vec<Task> {
-        let mut tasks = Vec::new();
-        let mut task_index = 0;
+    /// Split a trailing block anchor (`^t-4f2a`) off task text
+    /// Returns (text_without_anchor, anchor_without_caret)
+    fn split_anchor(text: &str) -> (&str, Option<&str>) {
+        let trimmed = text.trim_end();
+        let start = trimmed.rfind(char::is_whitespace).map(|i| i + 1).unwrap_or(0);
+        match trimmed[start..].strip_prefix('^') {
+            Some(anchor) if !anchor.is_empty() && anchor.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') => {
+                (trimmed[..start].trim_end(), Some(anchor))
+            }
+            _ => (trimmed, None),
+        }
+    }
 
-        // Parse the markdown to find list items (don't use ENABLE_TASKLISTS so we get raw text)
-        let options = Options::empty();
-        let parser = Parser::new_ext(&note.content, options);
-        let mut in_list_item = false;
-        let mut item_text = String::new();
+    /// Fallback ID for a task without an anchor, from its note and text
+    ///
+    /// Ticking the checkbox or adding tasks around it doesn't change the ID;
+    /// editing the task's own text does. IDs have to be unique across every
+    /// note, so 40 bits keep collisions unlikely well past a million tasks.
+    fn hash_id(note_path: &Path, text: &str) -> String {
+        let normalized = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        let key = format!("{}\n{}", note_path.display(), normalized);
+        format!("h-{:010x}", crate::fnv1a(&key) & 0xff_ffff_ffff)
+    }
 
-        for event in parser {
+    /// Extract all tasks from a note, using the default status vocabulary
+    pub fn extract_from_note(note: &Note) -> Vec<Task> {
+        Self::extract_with_vocabulary(note, &TaskVocabulary::default())
+    }
+
+    /// Extract all tasks from a note
+    ///
+    /// Task text is taken from the note's source, so inline code, links and
+    /// emphasis are kept exactly as written. List items whose checkbox marker
+    /// isn't in the vocabulary aren't tasks.
+    pub fn extract_with_vocabulary(note: &Note, vocabulary: &TaskVocabulary) -> Vec<Task> {
+        let content = &note.content;
+
+        // List items in document order: (start, end of own text without nested lists, parent item)
+        let mut items: Vec<(usize, Option<usize>, Option<usize>)> = Vec::new();
+        let mut open_items: Vec<usize> = Vec::new();
+
+        // Don't use ENABLE_TASKLISTS: checkboxes are parsed from the source below
+        let parser = Parser::new_ext(content, Options::empty()).into_offset_iter();
+        for (event, range) in parser {
             match event {
                 Event::Start(Tag::Item) => {
-                    in_list_item = true;
-                    item_text.clear();
+                    items.push((range.start, None, open_items.last().copied()));
+                    open_items.push(items.len() - 1);
                 }
-                Event::Text(text) if in_list_item => {
-                    item_text.push_str(&text);
+                Event::Start(Tag::List(_)) => {
+                    if let Some(&item) = open_items.last() {
+                        items[item].1.get_or_insert(range.start);
+                    }
                 }
-                Event::End(TagEnd::Item) if in_list_item => {
-                    // Check if this list item is a task (starts with [X])
-                    let trimmed = item_text.trim();
-                    if let Some(rest) = trimmed.strip_prefix('[') {
-                        if let Some(close_bracket) = rest.find(']') {
-                            if close_bracket == 1 {
-                                // We have a checkbox: [X]
-                                let checkbox_char = rest.chars().next().unwrap();
-                                if let Some(status) = TaskStatus::from_checkbox_char(checkbox_char) {
-                                    task_index += 1;
-                                    let task_text = rest[close_bracket + 1..].trim();
-
-                                    let (urgency, priority, rest) = Self::parse_urgency_and_priority(task_text);
-                                    let (tags, text) = Self::parse_tags(&rest);
-
-                                    tasks.push(Task {
-                                        note_path: note.path.clone(),
-                                        note_title: note.title.clone(),
-                                        index: task_index,
-                                        status,
-                                        text,
-                                        priority,
-                                        urgency,
-                                        tags,
-                                    });
-                                }
-                            }
-                        }
+                Event::End(TagEnd::Item) => {
+                    if let Some(item) = open_items.pop() {
+                        items[item].1.get_or_insert(range.end);
                     }
-
-                    in_list_item = false;
                 }
                 _ => {}
             }
         }
 
+        let headings = crate::repository::build_heading_positions(content);
+
+        let mut tasks: Vec<Task> = Vec::new();
+        let mut seen_ids = std::collections::HashMap::new();
+        // Task index of each list item that is a task, and each task's parent task
+        let mut item_tasks: Vec<Option<usize>> = vec![None; items.len()];
+        let mut parents: Vec<Option<usize>> = Vec::new();
+
+        for (item, &(start, end, parent_item)) in items.iter().enumerate() {
+            let end = end.unwrap_or(content.len());
+            let Some((checkbox, status, raw)) = Self::split_checkbox(&content[start..end], vocabulary) else {
+                continue;
+            };
+
+            // Nearest enclosing list item that is a task
+            let parent = std::iter::successors(parent_item, |&i| items[i].2).find_map(|i| item_tasks[i]);
+            item_tasks[item] = Some(tasks.len());
+            parents.push(parent);
+
+            // Continuation lines of the first paragraph are joined into one line of text
+            let joined = raw.lines().map(str::trim).take_while(|l| !l.is_empty()).collect::<Vec<_>>().join(" ");
+            let (source, anchor) = Self::split_anchor(&joined);
+
+            // Identical task lines in one note get numbered hash IDs
+            let id = match anchor {
+                Some(anchor) => anchor.to_string(),
+                None => {
+                    let id = Self::hash_id(&note.path, source);
+                    let count = seen_ids.entry(id.clone()).or_insert(0);
+                    *count += 1;
+                    if *count == 1 { id } else { format!("{}-{}", id, count) }
+                }
+            };
+
+            let (attributes, rest) = Self::parse_attributes(source);
+            let (urgency, priority, rest) = Self::parse_urgency_and_priority(&rest);
+            let (tags, text) = Self::parse_tags(&rest);
+
+            let offset = start + checkbox;
+            let line_start = content[..offset].rfind('\n').map(|i| i + 1).unwrap_or(0);
+
+            tasks.push(Task {
+                note_path: note.path.clone(),
+                note_title: note.title.clone(),
+                index: tasks.len() + 1,
+                id,
+                anchor: anchor.map(str::to_string),
+                line: content[..offset].matches('\n').count() + 1,
+                column: content[line_start..offset].chars().count() + 1,
+                source: source.to_string(),
+                parent: parent.map(|p| tasks[p].id.clone()),
+                depth: parent.map(|p| tasks[p].depth + 1).unwrap_or(0),
+                progress: None,
+                under_completed: parent
+                    .is_some_and(|p| !tasks[p].status.is_incomplete() || tasks[p].under_completed),
+                status,
+                text,
+                priority,
+                urgency,
+                tags,
+                dependencies: attributes.dependencies,
+                due: attributes.due,
+                done: attributes.done,
+                breadcrumb: headings
+                    .iter()
+                    .rev()
+                    .find(|(position, _)| *position <= offset)
+                    .map(|(_, breadcrumb)| breadcrumb.clone())
+                    .unwrap_or_default(),
+            });
+        }
+
+        // Roll completion of subtasks up into their ancestors, children first
+        let mut counts = vec![(0, 0); tasks.len()];
+        for i in (0..tasks.len()).rev() {
+            let (done, total) = counts[i];
+            if total > 0 {
+                tasks[i].progress = Some((done, total));
+            }
+            if let Some(p) = parents[i] {
+                // Closed subtasks (completed, cancelled, ...) count as done
+                match &tasks[i].status {
+                    TaskStatus::Migrated => {}
+                    status if status.is_incomplete() => counts[p] = (counts[p].0 + done, counts[p].1 + total + 1),
+                    _ => counts[p] = (counts[p].0 + done + 1, counts[p].1 + total + 1),
+                }
+            }
+        }
+
         tasks
     }
 
+    /// Split a list item's source into (checkbox offset, status, text after the checkbox)
+    ///
+    /// The checkbox must follow the list marker and be followed by whitespace,
+    /// so `[x](url)` links aren't mistaken for tasks.
+    pub(crate) fn split_checkbox<'a>(item: &'a str, vocabulary: &TaskVocabulary) -> Option<(usize, TaskStatus, &'a str)> {
+        let unindented = item.trim_start();
+        let after_marker = match unindented.strip_prefix(['-', '*', '+']) {
+            Some(rest) => rest,
+            None => unindented
+                .trim_start_matches(|c: char| c.is_ascii_digit())
+                .strip_prefix(['.', ')'])?,
+        };
+        let rest = after_marker.trim_start();
+        let checkbox = item.len() - rest.len();
+
+        let mut chars = rest.strip_prefix('[')?.chars();
+        let status = vocabulary.status(chars.next()?)?;
+        let after = chars.as_str().strip_prefix(']')?;
+        if !after.is_empty() && !after.starts_with(char::is_whitespace) {
+            return None;
+        }
+        Some((checkbox, status, after))
+    }
+
+    /// Wiki link targets in the task's text
+    pub fn links(&self) -> Vec<String> {
+        crate::repository::extract_wiki_links(&self.source)
+    }
+
+    /// Dependencies as they're written in task text, e.g. `blocked-by:^t-4f2a`
+    pub(crate) fn dependency_attributes(&self) -> Vec<String> {
+        self.dependencies
+            .iter()
+            .map(|dependency| match dependency {
+                TaskRef::Id(id) => format!("blocked-by:^{}", id),
+                TaskRef::Position { .. } => format!("after:{}", dependency),
+            })
+            .collect()
+    }
+
     /// Reconstruct a markdown task line from this Task
     pub fn to_markdown_line(&self) -> String {
-        let mut line = String::from("- [ ] ");
+        let mut line = format!("- [{}] ", self.status.checkbox_char());
 
         // Add urgency
         if let Some(urgency) = &self.urgency {
@@ -417,6 +800,17 @@ impl Task {
         // Add task text
         line.push_str(&self.text);
 
+        // Add attributes
+        for attribute in self.dependency_attributes() {
+            line.push(' ');
+            line.push_str(&attribute);
+        }
+        for (key, date) in [("due", self.due), ("done", self.done)] {
+            if let Some(date) = date {
+                line.push_str(&format!(" {}:{}", key, date.format("%Y-%m-%d")));
+            }
+        }
+
         // Add tags
         if !self.tags.is_empty() {
             line.push(' ');
@@ -429,20 +823,69 @@ impl Task {
             line.pop();
         }
 
+        // Keep the anchor so the task's ID survives being copied
+        if let Some(anchor) = &self.anchor {
+            line.push_str(" ^");
+            line.push_str(anchor);
+        }
+
         line
     }
 }
 
+/// Tasks to carry into the next period: every open task with its whole subtree
+///
+/// Returns the tasks in document order, each with its depth below the top of
+/// the subtree it's carried in. Subtasks keep their status so progress carries
+/// over; tasks that were already migrated are left behind.
+pub(crate) fn migration_subtrees(tasks: &[Task]) -> Vec<(&Task, usize)> {
+    let mut carried: std::collections::HashMap<&str, usize> = std::collections::HashMap::new();
+    let mut subtrees = Vec::new();
+
+    for task in tasks {
+        if task.status == TaskStatus::Migrated {
+            continue;
+        }
+        let root_depth = match task.parent.as_deref().and_then(|parent| carried.get(parent)) {
+            Some(&root_depth) => root_depth,
+            None if task.status.is_incomplete() => task.depth,
+            None => continue,
+        };
+        carried.insert(&task.id, root_depth);
+        subtrees.push((task, task.depth - root_depth));
+    }
+
+    subtrees
+}
+
+/// Replace the status character of the task checkbox on a line
+pub(crate) fn set_checkbox(line: &str, status: &TaskStatus) -> Option<String> {
+    let open = line.find('[')?;
+    let current = line[open + 1..].chars().next()?;
+    let close = open + 1 + current.len_utf8();
+    if line[close..].starts_with(']') {
+        Some(format!("{}{}{}", &line[..=open], status.checkbox_char(), &line[close..]))
+    } else {
+        None
+    }
+}
+
+/// Append a block anchor to a task line, keeping its line ending
+pub(crate) fn add_anchor(line: &str, anchor: &str) -> String {
+    let text = line.trim_end_matches(['\r', '\n']);
+    format!("{} ^{}{}", text.trim_end(), anchor, &line[text.len()..])
+}
+
 // ============================================================================
 // Helper Functions
 // ============================================================================
 
 /// Extract all tasks from multiple notes
-pub(crate) fn extract_tasks_from_notes(notes: &[Note]) -> Vec<Task> {
+pub(crate) fn extract_tasks_from_notes(notes: &[Note], vocabulary: &TaskVocabulary) -> Vec<Task> {
     let mut all_tasks = Vec::new();
 
     for note in notes {
-        let tasks = Task::extract_from_note(note);
+        let tasks = Task::extract_with_vocabulary(note, vocabulary);
         all_tasks.extend(tasks);
     }
 
@@ -591,17 +1034,103 @@ tags: [test]
         assert_eq!(tasks[3].tags, vec!["backend"]);
     }
 
+    #[test]
+    fn test_extract_tasks_keeps_source() {
+        let content = "---\ntags: [x]\n---\n# Tasks\n\n- [ ] review [[Design Doc]] and `cargo test` @work ^t-1a2b\n  1. [x] *nested* [docs](https://example.com)\n- [x](https://example.com) is a link\n";
+        let note = Note::parse(Path::new("tasks.md"), content).unwrap();
+        let tasks = Task::extract_from_note(&note);
+
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(tasks[0].text, "review [[Design Doc]] and `cargo test`");
+        assert_eq!(tasks[0].source, "review [[Design Doc]] and `cargo test` @work");
+        assert_eq!(tasks[0].tags, vec!["work"]);
+        assert_eq!(tasks[0].id, "t-1a2b");
+        assert_eq!((tasks[0].line, tasks[0].column), (6, 3));
+        assert_eq!(tasks[0].links(), vec!["Design Doc"]);
+        assert_eq!(tasks[0].breadcrumb, vec!["# Tasks"]);
+
+        assert_eq!(tasks[1].text, "*nested* [docs](https://example.com)");
+        assert_eq!(tasks[1].status, TaskStatus::Completed);
+        assert_eq!((tasks[1].line, tasks[1].column), (7, 6));
+    }
+
+    #[test]
+    fn test_extract_subtasks() {
+        let content = "# Plan\n\n- [ ] Launch\n  - [x] Spec\n  - Notes\n    - [ ] Build\n      - [x] API\n- [x] Shipped\n  - [ ] Leftover\n";
+        let note = Note::parse(Path::new("plan.md"), content).unwrap();
+        let tasks = Task::extract_from_note(&note);
+
+        let texts: Vec<&str> = tasks.iter().map(|t| t.text.as_str()).collect();
+        assert_eq!(texts, vec!["Launch", "Spec", "Build", "API", "Shipped", "Leftover"]);
+
+        // Plain list items in between don't break the hierarchy
+        assert_eq!(tasks[0].parent, None);
+        assert_eq!(tasks[2].parent.as_ref(), Some(&tasks[0].id));
+        assert_eq!(tasks[3].parent.as_ref(), Some(&tasks[2].id));
+        assert_eq!(tasks[3].depth, 2);
+
+        assert_eq!(tasks[0].progress, Some((2, 3)));
+        assert_eq!(tasks[2].progress, Some((1, 1)));
+        assert_eq!(tasks[1].progress, None);
+        assert_eq!(tasks[4].progress, Some((0, 1)));
+
+        assert!(tasks[5].under_completed);
+        assert!(!tasks[3].under_completed);
+        assert_eq!(tasks[0].breadcrumb, vec!["# Plan"]);
+    }
+
+    #[test]
+    fn test_extract_task_attributes() {
+        let content = "# Plan\n\n- [ ] !! Deploy blocked-by:^t-1a2b,h-00beef due:2026-11-02 @ops\n- [ ] Announce after:[[Release Plan]]#2 ^t-3c4d\n- [x] Read after:lunch due:soon done:2026-10-17\n";
+        let note = Note::parse(Path::new("plan.md"), content).unwrap();
+        let tasks = Task::extract_from_note(&note);
+
+        assert_eq!(tasks[0].text, "Deploy");
+        assert_eq!(tasks[0].urgency.as_deref(), Some("!!"));
+        assert_eq!(tasks[0].tags, vec!["ops"]);
+        assert_eq!(tasks[0].due, NaiveDate::from_ymd_opt(2026, 11, 2));
+        assert_eq!(
+            tasks[0].dependencies,
+            vec![TaskRef::Id("t-1a2b".to_string()), TaskRef::Id("h-00beef".to_string())]
+        );
+        assert_eq!(tasks[1].text, "Announce");
+        assert_eq!(
+            tasks[1].dependencies,
+            vec![TaskRef::Position { note: "Release Plan".to_string(), index: 2 }]
+        );
+        assert_eq!(tasks[1].to_markdown_line(), "- [ ] Announce after:[[Release Plan]]#2 ^t-3c4d");
+
+        // Values that aren't references stay in the text
+        assert_eq!(tasks[2].text, "Read after:lunch due:soon");
+        assert_eq!(tasks[2].due, None);
+        assert_eq!(tasks[2].done, NaiveDate::from_ymd_opt(2026, 10, 17));
+        assert!(tasks[2].dependencies.is_empty());
+    }
+
     #[test]
     fn test_reconstruct_task_line() {
         let task = Task {
             note_path: PathBuf::from("test.md"),
             note_title: "Test".to_string(),
             index: 1,
+            id: "h-000001".to_string(),
+            anchor: None,
+            line: 1,
+            column: 3,
+            source: String::new(),
+            parent: None,
+            depth: 0,
+            progress: None,
+            under_completed: false,
             status: TaskStatus::Uncompleted,
             text: "Simple task".to_string(),
             priority: None,
             urgency: None,
             tags: vec![],
+            dependencies: vec![],
+            due: None,
+            done: None,
+            breadcrumb: vec![],
         };
         assert_eq!(task.to_markdown_line(), "- [ ] Simple task");
 
@@ -609,11 +1138,24 @@ tags: [test]
             note_path: PathBuf::from("test.md"),
             note_title: "Test".to_string(),
             index: 1,
+            id: "h-000001".to_string(),
+            anchor: None,
+            line: 1,
+            column: 3,
+            source: String::new(),
+            parent: None,
+            depth: 0,
+            progress: None,
+            under_completed: false,
             status: TaskStatus::Uncompleted,
             text: "High priority task".to_string(),
             priority: Some("A".to_string()),
             urgency: None,
             tags: vec![],
+            dependencies: vec![],
+            due: None,
+            done: None,
+            breadcrumb: vec![],
         };
         assert_eq!(task_with_priority.to_markdown_line(), "- [ ] (A) High priority task");
 
@@ -621,11 +1163,24 @@ tags: [test]
             note_path: PathBuf::from("test.md"),
             note_title: "Test".to_string(),
             index: 1,
+            id: "h-000001".to_string(),
+            anchor: None,
+            line: 1,
+            column: 3,
+            source: String::new(),
+            parent: None,
+            depth: 0,
+            progress: None,
+            under_completed: false,
             status: TaskStatus::Uncompleted,
             text: "Complete task".to_string(),
             priority: Some("B".to_string()),
             urgency: Some("!!!".to_string()),
             tags: vec!["backend".to_string(), "urgent".to_string()],
+            dependencies: vec![],
+            due: None,
+            done: None,
+            breadcrumb: vec![],
         };
         assert_eq!(task_with_all.to_markdown_line(), "- [ ] !!! (B) Complete task @backend @urgent");
     }
@@ -0,0 +1,412 @@
+//! Typed frontmatter properties
+//!
+//! Any frontmatter field can be queried as a property. Values are interpreted
+//! as strings, numbers, dates, booleans or lists so that comparisons like
+//! `rating>=4` or `due<2026-11-01` behave as expected rather than comparing text.
+
+use crate::note::Note;
+use anyhow::Result;
+use chrono::{DateTime, NaiveDate};
+use std::cmp::Ordering;
+use std::fmt;
+
+/// A typed property value
+#[derive(Debug, Clone, PartialEq)]
+pub enum PropertyValue {
+    String(String),
+    Number(f64),
+    Date(NaiveDate),
+    Bool(bool),
+    List(Vec<PropertyValue>),
+}
+
+impl PropertyValue {
+    /// Convert a YAML value into a typed property value
+    ///
+    /// Strings that look like dates (YYYY-MM-DD or RFC3339) become dates.
+    /// Returns None for null and nested mappings.
+    pub fn from_yaml(value: &serde_yaml::Value) -> Option<Self> {
+        match value {
+            serde_yaml::Value::Null => None,
+            serde_yaml::Value::Bool(b) => Some(PropertyValue::Bool(*b)),
+            serde_yaml::Value::Number(n) => n.as_f64().map(PropertyValue::Number),
+            serde_yaml::Value::String(s) => Some(Self::from_str_typed(s)),
+            serde_yaml::Value::Sequence(items) => Some(PropertyValue::List(
+                items.iter().filter_map(Self::from_yaml).collect(),
+            )),
+            serde_yaml::Value::Mapping(_) => None,
+            serde_yaml::Value::Tagged(tagged) => Self::from_yaml(&tagged.value),
+        }
+    }
+
+    /// Interpret a string, detecting dates
+    fn from_str_typed(s: &str) -> Self {
+        match parse_date(s) {
+            Some(date) => PropertyValue::Date(date),
+            None => PropertyValue::String(s.to_string()),
+        }
+    }
+
+    /// Rank used to order values of different types
+    fn type_rank(&self) -> u8 {
+        match self {
+            PropertyValue::Bool(_) => 0,
+            PropertyValue::Number(_) => 1,
+            PropertyValue::Date(_) => 2,
+            PropertyValue::String(_) => 3,
+            PropertyValue::List(_) => 4,
+        }
+    }
+
+    /// Total ordering used for sorting
+    pub fn sort_cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (PropertyValue::Bool(a), PropertyValue::Bool(b)) => a.cmp(b),
+            (PropertyValue::Number(a), PropertyValue::Number(b)) => a.total_cmp(b),
+            (PropertyValue::Date(a), PropertyValue::Date(b)) => a.cmp(b),
+            (PropertyValue::String(a), PropertyValue::String(b)) => {
+                a.to_lowercase().cmp(&b.to_lowercase())
+            }
+            (PropertyValue::List(a), PropertyValue::List(b)) => {
+                for (x, y) in a.iter().zip(b.iter()) {
+                    let cmp = x.sort_cmp(y);
+                    if cmp != Ordering::Equal {
+                        return cmp;
+                    }
+                }
+                a.len().cmp(&b.len())
+            }
+            _ => self.type_rank().cmp(&other.type_rank()),
+        }
+    }
+
+    /// Compare against a raw query operand, interpreted using this value's type
+    ///
+    /// Returns None when the operand can't be interpreted as this type.
+    fn compare_to(&self, operand: &str) -> Option<Ordering> {
+        match self {
+            PropertyValue::Number(n) => {
+                let other: f64 = operand.trim().parse().ok()?;
+                n.partial_cmp(&other)
+            }
+            PropertyValue::Date(d) => {
+                let other = parse_date_operand(operand)?;
+                Some(d.cmp(&other))
+            }
+            PropertyValue::Bool(b) => {
+                let other = parse_bool(operand)?;
+                Some(b.cmp(&other))
+            }
+            PropertyValue::String(s) => Some(s.to_lowercase().cmp(&operand.trim().to_lowercase())),
+            PropertyValue::List(_) => None,
+        }
+    }
+
+    /// Check if the value contains the operand (case-insensitive substring)
+    fn contains(&self, operand: &str) -> bool {
+        self.to_string()
+            .to_lowercase()
+            .contains(&operand.trim().to_lowercase())
+    }
+}
+
+impl fmt::Display for PropertyValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PropertyValue::String(s) => write!(f, "{}", s),
+            PropertyValue::Number(n) => write!(f, "{}", n),
+            PropertyValue::Date(d) => write!(f, "{}", d.format("%Y-%m-%d")),
+            PropertyValue::Bool(b) => write!(f, "{}", b),
+            PropertyValue::List(items) => {
+                let parts: Vec<String> = items.iter().map(|i| i.to_string()).collect();
+                write!(f, "{}", parts.join(", "))
+            }
+        }
+    }
+}
+
+/// Parse a date in YYYY-MM-DD or RFC3339 format
+fn parse_date(s: &str) -> Option<NaiveDate> {
+    let s = s.trim();
+    if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+        return Some(date);
+    }
+    DateTime::parse_from_rfc3339(s).ok().map(|dt| dt.date_naive())
+}
+
+/// Parse a date operand, which may also be the keyword `today`
+fn parse_date_operand(s: &str) -> Option<NaiveDate> {
+    if s.trim().eq_ignore_ascii_case("today") {
+        return Some(chrono::Local::now().date_naive());
+    }
+    parse_date(s)
+}
+
+/// Parse a boolean operand
+fn parse_bool(s: &str) -> Option<bool> {
+    match s.trim().to_lowercase().as_str() {
+        "true" | "yes" => Some(true),
+        "false" | "no" => Some(false),
+        _ => None,
+    }
+}
+
+// ============================================================================
+// Filters
+// ============================================================================
+
+/// Comparison operator in a property filter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareOp {
+    Eq,
+    NotEq,
+    Lt,
+    LtEq,
+    Gt,
+    GtEq,
+    /// Case-insensitive substring match
+    Contains,
+    /// Property is present
+    Exists,
+    /// Property is absent
+    Missing,
+}
+
+/// A single `--where` condition such as `status=active` or `rating>=4`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PropertyFilter {
+    pub key: String,
+    pub op: CompareOp,
+    pub operand: String,
+}
+
+impl PropertyFilter {
+    /// Parse a filter expression
+    ///
+    /// Supported forms: `key=value`, `key!=value`, `key<value`, `key<=value`,
+    /// `key>value`, `key>=value`, `key~text`, `key` (exists) and `!key` (missing).
+    pub fn parse(s: &str) -> Result<Self> {
+        let s = s.trim();
+
+        // Two-character operators must be checked before their one-character prefixes
+        const OPERATORS: [(&str, CompareOp); 7] = [
+            ("!=", CompareOp::NotEq),
+            ("<=", CompareOp::LtEq),
+            (">=", CompareOp::GtEq),
+            ("=", CompareOp::Eq),
+            ("<", CompareOp::Lt),
+            (">", CompareOp::Gt),
+            ("~", CompareOp::Contains),
+        ];
+
+        let first_op = OPERATORS
+            .iter()
+            .filter_map(|(token, op)| s.find(token).map(|pos| (pos, *token, *op)))
+            .min_by_key(|(pos, token, _)| (*pos, std::cmp::Reverse(token.len())));
+
+        let filter = match first_op {
+            Some((pos, token, op)) => PropertyFilter {
+                key: s[..pos].trim().to_string(),
+                op,
+                operand: s[pos + token.len()..].trim().to_string(),
+            },
+            None => match s.strip_prefix('!') {
+                Some(key) => PropertyFilter {
+                    key: key.trim().to_string(),
+                    op: CompareOp::Missing,
+                    operand: String::new(),
+                },
+                None => PropertyFilter {
+                    key: s.to_string(),
+                    op: CompareOp::Exists,
+                    operand: String::new(),
+                },
+            },
+        };
+
+        if filter.key.is_empty() || filter.key.contains(char::is_whitespace) {
+            anyhow::bail!("Invalid property filter: '{}'. Expected e.g. 'status=active' or 'rating>=4'", s);
+        }
+
+        Ok(filter)
+    }
+
+    /// Check whether a note satisfies this filter
+    pub fn matches(&self, note: &Note) -> bool {
+        let value = note.property(&self.key);
+
+        match self.op {
+            CompareOp::Exists => value.is_some(),
+            CompareOp::Missing => value.is_none(),
+            // A missing property is "not equal" to anything
+            CompareOp::NotEq => match value {
+                None => true,
+                Some(v) => !Self::value_matches(&v, CompareOp::Eq, &self.operand),
+            },
+            _ => match value {
+                None => false,
+                Some(v) => Self::value_matches(&v, self.op, &self.operand),
+            },
+        }
+    }
+
+    fn value_matches(value: &PropertyValue, op: CompareOp, operand: &str) -> bool {
+        match value {
+            // Lists match if any element matches
+            PropertyValue::List(items) => match op {
+                CompareOp::Contains => value.contains(operand),
+                _ => items.iter().any(|item| Self::value_matches(item, op, operand)),
+            },
+            _ => {
+                if op == CompareOp::Contains {
+                    return value.contains(operand);
+                }
+
+                let Some(ordering) = value.compare_to(operand) else {
+                    return false;
+                };
+
+                match op {
+                    CompareOp::Eq => ordering == Ordering::Equal,
+                    CompareOp::NotEq => ordering != Ordering::Equal,
+                    CompareOp::Lt => ordering == Ordering::Less,
+                    CompareOp::LtEq => ordering != Ordering::Greater,
+                    CompareOp::Gt => ordering == Ordering::Greater,
+                    CompareOp::GtEq => ordering != Ordering::Less,
+                    CompareOp::Contains | CompareOp::Exists | CompareOp::Missing => false,
+                }
+            }
+        }
+    }
+}
+
+/// Sort notes by a property; a leading `-` sorts descending
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PropertySort {
+    pub key: String,
+    pub descending: bool,
+}
+
+impl PropertySort {
+    /// Parse a sort key such as `rating` or `-rating`
+    pub fn parse(s: &str) -> Result<Self> {
+        let s = s.trim();
+        let (descending, key) = match s.strip_prefix('-') {
+            Some(key) => (true, key.trim()),
+            None => (false, s),
+        };
+
+        if key.is_empty() {
+            anyhow::bail!("Sort property cannot be empty");
+        }
+
+        Ok(Self {
+            key: key.to_string(),
+            descending,
+        })
+    }
+
+    /// Compare two notes by this property; notes missing it always sort last
+    pub fn compare(&self, a: &Note, b: &Note) -> Ordering {
+        match (a.property(&self.key), b.property(&self.key)) {
+            (Some(a_val), Some(b_val)) => {
+                let cmp = a_val.sort_cmp(&b_val);
+                if self.descending { cmp.reverse() } else { cmp }
+            }
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        }
+        .then_with(|| a.title.cmp(&b.title))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn book() -> Note {
+        Note::parse(
+            Path::new("book.md"),
+            r#"---
+tags: [book, reading]
+status: active
+rating: 4
+due: 2026-10-15
+finished: false
+authors: [Ursula Le Guin]
+---
+
+# The Dispossessed
+"#,
+        )
+        .unwrap()
+    }
+
+    fn matches(expr: &str) -> bool {
+        PropertyFilter::parse(expr).unwrap().matches(&book())
+    }
+
+    #[test]
+    fn test_parse_filter_operators() {
+        let f = PropertyFilter::parse("rating>=4").unwrap();
+        assert_eq!(f.key, "rating");
+        assert_eq!(f.op, CompareOp::GtEq);
+        assert_eq!(f.operand, "4");
+
+        assert_eq!(PropertyFilter::parse("status != done").unwrap().op, CompareOp::NotEq);
+        assert_eq!(PropertyFilter::parse("due<2026-11-01").unwrap().op, CompareOp::Lt);
+        assert_eq!(PropertyFilter::parse("status").unwrap().op, CompareOp::Exists);
+        assert_eq!(PropertyFilter::parse("!status").unwrap().op, CompareOp::Missing);
+        assert!(PropertyFilter::parse("=active").is_err());
+    }
+
+    #[test]
+    fn test_typed_comparisons() {
+        assert!(matches("status=active"));
+        assert!(matches("status=Active"));
+        assert!(!matches("status=done"));
+        assert!(matches("status!=done"));
+
+        // Numbers compare numerically, not lexically
+        assert!(matches("rating>=4"));
+        assert!(!matches("rating>10"));
+        assert!(matches("rating<10"));
+
+        assert!(matches("due<2026-11-01"));
+        assert!(!matches("due>2026-11-01"));
+
+        assert!(matches("finished=false"));
+        assert!(matches("finished=no"));
+
+        // Lists match if any element matches
+        assert!(matches("tags=reading"));
+        assert!(matches("authors~guin"));
+        assert!(!matches("tags=work"));
+
+        assert!(matches("title~dispossessed"));
+        assert!(!matches("missing=1"));
+        assert!(matches("missing!=1"));
+        assert!(matches("!missing"));
+    }
+
+    #[test]
+    fn test_sort_missing_last() {
+        let rated = book();
+        let unrated = Note::parse(Path::new("other.md"), "# Another Book").unwrap();
+
+        let sort = PropertySort::parse("-rating").unwrap();
+        assert!(sort.descending);
+        assert_eq!(sort.compare(&rated, &unrated), Ordering::Less);
+        assert_eq!(sort.compare(&unrated, &rated), Ordering::Greater);
+    }
+
+    #[test]
+    fn test_display_values() {
+        let note = book();
+        assert_eq!(note.property("rating").unwrap().to_string(), "4");
+        assert_eq!(note.property("due").unwrap().to_string(), "2026-10-15");
+        assert_eq!(note.property("tags").unwrap().to_string(), "book, reading");
+    }
+}
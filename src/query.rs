@@ -0,0 +1,503 @@
+//! Task query expressions
+//!
+//! A [`TaskQuery`] selects tasks with an expression such as
+//! `status:open and (@work or @oncall) and not @waiting and due<=+3d`.
+//! Terms next to each other are ANDed; `and`, `or`, `not` and parentheses
+//! combine them. Terms are:
+//!
+//! - `@work` or `tag:work` - tagged `work` or anything under `work/`
+//! - `status:open` - `open`, `closed`, `all`, a status name or marker
+//! - `note:2026-W*` - note title, `*` and `?` wildcards, case-insensitive
+//! - `due<=+3d` - due date compared with `<`, `<=`, `>`, `>=` or `:`, against
+//!   a date, `today`, `tomorrow`, `yesterday` or `+3d`/`-1w`; `due:none` and
+//!   `due:any` check for a due date at all
+//! - `priority:A`, `urgency:!!` (or `none`), `id:t-4f2a`, `is:blocked`
+//! - any other word or `"quoted phrase"` - text the task contains
+
+use crate::dependencies::DependencyGraph;
+use crate::note::{Task, TaskStatus, TaskVocabulary};
+use crate::tags::{normalize, tag_matches, TagFilter};
+use anyhow::Result;
+use chrono::{NaiveDate, TimeDelta};
+use std::cmp::Ordering;
+use wildmatch::WildMatch;
+
+/// What a query is checked against besides the task itself
+pub struct QueryContext<'a> {
+    pub vocabulary: &'a TaskVocabulary,
+    /// Date relative due dates like `+3d` count from
+    pub today: NaiveDate,
+    /// Needed for `is:blocked`; without it no task is blocked
+    pub dependencies: Option<&'a DependencyGraph>,
+}
+
+/// A parsed task filter; the empty query matches every task
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TaskQuery {
+    expr: Option<Expr>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Term(Term),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Term {
+    Tag(String),
+    /// `--tag` arguments, which have their own syntax
+    Tags(TagFilter),
+    Status(String),
+    Note(String),
+    Due(Ordering, bool, DateSpec),
+    HasDue(bool),
+    Priority(Option<String>),
+    Urgency(Option<String>),
+    Id(String),
+    Blocked,
+    Text(String),
+}
+
+/// A date in a query, relative ones resolved when matching
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DateSpec {
+    Date(NaiveDate),
+    Days(i64),
+}
+
+impl TaskQuery {
+    /// Parse a query expression
+    pub fn parse(expression: &str) -> Result<Self> {
+        let tokens = tokenize(expression)?;
+        if tokens.is_empty() {
+            return Ok(Self::default());
+        }
+
+        let mut parser = QueryParser { tokens, pos: 0 };
+        let expr = parser.or()?;
+        if let Some(token) = parser.tokens.get(parser.pos) {
+            anyhow::bail!("Invalid task query: unexpected '{}'", token.text());
+        }
+        Ok(Self { expr: Some(expr) })
+    }
+
+    /// Tasks matching `--tag` arguments (see [`TagFilter`])
+    pub fn tags(args: &[String]) -> Result<Self> {
+        let filter = TagFilter::parse(args)?;
+        Ok(if filter.is_empty() { Self::default() } else { Self::term(Term::Tags(filter)) })
+    }
+
+    /// Tasks matching a `--status` filter
+    pub fn status(filter: &str) -> Self {
+        Self::term(Term::Status(filter.trim().to_string()))
+    }
+
+    /// Tasks in notes whose title matches a `*` pattern
+    pub fn note(pattern: &str) -> Self {
+        Self::term(Term::Note(pattern.to_lowercase()))
+    }
+
+    /// Tasks waiting on unfinished tasks
+    pub fn blocked() -> Self {
+        Self::term(Term::Blocked)
+    }
+
+    fn term(term: Term) -> Self {
+        Self { expr: Some(Expr::Term(term)) }
+    }
+
+    /// Tasks matching both queries
+    pub fn and(self, other: TaskQuery) -> Self {
+        match (self.expr, other.expr) {
+            (Some(a), Some(b)) => Self { expr: Some(Expr::And(Box::new(a), Box::new(b))) },
+            (a, b) => Self { expr: a.or(b) },
+        }
+    }
+
+    /// Tasks not matching the query
+    pub fn negate(self) -> Self {
+        Self { expr: self.expr.map(|expr| Expr::Not(Box::new(expr))) }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.expr.is_none()
+    }
+
+    /// Check that every status in the query is known
+    pub fn validate(&self, vocabulary: &TaskVocabulary) -> Result<()> {
+        fn visit(expr: &Expr, vocabulary: &TaskVocabulary) -> Result<()> {
+            match expr {
+                Expr::And(a, b) | Expr::Or(a, b) => {
+                    visit(a, vocabulary)?;
+                    visit(b, vocabulary)
+                }
+                Expr::Not(inner) => visit(inner, vocabulary),
+                Expr::Term(Term::Status(filter)) => {
+                    vocabulary.matches_filter(&TaskStatus::Uncompleted, filter).map(|_| ())
+                }
+                Expr::Term(_) => Ok(()),
+            }
+        }
+        self.expr.as_ref().map_or(Ok(()), |expr| visit(expr, vocabulary))
+    }
+
+    pub fn matches(&self, task: &Task, ctx: &QueryContext) -> bool {
+        self.expr.as_ref().is_none_or(|expr| expr.matches(task, ctx))
+    }
+}
+
+impl Expr {
+    fn matches(&self, task: &Task, ctx: &QueryContext) -> bool {
+        match self {
+            Expr::And(a, b) => a.matches(task, ctx) && b.matches(task, ctx),
+            Expr::Or(a, b) => a.matches(task, ctx) || b.matches(task, ctx),
+            Expr::Not(inner) => !inner.matches(task, ctx),
+            Expr::Term(term) => term.matches(task, ctx),
+        }
+    }
+}
+
+impl Term {
+    fn matches(&self, task: &Task, ctx: &QueryContext) -> bool {
+        match self {
+            Term::Tag(tag) => task.tags.iter().any(|t| tag_matches(t, tag)),
+            Term::Tags(filter) => filter.matches(&task.tags),
+            Term::Status(filter) => ctx.vocabulary.matches_filter(&task.status, filter).unwrap_or(false),
+            Term::Note(pattern) => WildMatch::new(pattern).matches(&task.note_title.to_lowercase()),
+            Term::Due(ordering, or_equal, date) => task.due.is_some_and(|due| {
+                let date = match date {
+                    DateSpec::Date(date) => *date,
+                    // Past the end of the calendar counts as the first or last date
+                    DateSpec::Days(days) => TimeDelta::try_days(*days)
+                        .and_then(|delta| ctx.today.checked_add_signed(delta))
+                        .unwrap_or(if *days < 0 { NaiveDate::MIN } else { NaiveDate::MAX }),
+                };
+                let cmp = due.cmp(&date);
+                cmp == *ordering || (*or_equal && cmp == Ordering::Equal)
+            }),
+            Term::HasDue(has) => task.due.is_some() == *has,
+            Term::Priority(priority) => {
+                task.priority.as_ref().map(|p| p.to_uppercase()) == priority.as_ref().map(|p| p.to_uppercase())
+            }
+            Term::Urgency(urgency) => task.urgency == *urgency,
+            Term::Id(id) => task.id == *id,
+            Term::Blocked => ctx.dependencies.is_some_and(|graph| graph.is_blocked(task)),
+            Term::Text(text) => task.text.to_lowercase().contains(text),
+        }
+    }
+
+    /// Parse one term, e.g. `@work`, `due<=+3d` or `draft`
+    fn parse(word: &str, quoted: bool) -> Result<Term> {
+        if quoted {
+            return Ok(Term::Text(word.to_lowercase()));
+        }
+        if let Some(tag) = word.strip_prefix('@') {
+            return Self::tag(tag, word);
+        }
+
+        let key_end = word.find(|c: char| !c.is_ascii_alphabetic()).unwrap_or(word.len());
+        let (key, rest) = word.split_at(key_end);
+        let op = ["<=", ">=", "<", ">", ":", "="].into_iter().find(|op| rest.starts_with(op));
+        let (Some(op), false) = (op, key.is_empty()) else {
+            return Ok(Term::Text(unquote(word).to_lowercase()));
+        };
+        let value = unquote(&rest[op.len()..]);
+        let equality = op == ":" || op == "=";
+        let invalid = || anyhow::anyhow!("Invalid task query term '{}'", word);
+
+        let term = match key.to_lowercase().as_str() {
+            "due" => match (equality, value.to_lowercase().as_str()) {
+                (true, "none") => Term::HasDue(false),
+                (true, "any") => Term::HasDue(true),
+                _ => {
+                    let date = parse_date_spec(value).ok_or_else(invalid)?;
+                    match op {
+                        "<" => Term::Due(Ordering::Less, false, date),
+                        "<=" => Term::Due(Ordering::Less, true, date),
+                        ">" => Term::Due(Ordering::Greater, false, date),
+                        ">=" => Term::Due(Ordering::Greater, true, date),
+                        _ => Term::Due(Ordering::Equal, true, date),
+                    }
+                }
+            },
+            _ if !equality => return Err(invalid()),
+            _ if value.is_empty() => return Err(invalid()),
+            "tag" => return Self::tag(value, word),
+            "status" => Term::Status(value.to_string()),
+            "note" => Term::Note(value.to_lowercase()),
+            "priority" => Term::Priority((!value.eq_ignore_ascii_case("none")).then(|| value.to_string())),
+            "urgency" => match value {
+                "!" | "!!" | "!!!" => Term::Urgency(Some(value.to_string())),
+                _ if value.eq_ignore_ascii_case("none") => Term::Urgency(None),
+                _ => return Err(invalid()),
+            },
+            "id" => Term::Id(value.trim_start_matches('^').to_string()),
+            "is" if value.eq_ignore_ascii_case("blocked") => Term::Blocked,
+            "text" => Term::Text(value.to_lowercase()),
+            _ => anyhow::bail!(
+                "Unknown task query field '{}'. Use tag, status, note, due, priority, urgency, id, is or text.",
+                key
+            ),
+        };
+        Ok(term)
+    }
+
+    fn tag(tag: &str, word: &str) -> Result<Term> {
+        let tag = normalize(tag);
+        if tag.is_empty() {
+            anyhow::bail!("Invalid task query term '{}'", word);
+        }
+        Ok(Term::Tag(tag))
+    }
+}
+
+/// `2026-11-02`, `today`, `tomorrow`, `yesterday`, or days/weeks from today like `+3d` or `-1w`
+fn parse_date_spec(value: &str) -> Option<DateSpec> {
+    let value = value.to_lowercase();
+    match value.as_str() {
+        "today" => return Some(DateSpec::Days(0)),
+        "tomorrow" => return Some(DateSpec::Days(1)),
+        "yesterday" => return Some(DateSpec::Days(-1)),
+        _ => {}
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(&value, "%Y-%m-%d") {
+        return Some(DateSpec::Date(date));
+    }
+
+    let (sign, rest) = match value.as_bytes().first()? {
+        b'+' => (1, &value[1..]),
+        b'-' => (-1, &value[1..]),
+        _ => (1, value.as_str()),
+    };
+    let (number, unit) = rest.split_at(rest.char_indices().last()?.0);
+    let number: i64 = number.parse().ok()?;
+    let days = match unit {
+        "d" => Some(number),
+        "w" => number.checked_mul(7),
+        _ => None,
+    }?
+    .checked_mul(sign)?;
+    // No offset this large lands on a date from any day
+    let delta = TimeDelta::try_days(days)?;
+    NaiveDate::MIN.checked_add_signed(delta.abs())?;
+    Some(DateSpec::Days(days))
+}
+
+fn unquote(value: &str) -> &str {
+    value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .unwrap_or(value)
+}
+
+// ============================================================================
+// Parsing
+// ============================================================================
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Open,
+    Close,
+    Word { text: String, quoted: bool },
+}
+
+impl Token {
+    fn text(&self) -> &str {
+        match self {
+            Token::Open => "(",
+            Token::Close => ")",
+            Token::Word { text, .. } => text,
+        }
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self, Token::Word { text, quoted: false } if text.eq_ignore_ascii_case(keyword))
+    }
+}
+
+/// Split an expression into words and parentheses
+///
+/// Quotes group spaces into one word, either a whole `"phrase"` or a
+/// value like `note:"Weekly review"`.
+fn tokenize(expression: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = expression.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            _ if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::Open);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::Close);
+            }
+            _ => {
+                let quoted = c == '"';
+                let mut text = String::new();
+                let mut in_quotes = false;
+                while let Some(&c) = chars.peek() {
+                    if !in_quotes && (c.is_whitespace() || c == '(' || c == ')') {
+                        break;
+                    }
+                    if c == '"' {
+                        in_quotes = !in_quotes;
+                    }
+                    text.push(c);
+                    chars.next();
+                }
+                if in_quotes {
+                    anyhow::bail!("Invalid task query: unclosed quote in {}", text);
+                }
+                if quoted && text.len() >= 2 && text.ends_with('"') {
+                    text = text[1..text.len() - 1].to_string();
+                    tokens.push(Token::Word { text, quoted: true });
+                } else {
+                    tokens.push(Token::Word { text, quoted: false });
+                }
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Recursive descent: `or` binds loosest, then `and` (or nothing), then `not`
+struct QueryParser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl QueryParser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn or(&mut self) -> Result<Expr> {
+        let mut expr = self.and()?;
+        while self.peek().is_some_and(|t| t.is_keyword("or")) {
+            self.pos += 1;
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr> {
+        let mut expr = self.unary()?;
+        loop {
+            match self.peek() {
+                Some(t) if t.is_keyword("and") => self.pos += 1,
+                // Terms next to each other are ANDed too
+                Some(t) if *t != Token::Close && !t.is_keyword("or") => {}
+                _ => return Ok(expr),
+            }
+            expr = Expr::And(Box::new(expr), Box::new(self.unary()?));
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr> {
+        let token = self.peek().cloned().ok_or_else(|| anyhow::anyhow!("Invalid task query: expected a term at the end"))?;
+        self.pos += 1;
+        match token {
+            Token::Open => {
+                let expr = self.or()?;
+                if self.peek() != Some(&Token::Close) {
+                    anyhow::bail!("Invalid task query: missing ')'");
+                }
+                self.pos += 1;
+                Ok(expr)
+            }
+            Token::Close => anyhow::bail!("Invalid task query: unexpected ')'"),
+            _ if token.is_keyword("not") => Ok(Expr::Not(Box::new(self.unary()?))),
+            _ if token.is_keyword("and") || token.is_keyword("or") => {
+                anyhow::bail!("Invalid task query: unexpected '{}'", token.text())
+            }
+            Token::Word { text, quoted } => Ok(Expr::Term(Term::parse(&text, quoted)?)),
+        }
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::note::Note;
+    use std::path::Path;
+
+    fn select(query: &str, tasks: &[Task]) -> Vec<String> {
+        let vocabulary = TaskVocabulary::default();
+        let graph = DependencyGraph::build(tasks.to_vec());
+        let ctx = QueryContext {
+            vocabulary: &vocabulary,
+            today: NaiveDate::from_ymd_opt(2026, 10, 18).unwrap(),
+            dependencies: Some(&graph),
+        };
+        let query = TaskQuery::parse(query).unwrap();
+        query.validate(&vocabulary).unwrap();
+        tasks.iter().filter(|t| query.matches(t, &ctx)).map(|t| t.text.clone()).collect()
+    }
+
+    #[test]
+    fn test_query_matches() {
+        let note = Note::parse(
+            Path::new("2026-W42.md"),
+            "# 2026-W42\n\n- [ ] Page @oncall due:2026-10-19\n- [ ] Deploy @work/ops due:2026-10-25 ^t-dep\n- [ ] Reply @work @waiting\n- [x] Done @work\n- [ ] (A) Write \"launch\" post blocked-by:^t-dep\n",
+        )
+        .unwrap();
+        let tasks = Task::extract_from_note(&note);
+
+        assert_eq!(
+            select("status:open and (@work or @oncall) and not @waiting and due<=+3d", &tasks),
+            vec!["Page"]
+        );
+        assert_eq!(select("@work not @waiting status:open", &tasks), vec!["Deploy"]);
+        assert_eq!(select("due:none and status:closed or priority:a", &tasks), vec!["Done", "Write \"launch\" post"]);
+        assert_eq!(select("due>tomorrow", &tasks), vec!["Deploy"]);
+        assert_eq!(select("note:2026-W* \"launch\" post", &tasks), vec!["Write \"launch\" post"]);
+        assert_eq!(select("is:blocked", &tasks), vec!["Write \"launch\" post"]);
+        assert_eq!(select("id:^t-dep", &tasks), vec!["Deploy"]);
+        assert_eq!(select("", &tasks).len(), 5);
+    }
+
+    #[test]
+    fn test_query_errors() {
+        for bad in ["(@work", "@work)", "and @work", "not", "due<soon", "colour:red", "tag:", "urgency:!!!!", "\"open"] {
+            assert!(TaskQuery::parse(bad).is_err(), "{} should not parse", bad);
+        }
+
+        let query = TaskQuery::parse("status:someday").unwrap();
+        assert!(query.validate(&TaskVocabulary::default()).is_err());
+    }
+
+    #[test]
+    fn test_date_spec() {
+        assert_eq!(parse_date_spec("+3d"), Some(DateSpec::Days(3)));
+        assert_eq!(parse_date_spec("-2w"), Some(DateSpec::Days(-14)));
+        assert_eq!(parse_date_spec("today"), Some(DateSpec::Days(0)));
+        assert_eq!(parse_date_spec("2026-11-02"), NaiveDate::from_ymd_opt(2026, 11, 2).map(DateSpec::Date));
+        assert_eq!(parse_date_spec("soon"), None);
+
+        // Offsets that overflow are rejected rather than panicking
+        assert_eq!(parse_date_spec("+9999999999999999w"), None);
+        assert_eq!(parse_date_spec("-9223372036854775807d"), None);
+        assert_eq!(parse_date_spec("+999999999d"), None);
+        let err = TaskQuery::parse("due<=+9999999999999999w").unwrap_err();
+        assert!(err.to_string().contains("Invalid task query term"));
+
+        // Large but valid offsets clamp to the ends of the calendar when matching
+        let note = Note::parse(Path::new("a.md"), "# A\n\n- [ ] Later due:2026-11-02\n").unwrap();
+        let tasks = Task::extract_from_note(&note);
+        assert_eq!(select("due<=+99999999d", &tasks), vec!["Later"]);
+        assert!(select("due<=-99999999d", &tasks).is_empty());
+    }
+}
@@ -0,0 +1,434 @@
+//! Safe repairs for problems found by health checks
+//!
+//! A [`Fix`] is a small, targeted edit to one note. Fixes are applied to a
+//! note's current content, so several can be previewed and applied to the
+//! same note one after another, and callers can show a diff before writing.
+
+use crate::frontmatter::FrontmatterEditor;
+use crate::note::{Note, TaskVocabulary};
+use crate::repository::{extract_wiki_links, LinkGraph};
+use crate::tags::normalize;
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde_yaml::Value;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Kinds of problems `doctor --fix` can repair
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RepairKind {
+    Frontmatter,
+    Links,
+    Titles,
+    Orphans,
+}
+
+/// An edit that repairs one problem in a note
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Fix {
+    /// Add `tags` and `created` to a note that has neither
+    AddFrontmatter { created: DateTime<Utc> },
+    /// Point `[[from]]` links at another title
+    RetargetLink { from: String, to: String },
+    /// Change the note's title where it is defined
+    SetTitle(String),
+    AddTag(String),
+    /// Append a link to another note
+    AddLink(String),
+}
+
+impl Fix {
+    /// Apply the fix to a note's content
+    pub fn apply(&self, content: &str) -> String {
+        match self {
+            Fix::AddFrontmatter { created } => {
+                let mut editor = FrontmatterEditor::parse(content);
+                if editor.get("tags").is_none() {
+                    set_or_keep(&mut editor, "tags", &Value::Sequence(Vec::new()));
+                }
+                if editor.get("created").is_none() {
+                    let created = created.to_rfc3339_opts(SecondsFormat::Secs, true);
+                    set_or_keep(&mut editor, "created", &Value::String(created));
+                }
+                editor.render()
+            }
+            Fix::RetargetLink { from, to } => retarget_links(content, from, to),
+            Fix::SetTitle(title) => set_title(content, title),
+            Fix::AddTag(tag) => {
+                let mut editor = FrontmatterEditor::parse(content);
+                let value = match editor.get("tags") {
+                    Some(Value::String(existing)) if !existing.trim().is_empty() => {
+                        Value::String(format!("{}, {}", existing, tag))
+                    }
+                    Some(Value::Sequence(mut items)) => {
+                        items.push(Value::String(tag.clone()));
+                        Value::Sequence(items)
+                    }
+                    _ => Value::Sequence(vec![Value::String(tag.clone())]),
+                };
+                set_or_keep(&mut editor, "tags", &value);
+                editor.render()
+            }
+            Fix::AddLink(title) => {
+                let mut content = content.to_string();
+                if !content.is_empty() && !content.ends_with('\n') {
+                    content.push('\n');
+                }
+                if !content.is_empty() && !content.ends_with("\n\n") {
+                    content.push('\n');
+                }
+                content.push_str(&format!("Related: [[{}]]\n", title));
+                content
+            }
+        }
+    }
+
+    /// Short description of what the fix does
+    pub fn describe(&self) -> String {
+        match self {
+            Fix::AddFrontmatter { .. } => "add frontmatter".to_string(),
+            Fix::RetargetLink { from, to } => format!("change [[{}]] to [[{}]]", from, to),
+            Fix::SetTitle(title) => format!("rename to '{}'", title),
+            Fix::AddTag(tag) => format!("tag with '{}'", tag),
+            Fix::AddLink(title) => format!("link to [[{}]]", title),
+        }
+    }
+}
+
+/// Set a key, leaving the document alone if the value can't be written
+fn set_or_keep(editor: &mut FrontmatterEditor, key: &str, value: &Value) {
+    let _ = editor.set(key, value);
+}
+
+/// A broken link and the existing titles it most likely meant
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BrokenLink {
+    pub path: PathBuf,
+    pub note_title: String,
+    pub target: String,
+    /// Closest titles by edit distance, best first
+    pub suggestions: Vec<String>,
+}
+
+/// An orphaned note with tags and notes it could be connected to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Orphan {
+    pub path: PathBuf,
+    pub title: String,
+    /// Most used tags in the collection
+    pub tags: Vec<String>,
+    /// Notes whose titles are mentioned in the orphan without a link
+    pub links: Vec<String>,
+}
+
+/// Everything `doctor --fix` can offer to repair
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RepairPlan {
+    /// Notes without tags or dates
+    pub missing_frontmatter: Vec<PathBuf>,
+    pub broken_links: Vec<BrokenLink>,
+    /// New titles for all but one note of each duplicated title
+    pub retitles: Vec<(PathBuf, String, String)>,
+    pub orphans: Vec<Orphan>,
+}
+
+/// Number of suggestions offered per broken link or orphan
+const MAX_SUGGESTIONS: usize = 5;
+
+/// Work out what can be repaired in a collection of notes
+pub(crate) fn plan(all_notes: &[Note], vocabulary: &TaskVocabulary) -> RepairPlan {
+    let mut notes: Vec<&Note> = all_notes.iter().collect();
+    notes.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let missing_frontmatter = notes
+        .iter()
+        .filter(|n| n.tags.is_empty() && n.created.is_none() && n.updated.is_none())
+        .map(|n| n.path.clone())
+        .collect();
+
+    // Broken links, with suggestions from existing titles
+    let titles: Vec<&str> = notes.iter().map(|n| n.title.as_str()).collect();
+    let known: HashSet<String> = titles.iter().map(|t| t.to_lowercase()).collect();
+    let mut broken_links = Vec::new();
+    for note in &notes {
+        let mut seen = HashSet::new();
+        for target in extract_wiki_links(&note.content) {
+            if known.contains(&target.to_lowercase()) || !seen.insert(target.to_lowercase()) {
+                continue;
+            }
+            broken_links.push(BrokenLink {
+                path: note.path.clone(),
+                note_title: note.title.clone(),
+                suggestions: closest_titles(&target, &titles),
+                target,
+            });
+        }
+    }
+
+    // Keep the first note of each duplicated title, rename the rest
+    let mut taken = known.clone();
+    let mut by_title: HashMap<String, Vec<&Note>> = HashMap::new();
+    for note in &notes {
+        by_title.entry(note.title.to_lowercase()).or_default().push(note);
+    }
+    let mut retitles = Vec::new();
+    for note in &notes {
+        let group = &by_title[&note.title.to_lowercase()];
+        if group.len() < 2 || std::ptr::eq(*note, group[0]) {
+            continue;
+        }
+        let title = disambiguate(&note.title, &note.path, &taken);
+        taken.insert(title.to_lowercase());
+        retitles.push((note.path.clone(), note.title.clone(), title));
+    }
+
+    // Orphans, with the most used tags and unlinked mentions as choices
+    let graph = LinkGraph::build(all_notes, vocabulary);
+    let all_titles: Vec<String> = titles.iter().map(|t| t.to_string()).collect();
+    let orphaned: HashSet<String> = graph.orphaned_notes(&all_titles).into_iter().collect();
+    let popular = popular_tags(&notes);
+    let orphans = notes
+        .iter()
+        .filter(|n| n.tags.is_empty() && orphaned.contains(&n.title))
+        .map(|n| Orphan {
+            path: n.path.clone(),
+            title: n.title.clone(),
+            tags: popular.clone(),
+            links: mentioned_titles(n, &titles),
+        })
+        .collect();
+
+    RepairPlan {
+        missing_frontmatter,
+        broken_links,
+        retitles,
+        orphans,
+    }
+}
+
+/// Levenshtein distance between two strings, ignoring case
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.to_lowercase().chars().collect();
+    let b: Vec<char> = b.to_lowercase().chars().collect();
+
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+/// Titles close enough to a link target to be what it meant, best first
+fn closest_titles(target: &str, titles: &[&str]) -> Vec<String> {
+    let limit = (target.chars().count() / 3).max(2);
+
+    let mut scored: Vec<(usize, &str)> = titles
+        .iter()
+        .map(|title| (edit_distance(target, title), *title))
+        .filter(|(distance, _)| *distance <= limit)
+        .collect();
+    scored.sort();
+    scored.dedup_by(|a, b| a.1.eq_ignore_ascii_case(b.1));
+
+    scored
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, title)| title.to_string())
+        .collect()
+}
+
+/// A title for a duplicate that no other note uses, based on where it lives
+fn disambiguate(title: &str, path: &Path, taken: &HashSet<String>) -> String {
+    let parent = path
+        .parent()
+        .and_then(|p| p.file_name())
+        .map(|p| p.to_string_lossy().into_owned());
+    let stem = path.file_stem().map(|s| s.to_string_lossy().into_owned());
+
+    let candidates = parent
+        .into_iter()
+        .chain(stem.filter(|s| !s.eq_ignore_ascii_case(title)))
+        .map(|hint| format!("{} ({})", title, hint));
+
+    for candidate in candidates {
+        if !taken.contains(&candidate.to_lowercase()) {
+            return candidate;
+        }
+    }
+
+    (2..)
+        .map(|n| format!("{} ({})", title, n))
+        .find(|candidate| !taken.contains(&candidate.to_lowercase()))
+        .expect("some numbered title is free")
+}
+
+/// The most used tags across notes
+fn popular_tags(notes: &[&Note]) -> Vec<String> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for note in notes {
+        for tag in &note.tags {
+            *counts.entry(normalize(tag)).or_default() += 1;
+        }
+    }
+
+    let mut tags: Vec<(String, usize)> = counts.into_iter().collect();
+    tags.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    tags.into_iter().take(MAX_SUGGESTIONS).map(|(tag, _)| tag).collect()
+}
+
+/// Titles of other notes mentioned in a note's text
+fn mentioned_titles(note: &Note, titles: &[&str]) -> Vec<String> {
+    let content = note.content.to_lowercase();
+    titles
+        .iter()
+        .filter(|title| title.chars().count() >= 3 && !title.eq_ignore_ascii_case(&note.title))
+        .filter(|title| content.contains(&title.to_lowercase()))
+        .take(MAX_SUGGESTIONS)
+        .map(|title| title.to_string())
+        .collect()
+}
+
+/// Replace `[[from]]` links (in any case) with `[[to]]`
+fn retarget_links(content: &str, from: &str, to: &str) -> String {
+    let mut result = String::with_capacity(content.len());
+    let mut rest = content;
+
+    while let Some(begin) = rest.find("[[") {
+        let Some(end) = rest[begin + 2..].find("]]") else {
+            break;
+        };
+        let inner = &rest[begin + 2..begin + 2 + end];
+
+        result.push_str(&rest[..begin]);
+        if inner.to_lowercase() == from.to_lowercase() {
+            result.push_str(&format!("[[{}]]", to));
+        } else {
+            result.push_str(&rest[begin..begin + 2 + end + 2]);
+        }
+        rest = &rest[begin + 2 + end + 2..];
+    }
+
+    result.push_str(rest);
+    result
+}
+
+/// Change a note's title: the frontmatter title if set, otherwise the first H1
+fn set_title(content: &str, title: &str) -> String {
+    let mut editor = FrontmatterEditor::parse(content);
+    if editor.get("title").is_some() {
+        set_or_keep(&mut editor, "title", &Value::String(title.to_string()));
+        return editor.render();
+    }
+
+    let body = editor.body().to_string();
+    let mut in_code = false;
+    let mut offset = 0;
+    for line in body.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") {
+            in_code = !in_code;
+        } else if !in_code && trimmed.starts_with("# ") {
+            let ending = &line[line.trim_end_matches(['\r', '\n']).len()..];
+            let new_body = format!("{}# {}{}{}", &body[..offset], title, ending, &body[offset + line.len()..]);
+            editor.set_body(new_body);
+            return editor.render();
+        }
+        offset += line.len();
+    }
+
+    // Title came from the filename, so pin it in frontmatter
+    set_or_keep(&mut editor, "title", &Value::String(title.to_string()));
+    editor.render()
+}
+
+/// Unified diff between two versions of a note
+pub fn diff(path: &Path, old: &str, new: &str) -> String {
+    let patch = diffy::create_patch(old, new).to_string();
+    let name = path.display();
+
+    // diffy labels the files "original" and "modified"; use the note's path
+    patch
+        .replacen("--- original", &format!("--- a/{}", name), 1)
+        .replacen("+++ modified", &format!("+++ b/{}", name), 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn note(path: &str, content: &str) -> Note {
+        Note::parse(Path::new(path), content).unwrap()
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("Project", "project"), 0);
+        assert_eq!(edit_distance("", "abc"), 3);
+    }
+
+    #[test]
+    fn test_fixes() {
+        let created = DateTime::parse_from_rfc3339("2026-01-02T03:04:05Z").unwrap().with_timezone(&Utc);
+        assert_eq!(
+            Fix::AddFrontmatter { created }.apply("# Note\n"),
+            "---\ntags: []\ncreated: 2026-01-02T03:04:05Z\n---\n# Note\n"
+        );
+
+        let retarget = Fix::RetargetLink {
+            from: "projct plan".to_string(),
+            to: "Project Plan".to_string(),
+        };
+        assert_eq!(
+            retarget.apply("See [[Projct Plan]] and [[Other]].\n"),
+            "See [[Project Plan]] and [[Other]].\n"
+        );
+
+        assert_eq!(Fix::SetTitle("Meeting (work)".to_string()).apply("Intro\n# Meeting\n"), "Intro\n# Meeting (work)\n");
+        assert_eq!(
+            Fix::SetTitle("New".to_string()).apply("---\ntitle: Old # keep\n---\nBody\n"),
+            "---\ntitle: New # keep\n---\nBody\n"
+        );
+
+        assert_eq!(
+            Fix::AddTag("ideas".to_string()).apply("---\ntags: [a]\n---\n# Note\n"),
+            "---\ntags: [a, ideas]\n---\n# Note\n"
+        );
+        assert_eq!(Fix::AddLink("Hub".to_string()).apply("# Note\nText"), "# Note\nText\n\nRelated: [[Hub]]\n");
+    }
+
+    #[test]
+    fn test_plan() {
+        let notes = vec![
+            note("plan.md", "---\ntags: [work]\n---\n# Project Plan\n\nSee [[Projct Plan]].\n"),
+            note("work/meeting.md", "---\ntags: [work]\n---\n# Meeting\n"),
+            note("home/meeting.md", "---\ntags: [home]\n---\n# Meeting\n"),
+            note("loose.md", "# Loose\n\nThoughts about the project plan.\n"),
+        ];
+
+        let plan = plan(&notes, &TaskVocabulary::default());
+        assert_eq!(plan.missing_frontmatter, vec![PathBuf::from("loose.md")]);
+        assert_eq!(plan.broken_links.len(), 1);
+        assert_eq!(plan.broken_links[0].suggestions[0], "Project Plan");
+        assert_eq!(
+            plan.retitles,
+            vec![(PathBuf::from("work/meeting.md"), "Meeting".to_string(), "Meeting (work)".to_string())]
+        );
+
+        let loose = plan.orphans.iter().find(|o| o.title == "Loose").unwrap();
+        assert_eq!(loose.tags[0], "work");
+        assert_eq!(loose.links, vec!["Project Plan"]);
+    }
+
+    #[test]
+    fn test_diff_uses_path() {
+        let diff = diff(Path::new("note.md"), "a\n", "b\n");
+        assert!(diff.starts_with("--- a/note.md\n+++ b/note.md\n"));
+        assert!(diff.contains("-a\n+b\n"));
+    }
+}
@@ -4,10 +4,13 @@
 //! using the Storage abstraction for file access. This module also includes link
 //! analysis (LinkGraph) and health checking (HealthReport) functionality.
 
-use crate::note::{render_template, Note};
+use crate::note::{render_template, Note, Task, TaskVocabulary};
+use crate::lint::Finding;
+use crate::schema::SchemaViolation;
 use crate::storage::Storage;
+use crate::tags::TagFilter;
 use anyhow::{Context, Result};
-use pulldown_cmark::{Event, HeadingLevel, Parser, Tag, TagEnd};
+use pulldown_cmark::{Event, HeadingLevel, Options, Parser, Tag, TagEnd};
 use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 
@@ -103,15 +106,23 @@ fn find_content_matches(content: &str, query: &str) -> Vec<ContentMatch> {
     matches
 }
 
+/// Parser for finding headings, skipping over frontmatter
+///
+/// Without this, the closing `---` of frontmatter turns its last line into a
+/// setext heading.
+fn heading_parser(content: &str) -> Parser<'_> {
+    Parser::new_ext(content, Options::ENABLE_YAML_STYLE_METADATA_BLOCKS)
+}
+
 /// Build list of (position, breadcrumb) pairs by parsing headings
-fn build_heading_positions(content: &str) -> Vec<(usize, Vec<String>)> {
+pub(crate) fn build_heading_positions(content: &str) -> Vec<(usize, Vec<String>)> {
     let mut positions = Vec::new();
     let mut in_heading = false;
     let mut heading_text = String::new();
     let mut heading_level = HeadingLevel::H1;
 
     let breadcrumb_map = build_heading_breadcrumbs(content);
-    let parser = Parser::new(content);
+    let parser = heading_parser(content);
 
     for (event, range) in parser.into_offset_iter() {
         match event {
@@ -127,15 +138,13 @@ fn build_heading_positions(content: &str) -> Vec<(usize, Vec<String>)> {
 
                     if let Some(breadcrumb) = breadcrumb_map.get(&formatted) {
                         // Record that from this position onward, we're under this breadcrumb
-                        positions.push((range.end, breadcrumb.clone()));
+                        positions.push((range.start, breadcrumb.clone()));
                     }
                 }
                 in_heading = false;
             }
-            Event::Text(text) => {
-                if in_heading {
-                    heading_text.push_str(&text);
-                }
+            Event::Text(text) if in_heading => {
+                heading_text.push_str(&text);
             }
             _ => {}
         }
@@ -177,7 +186,7 @@ fn build_heading_breadcrumbs(content: &str) -> HashMap<String, Vec<String>> {
     let mut in_heading = false;
     let mut current_heading_level = HeadingLevel::H1;
 
-    let parser = Parser::new(content);
+    let parser = heading_parser(content);
 
     for event in parser {
         match event {
@@ -214,10 +223,8 @@ fn build_heading_breadcrumbs(content: &str) -> HashMap<String, Vec<String>> {
                 }
                 in_heading = false;
             }
-            Event::Text(text) => {
-                if in_heading {
-                    current_heading_text.push_str(&text);
-                }
+            Event::Text(text) if in_heading => {
+                current_heading_text.push_str(&text);
             }
             _ => {}
         }
@@ -259,14 +266,62 @@ impl Repository {
     }
 
     /// Discover all notes in the repository
+    ///
+    /// `.md` files that can't be loaded are left out; [`Repository::load_notes`]
+    /// says which.
     pub fn discover_notes(&self) -> Result<Vec<Note>> {
+        Ok(self.load_notes()?.0)
+    }
+
+    /// Discover all notes, along with the `.md` files that couldn't be loaded
+    pub fn load_notes(&self) -> Result<(Vec<Note>, Vec<NoteLoadError>)> {
         let mut notes = Vec::new();
-        self.discover_notes_recursive(Path::new(""), &mut notes)?;
-        Ok(notes)
+        let mut errors = Vec::new();
+        for path in self.discover_files()? {
+            // Only process .md files
+            if path.extension().and_then(|s| s.to_str()) != Some("md") {
+                continue;
+            }
+
+            match self.load_note(&path) {
+                Ok(note) => notes.push(note),
+                Err(error) => errors.push(error),
+            }
+        }
+        Ok((notes, errors))
     }
 
-    /// Recursively discover notes starting from the given path
-    fn discover_notes_recursive(&self, path: &Path, notes: &mut Vec<Note>) -> Result<()> {
+    /// Read and parse a single note
+    fn load_note(&self, path: &Path) -> std::result::Result<Note, NoteLoadError> {
+        let error = |line, message| NoteLoadError { path: path.to_path_buf(), line, message };
+
+        let bytes = self.storage.read(path).map_err(|e| error(None, format!("{:#}", e)))?;
+        let content = String::from_utf8(bytes).map_err(|e| {
+            let valid = &e.as_bytes()[..e.utf8_error().valid_up_to()];
+            let line = valid.iter().filter(|&&b| b == b'\n').count() + 1;
+            error(Some(line), "Not valid UTF-8 text".to_string())
+        })?;
+        Note::parse(path, &content).map_err(|e| error(None, format!("Failed to parse note: {:#}", e)))
+    }
+
+    /// Files in the notes directory that aren't loaded as notes
+    pub fn discover_skipped_files(&self) -> Result<Vec<PathBuf>> {
+        Ok(self
+            .discover_files()?
+            .into_iter()
+            .filter(|path| path.extension().and_then(|s| s.to_str()) != Some("md"))
+            .collect())
+    }
+
+    /// Every file outside hidden directories
+    fn discover_files(&self) -> Result<Vec<PathBuf>> {
+        let mut files = Vec::new();
+        self.discover_files_recursive(Path::new(""), &mut files)?;
+        Ok(files)
+    }
+
+    /// Recursively discover files starting from the given path
+    fn discover_files_recursive(&self, path: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
         // Skip if any component of the path starts with '.'
         for component in path.components() {
             if let Some(name_str) = component.as_os_str().to_str()
@@ -279,24 +334,10 @@ impl Repository {
         if self.storage.is_dir(path) {
             let entries = self.storage.read_dir(path)?;
             for entry in entries {
-                self.discover_notes_recursive(&entry, notes)?;
+                self.discover_files_recursive(&entry, files)?;
             }
         } else if self.storage.exists(path) {
-            // Only process .md files
-            if path.extension().and_then(|s| s.to_str()) == Some("md") {
-                // Read content and parse note
-                match self.storage.read_to_string(path) {
-                    Ok(content) => match Note::parse(path, &content) {
-                        Ok(note) => notes.push(note),
-                        Err(e) => {
-                            eprintln!("Warning: Failed to parse {}: {}", path.display(), e);
-                        }
-                    },
-                    Err(e) => {
-                        eprintln!("Warning: Failed to read {}: {}", path.display(), e);
-                    }
-                }
-            }
+            files.push(path.to_path_buf());
         }
 
         Ok(())
@@ -360,16 +401,13 @@ impl Repository {
         Ok(results)
     }
 
-    /// Filter notes by tags
-    pub fn filter_by_tags(&self, tags: &[String]) -> Result<Vec<Note>> {
+    /// Filter notes by tags (hierarchical, see [`TagFilter`])
+    pub fn filter_by_tags(&self, filter: &TagFilter) -> Result<Vec<Note>> {
         let all_notes = self.discover_notes()?;
 
         let matches: Vec<Note> = all_notes
             .into_iter()
-            .filter(|note| {
-                tags.iter()
-                    .all(|tag| note.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)))
-            })
+            .filter(|note| filter.matches(&note.tags))
             .collect();
 
         Ok(matches)
@@ -448,6 +486,8 @@ pub struct LinkGraph {
     pub outbound: HashMap<String, HashSet<String>>,
     /// Map from note title to set of titles that link to it (inbound)
     pub inbound: HashMap<String, HashSet<String>>,
+    /// Map from note title to IDs of tasks whose text links to it
+    pub task_links: HashMap<String, HashSet<String>>,
 }
 
 impl Default for LinkGraph {
@@ -461,11 +501,12 @@ impl LinkGraph {
         Self {
             outbound: HashMap::new(),
             inbound: HashMap::new(),
+            task_links: HashMap::new(),
         }
     }
 
-    /// Build a link graph from a collection of notes
-    pub fn build(notes: &[Note]) -> Self {
+    /// Build a link graph from a collection of notes, finding tasks with `vocabulary`
+    pub fn build(notes: &[Note], vocabulary: &TaskVocabulary) -> Self {
         let mut graph = Self::new();
 
         // Create title -> note mapping for resolving links
@@ -504,6 +545,15 @@ impl LinkGraph {
                         .insert(note_title.clone());
                 }
             }
+
+            // Links inside tasks are already counted above; also remember which task made them
+            for task in Task::extract_with_vocabulary(note, vocabulary) {
+                for link_text in task.links() {
+                    if title_map.contains_key(&link_text.to_lowercase()) {
+                        graph.task_links.entry(link_text).or_default().insert(task.id.clone());
+                    }
+                }
+            }
         }
 
         graph
@@ -616,7 +666,19 @@ fn extract_wiki_links_from_text(text: &str, links: &mut Vec<String>) {
 // HealthReport
 // ============================================================================
 
+/// A `.md` file that couldn't be loaded as a note
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NoteLoadError {
+    pub path: PathBuf,
+    /// 1-based line of the problem, when it has one
+    pub line: Option<usize>,
+    pub message: String,
+}
+
 /// Results of a health check operation
+///
+/// The same checks as the built-in [`crate::lint`] rules, grouped by rule.
+/// Problems with the content of a note come with the line they're on.
 #[derive(Debug, Clone)]
 pub struct HealthReport {
     /// Broken wiki links: note title -> list of broken link targets
@@ -629,6 +691,22 @@ pub struct HealthReport {
     pub duplicate_titles: HashMap<String, Vec<String>>,
     /// Orphaned notes (no links and no tags)
     pub orphaned_notes: Vec<String>,
+    /// Notes whose frontmatter doesn't match their configured note type
+    pub schema_violations: Vec<SchemaViolation>,
+    /// `.md` files that couldn't be read or parsed
+    pub unreadable_notes: Vec<Finding>,
+    /// Frontmatter that isn't valid YAML or has keys of the wrong shape
+    pub invalid_frontmatter: Vec<Finding>,
+    /// `created` and `updated` values that aren't dates
+    pub invalid_dates: Vec<Finding>,
+    /// Task-like lines that aren't tasks, e.g. `- [ ]!!! fix` or an unknown status
+    pub malformed_tasks: Vec<Finding>,
+    /// Notes with nothing but a title
+    pub empty_notes: Vec<Finding>,
+    /// Notes named almost like periodic notes, e.g. `2026-1-5.md`
+    pub misnamed_periodic_notes: Vec<Finding>,
+    /// Markdown files without the `.md` extension, which aren't loaded
+    pub misnamed_markdown_files: Vec<Finding>,
 }
 
 impl HealthReport {
@@ -639,6 +717,14 @@ impl HealthReport {
             || !self.notes_without_frontmatter.is_empty()
             || !self.duplicate_titles.is_empty()
             || !self.orphaned_notes.is_empty()
+            || !self.schema_violations.is_empty()
+            || !self.unreadable_notes.is_empty()
+            || !self.invalid_frontmatter.is_empty()
+            || !self.invalid_dates.is_empty()
+            || !self.malformed_tasks.is_empty()
+            || !self.empty_notes.is_empty()
+            || !self.misnamed_periodic_notes.is_empty()
+            || !self.misnamed_markdown_files.is_empty()
     }
 
     /// Count total number of issues
@@ -648,65 +734,14 @@ impl HealthReport {
             + self.notes_without_frontmatter.len()
             + self.duplicate_titles.len()
             + self.orphaned_notes.len()
-    }
-}
-
-/// Run health checks on a collection of notes
-pub(crate) fn check_health(notes: &[Note]) -> HealthReport {
-    let graph = LinkGraph::build(notes);
-
-    // Check for broken wiki links
-    let broken_links = graph.broken_links(notes);
-
-    // Check for notes without tags
-    let notes_without_tags: Vec<String> = notes
-        .iter()
-        .filter(|n| n.tags.is_empty())
-        .map(|n| n.title.clone())
-        .collect();
-
-    // Check for notes missing frontmatter
-    let notes_without_frontmatter: Vec<String> = notes
-        .iter()
-        .filter(|n| n.tags.is_empty() && n.created.is_none() && n.updated.is_none())
-        .map(|n| n.title.clone())
-        .collect();
-
-    // Check for multiple notes with the same title
-    let mut title_counts: HashMap<String, Vec<String>> = HashMap::new();
-    for note in notes {
-        title_counts
-            .entry(note.title.to_lowercase())
-            .or_default()
-            .push(note.path.display().to_string());
-    }
-
-    let duplicate_titles: HashMap<String, Vec<String>> = title_counts
-        .into_iter()
-        .filter(|(_, paths)| paths.len() > 1)
-        .collect();
-
-    // Check for orphaned notes (no links and no tags)
-    let all_titles: Vec<String> = notes.iter().map(|n| n.title.clone()).collect();
-    let orphaned = graph.orphaned_notes(&all_titles);
-
-    let orphaned_notes: Vec<String> = orphaned
-        .into_iter()
-        .filter(|title| {
-            notes
-                .iter()
-                .find(|n| &n.title == title)
-                .map(|n| n.tags.is_empty())
-                .unwrap_or(true)
-        })
-        .collect();
-
-    HealthReport {
-        broken_links,
-        notes_without_tags,
-        notes_without_frontmatter,
-        duplicate_titles,
-        orphaned_notes,
+            + self.schema_violations.len()
+            + self.unreadable_notes.len()
+            + self.invalid_frontmatter.len()
+            + self.invalid_dates.len()
+            + self.malformed_tasks.len()
+            + self.empty_notes.len()
+            + self.misnamed_periodic_notes.len()
+            + self.misnamed_markdown_files.len()
     }
 }
 
@@ -717,7 +752,19 @@ pub(crate) fn check_health(notes: &[Note]) -> HealthReport {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::storage::{MemoryStorage, Storage};
+    use crate::storage::RealStorage;
+
+    #[test]
+    fn test_link_graph_tracks_task_links() {
+        let notes = vec![
+            Note::parse(Path::new("todo.md"), "# Todo\n\n- [ ] Review [[Design Doc]] ^t-1a2b\n").unwrap(),
+            Note::parse(Path::new("design.md"), "# Design Doc\n").unwrap(),
+        ];
+        let graph = LinkGraph::build(&notes, &TaskVocabulary::default());
+
+        assert!(graph.inbound["Design Doc"].contains("Todo"));
+        assert_eq!(graph.task_links["Design Doc"], HashSet::from(["t-1a2b".to_string()]));
+    }
 
     #[test]
     fn test_extract_wiki_links() {
@@ -761,7 +808,7 @@ Links to [[Note One]] and [[Note Three]].
         let note3 = Note::parse(Path::new("note3.md"), "# Note Three\n\nNo links.").unwrap();
 
         let notes = vec![note1, note2, note3];
-        let graph = LinkGraph::build(&notes);
+        let graph = LinkGraph::build(&notes, &TaskVocabulary::default());
 
         // Note One links to Note Two
         assert!(graph
@@ -785,118 +832,20 @@ Links to [[Note One]] and [[Note Three]].
     }
 
     #[test]
-    fn test_health_check_no_issues() {
-        let storage = Box::new(MemoryStorage::new());
-        storage
-            .write(
-                Path::new("note1.md"),
-                r#"---
-tags: [test]
----
-
-# Note 1
-
-Content with [[Note 2]] link"#,
-            )
-            .unwrap();
-        storage
-            .write(
-                Path::new("note2.md"),
-                r#"---
-tags: [test]
----
-
-# Note 2
-
-Content"#,
-            )
-            .unwrap();
-
-        let notes = vec![
-            Note::parse(Path::new("note1.md"), &storage.read_to_string(Path::new("note1.md")).unwrap()).unwrap(),
-            Note::parse(Path::new("note2.md"), &storage.read_to_string(Path::new("note2.md")).unwrap()).unwrap(),
-        ];
-
-        let report = check_health(&notes);
-        assert!(!report.has_issues());
-        assert_eq!(report.issue_count(), 0);
-    }
-
-    #[test]
-    fn test_health_check_broken_links() {
-        let storage = Box::new(MemoryStorage::new());
-        storage
-            .write(
-                Path::new("note1.md"),
-                r#"---
-tags: [test]
----
+    fn test_load_notes_reports_unreadable_files() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("good.md"), "# Good\n").unwrap();
+        std::fs::write(dir.path().join("binary.md"), b"# Binary\n\nok\n\xff\xfe\n").unwrap();
 
-# Note 1
-
-Content with [[Missing Note]] link"#,
-            )
-            .unwrap();
-
-        let notes = vec![
-            Note::parse(Path::new("note1.md"), &storage.read_to_string(Path::new("note1.md")).unwrap()).unwrap(),
-        ];
-
-        let report = check_health(&notes);
-        assert!(report.has_issues());
-        assert_eq!(report.broken_links.len(), 1);
-        assert!(report.broken_links.contains_key("Note 1"));
-    }
-
-    #[test]
-    fn test_health_check_missing_frontmatter() {
-        let storage = Box::new(MemoryStorage::new());
-        storage
-            .write(Path::new("note1.md"), "# Note 1\n\nContent without frontmatter")
-            .unwrap();
-
-        let notes = vec![
-            Note::parse(Path::new("note1.md"), &storage.read_to_string(Path::new("note1.md")).unwrap()).unwrap(),
-        ];
-
-        let report = check_health(&notes);
-        assert!(report.has_issues());
-        assert_eq!(report.notes_without_frontmatter.len(), 1);
-        assert_eq!(report.notes_without_tags.len(), 1);
-    }
-
-    #[test]
-    fn test_health_check_duplicate_titles() {
-        let storage = Box::new(MemoryStorage::new());
-        storage
-            .write(
-                Path::new("note1.md"),
-                r#"---
-tags: [test]
----
-
-# Same Title"#,
-            )
-            .unwrap();
-        storage
-            .write(
-                Path::new("subfolder/note2.md"),
-                r#"---
-tags: [test]
----
-
-# Same Title"#,
-            )
-            .unwrap();
-
-        let notes = vec![
-            Note::parse(Path::new("note1.md"), &storage.read_to_string(Path::new("note1.md")).unwrap()).unwrap(),
-            Note::parse(Path::new("subfolder/note2.md"), &storage.read_to_string(Path::new("subfolder/note2.md")).unwrap()).unwrap(),
-        ];
+        let repo = Repository::new(Box::new(RealStorage::new(dir.path().to_path_buf())));
+        let (notes, errors) = repo.load_notes().unwrap();
 
-        let report = check_health(&notes);
-        assert!(report.has_issues());
-        assert_eq!(report.duplicate_titles.len(), 1);
+        assert_eq!(notes.len(), 1);
+        assert_eq!(notes[0].title, "Good");
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].path, PathBuf::from("binary.md"));
+        assert_eq!(errors[0].line, Some(4));
+        assert_eq!(errors[0].message, "Not valid UTF-8 text");
     }
 }
 
@@ -966,9 +915,9 @@ More content."#;
         let breadcrumbs = build_heading_breadcrumbs(markdown);
 
         // Should have entries for each heading
-        assert!(breadcrumbs.contains_key(&"# Main Heading".to_string()));
-        assert!(breadcrumbs.contains_key(&"## Section One".to_string()));
-        assert!(breadcrumbs.contains_key(&"### Subsection".to_string()));
+        assert!(breadcrumbs.contains_key("# Main Heading"));
+        assert!(breadcrumbs.contains_key("## Section One"));
+        assert!(breadcrumbs.contains_key("### Subsection"));
 
         // Subsection should have full path
         let subsection_path = &breadcrumbs["### Subsection"];
@@ -0,0 +1,296 @@
+//! Note-type schemas
+//!
+//! Note types are declared in `.bnotes/config.toml` and describe which
+//! frontmatter fields a kind of note (person, book, project, ...) should have:
+//!
+//! ```toml
+//! [types.person]
+//! required = ["email"]
+//!
+//! [types.person.fields]
+//! email = "string"
+//! company = "string"
+//! status = ["active", "inactive"]
+//! ```
+//!
+//! A note has a type when its `type:` property names it, or when it carries
+//! the type's tag (the type name unless `tag` is set).
+
+use crate::note::Note;
+use crate::properties::PropertyValue;
+use crate::tags::tag_matches;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Schema for one note type
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NoteType {
+    /// Tag that marks notes of this type (defaults to the type name)
+    #[serde(default)]
+    pub tag: Option<String>,
+    /// Properties every note of this type must have
+    #[serde(default)]
+    pub required: Vec<String>,
+    /// Expected type of each property
+    #[serde(default)]
+    pub fields: BTreeMap<String, FieldType>,
+}
+
+/// Expected type of a frontmatter field
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum FieldType {
+    /// A basic value type
+    Kind(FieldKind),
+    /// One of a fixed set of string values
+    Enum(Vec<String>),
+}
+
+/// Basic frontmatter value types
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FieldKind {
+    String,
+    Number,
+    Date,
+    Bool,
+    List,
+}
+
+impl fmt::Display for FieldKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            FieldKind::String => "string",
+            FieldKind::Number => "number",
+            FieldKind::Date => "date",
+            FieldKind::Bool => "bool",
+            FieldKind::List => "list",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FieldKind {
+    /// Describe the kind of a property value
+    fn of(value: &PropertyValue) -> Self {
+        match value {
+            PropertyValue::String(_) => FieldKind::String,
+            PropertyValue::Number(_) => FieldKind::Number,
+            PropertyValue::Date(_) => FieldKind::Date,
+            PropertyValue::Bool(_) => FieldKind::Bool,
+            PropertyValue::List(_) => FieldKind::List,
+        }
+    }
+
+    /// Check whether a value is acceptable for this kind
+    ///
+    /// Dates are accepted for string fields since a date-like string is still a string.
+    fn accepts(&self, value: &PropertyValue) -> bool {
+        matches!(
+            (self, value),
+            (FieldKind::String, PropertyValue::String(_) | PropertyValue::Date(_))
+                | (FieldKind::Number, PropertyValue::Number(_))
+                | (FieldKind::Date, PropertyValue::Date(_))
+                | (FieldKind::Bool, PropertyValue::Bool(_))
+                | (FieldKind::List, PropertyValue::List(_))
+        )
+    }
+}
+
+/// What is wrong with a note's frontmatter
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ViolationKind {
+    /// A required field is missing or empty
+    MissingField { field: String },
+    /// A field has the wrong value type
+    WrongType { field: String, expected: FieldKind, found: FieldKind },
+    /// A field's value is not one of the allowed values
+    UnknownValue { field: String, value: String, allowed: Vec<String> },
+}
+
+impl fmt::Display for ViolationKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ViolationKind::MissingField { field } => write!(f, "missing required field '{}'", field),
+            ViolationKind::WrongType { field, expected, found } => {
+                write!(f, "field '{}' should be {} but is {}", field, expected, found)
+            }
+            ViolationKind::UnknownValue { field, value, allowed } => write!(
+                f,
+                "field '{}' has unknown value '{}' (expected one of: {})",
+                field,
+                value,
+                allowed.join(", ")
+            ),
+        }
+    }
+}
+
+/// A schema violation found in a note
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaViolation {
+    pub note_title: String,
+    pub note_type: String,
+    pub kind: ViolationKind,
+}
+
+/// Determine which configured types apply to a note
+pub fn types_for_note<'a>(note: &Note, types: &'a BTreeMap<String, NoteType>) -> Vec<(&'a str, &'a NoteType)> {
+    let declared = note.property("type").map(|v| v.to_string().to_lowercase());
+
+    types
+        .iter()
+        .filter(|(name, note_type)| {
+            let tag = note_type.tag.as_deref().unwrap_or(name);
+            declared.as_deref() == Some(name.to_lowercase().as_str())
+                || note.tags.iter().any(|t| tag_matches(t, tag))
+        })
+        .map(|(name, note_type)| (name.as_str(), note_type))
+        .collect()
+}
+
+/// Validate a note against a single type schema
+fn validate_note(note: &Note, type_name: &str, note_type: &NoteType) -> Vec<SchemaViolation> {
+    let mut violations = Vec::new();
+    let violation = |kind| SchemaViolation {
+        note_title: note.title.clone(),
+        note_type: type_name.to_string(),
+        kind,
+    };
+
+    for field in &note_type.required {
+        let present = match note.property(field) {
+            None => false,
+            Some(PropertyValue::String(s)) => !s.trim().is_empty(),
+            Some(PropertyValue::List(items)) => !items.is_empty(),
+            Some(_) => true,
+        };
+
+        if !present {
+            violations.push(violation(ViolationKind::MissingField { field: field.clone() }));
+        }
+    }
+
+    for (field, expected) in &note_type.fields {
+        let Some(value) = note.property(field) else {
+            continue;
+        };
+
+        match expected {
+            FieldType::Kind(kind) => {
+                if !kind.accepts(&value) {
+                    violations.push(violation(ViolationKind::WrongType {
+                        field: field.clone(),
+                        expected: *kind,
+                        found: FieldKind::of(&value),
+                    }));
+                }
+            }
+            FieldType::Enum(allowed) => {
+                let values = match value {
+                    PropertyValue::List(items) => items,
+                    other => vec![other],
+                };
+
+                for value in values {
+                    let value = value.to_string();
+                    if !allowed.iter().any(|a| a.eq_ignore_ascii_case(&value)) {
+                        violations.push(violation(ViolationKind::UnknownValue {
+                            field: field.clone(),
+                            value,
+                            allowed: allowed.clone(),
+                        }));
+                    }
+                }
+            }
+        }
+    }
+
+    violations
+}
+
+/// Validate all notes against the configured note types
+pub fn validate_notes(notes: &[Note], types: &BTreeMap<String, NoteType>) -> Vec<SchemaViolation> {
+    if types.is_empty() {
+        return Vec::new();
+    }
+
+    notes
+        .iter()
+        .flat_map(|note| {
+            types_for_note(note, types)
+                .into_iter()
+                .flat_map(move |(name, note_type)| validate_note(note, name, note_type))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn person_types() -> BTreeMap<String, NoteType> {
+        toml::from_str::<BTreeMap<String, NoteType>>(
+            r#"
+[person]
+required = ["email"]
+
+[person.fields]
+email = "string"
+age = "number"
+status = ["active", "inactive"]
+"#,
+        )
+        .unwrap()
+    }
+
+    fn note(content: &str) -> Note {
+        Note::parse(Path::new("note.md"), content).unwrap()
+    }
+
+    #[test]
+    fn test_types_matched_by_tag_or_type_property() {
+        let types = person_types();
+
+        let tagged = note("---\ntags: [person]\n---\n# Ann");
+        assert_eq!(types_for_note(&tagged, &types).len(), 1);
+
+        let declared = note("---\ntype: Person\n---\n# Bob");
+        assert_eq!(types_for_note(&declared, &types).len(), 1);
+
+        let other = note("---\ntags: [book]\n---\n# Dune");
+        assert!(types_for_note(&other, &types).is_empty());
+    }
+
+    #[test]
+    fn test_validate_violations() {
+        let types = person_types();
+        let notes = vec![note(
+            "---\ntags: [person]\nage: forty\nstatus: retired\n---\n# Ann",
+        )];
+
+        let violations = validate_notes(&notes, &types);
+        assert_eq!(violations.len(), 3);
+        assert!(violations.iter().any(|v| v.kind == ViolationKind::MissingField { field: "email".to_string() }));
+        assert!(violations.iter().any(|v| matches!(
+            &v.kind,
+            ViolationKind::WrongType { field, expected: FieldKind::Number, found: FieldKind::String } if field == "age"
+        )));
+        assert!(violations.iter().any(|v| matches!(
+            &v.kind,
+            ViolationKind::UnknownValue { value, .. } if value == "retired"
+        )));
+    }
+
+    #[test]
+    fn test_validate_valid_note() {
+        let types = person_types();
+        let notes = vec![note(
+            "---\ntags: [person]\nemail: ann@example.com\nage: 40\nstatus: Active\n---\n# Ann",
+        )];
+
+        assert!(validate_notes(&notes, &types).is_empty());
+    }
+}
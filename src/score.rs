@@ -0,0 +1,181 @@
+//! Computed task scores
+//!
+//! A task's score adds up what makes it worth doing next: its urgency marker,
+//! its priority, how close it is to its `due:` date, how old its note is, and
+//! coefficients for its tags, minus a penalty while it's blocked. Each part
+//! is a factor between 0 and 1 weighted by [`TaskScoreConfig`], so the
+//! defaults can be tuned per notes collection.
+
+use crate::config::TaskScoreConfig;
+use crate::dependencies::DependencyGraph;
+use crate::note::{Note, Task};
+use crate::tags::tag_matches;
+use chrono::NaiveDate;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Scores tasks against a fixed date
+pub struct TaskScorer {
+    weights: TaskScoreConfig,
+    today: NaiveDate,
+    /// Date each note was started, for task age
+    note_dates: HashMap<PathBuf, NaiveDate>,
+    dependencies: DependencyGraph,
+}
+
+impl TaskScorer {
+    pub fn new(weights: TaskScoreConfig, notes: &[Note], dependencies: DependencyGraph, today: NaiveDate) -> Self {
+        let note_dates = notes
+            .iter()
+            .filter_map(|note| note_date(note).map(|date| (note.path.clone(), date)))
+            .collect();
+        Self { weights, today, note_dates, dependencies }
+    }
+
+    /// Fill in start dates for notes with neither a `created` date nor a
+    /// daily note name, for example from their first commit
+    pub fn with_fallback_dates<F>(mut self, notes: &[Note], mut started: F) -> Self
+    where
+        F: FnMut(&Note) -> Option<NaiveDate>,
+    {
+        for note in notes {
+            if !self.note_dates.contains_key(&note.path)
+                && let Some(date) = started(note)
+            {
+                self.note_dates.insert(note.path.clone(), date);
+            }
+        }
+        self
+    }
+
+    pub fn score(&self, task: &Task) -> f64 {
+        let w = &self.weights;
+        let mut score = w.urgency * urgency_factor(task.urgency.as_deref())
+            + w.priority * priority_factor(task.priority.as_deref())
+            + w.due * task.due.map(|due| due_factor(due, self.today)).unwrap_or(0.0);
+
+        if let Some(&started) = self.note_dates.get(&task.note_path) {
+            score += w.age * age_factor(started, self.today);
+        }
+        if self.dependencies.is_blocked(task) {
+            score += w.blocked;
+        }
+        for (tag, coefficient) in &w.tags {
+            if task.tags.iter().any(|t| tag_matches(t, tag)) {
+                score += coefficient;
+            }
+        }
+
+        score
+    }
+}
+
+/// When a note was started: its `created` date, or the date a daily note is for
+fn note_date(note: &Note) -> Option<NaiveDate> {
+    note.created.map(|created| created.date_naive()).or_else(|| {
+        let stem = note.path.file_stem()?.to_str()?;
+        NaiveDate::parse_from_str(stem, "%Y-%m-%d").ok()
+    })
+}
+
+fn urgency_factor(urgency: Option<&str>) -> f64 {
+    match urgency {
+        Some("!!!") => 1.0,
+        Some("!!") => 0.65,
+        Some("!") => 0.3,
+        _ => 0.0,
+    }
+}
+
+fn priority_factor(priority: Option<&str>) -> f64 {
+    match priority.map(|p| p.trim().to_uppercase()).as_deref() {
+        Some("A") => 1.0,
+        Some("B") => 0.65,
+        Some("C") => 0.3,
+        _ => 0.0,
+    }
+}
+
+/// 1.0 a week or more overdue, down to 0.2 two weeks or more ahead
+fn due_factor(due: NaiveDate, today: NaiveDate) -> f64 {
+    let overdue = (today - due).num_days() as f64;
+    if overdue >= 7.0 {
+        1.0
+    } else if overdue <= -14.0 {
+        0.2
+    } else {
+        (overdue + 14.0) * 0.8 / 21.0 + 0.2
+    }
+}
+
+/// Grows linearly to 1.0 over a year
+fn age_factor(started: NaiveDate, today: NaiveDate) -> f64 {
+    ((today - started).num_days() as f64 / 365.0).clamp(0.0, 1.0)
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::note;
+    use std::path::Path;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_due_factor() {
+        let today = date("2026-10-18");
+        assert_eq!(due_factor(date("2026-10-01"), today), 1.0);
+        assert_eq!(due_factor(date("2026-10-11"), today), 1.0);
+        assert_eq!(due_factor(date("2026-11-01"), today), 0.2);
+        assert_eq!(due_factor(date("2027-01-01"), today), 0.2);
+        let due_today = due_factor(today, today);
+        assert!(due_today > 0.7 && due_today < 0.8);
+    }
+
+    #[test]
+    fn test_score() {
+        let notes = vec![
+            Note::parse(
+                Path::new("2026-04-18.md"),
+                "# 2026-04-18\n\n- [ ] Old\n- [ ] !!! (A) Hot due:2026-10-18 @work/ops\n- [ ] Later @someday\n- [ ] Wait blocked-by:^t-x\n- [ ] X ^t-x\n",
+            )
+            .unwrap(),
+        ];
+        let tasks = Task::extract_from_note(&notes[0]);
+        let mut weights = TaskScoreConfig::default();
+        weights.tags.insert("work".to_string(), 1.0);
+        weights.tags.insert("someday".to_string(), -3.0);
+
+        let scorer = TaskScorer::new(weights, &notes, DependencyGraph::build(tasks.clone()), date("2026-10-18"));
+        let scores: Vec<f64> = tasks.iter().map(|t| scorer.score(t)).collect();
+
+        // Half a year old
+        assert!((scores[0] - 2.0 * 183.0 / 365.0).abs() < 1e-9);
+        // Urgency, priority, due today and the work tag on top of age
+        assert!((scores[1] - scores[0] - (6.0 + 4.0 + 12.0 * (14.0 * 0.8 / 21.0 + 0.2) + 1.0)).abs() < 1e-9);
+        assert!((scores[2] - scores[0] + 3.0).abs() < 1e-9);
+        assert!((scores[3] - scores[0] + 5.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_fallback_dates() {
+        let notes = vec![
+            Note::parse(Path::new("dated.md"), "---\ncreated: 2026-04-18T09:00:00Z\n---\n# Dated\n\n- [ ] A\n").unwrap(),
+            Note::parse(Path::new("undated.md"), "# Undated\n\n- [ ] B\n").unwrap(),
+        ];
+        let tasks = note::extract_tasks_from_notes(&notes, &Default::default());
+        let scorer = TaskScorer::new(TaskScoreConfig::default(), &notes, DependencyGraph::build(tasks.clone()), date("2026-10-18"));
+        assert_eq!(scorer.score(&tasks[1]), 0.0);
+
+        // Only notes without a date of their own use the fallback
+        let scorer = scorer.with_fallback_dates(&notes, |_| Some(date("2026-04-18")));
+        assert!(scorer.score(&tasks[1]) > 0.0);
+        assert_eq!(scorer.score(&tasks[0]), scorer.score(&tasks[1]));
+    }
+}
@@ -17,6 +17,11 @@ pub trait Storage {
     /// Read a file to a string
     fn read_to_string(&self, path: &Path) -> Result<String>;
 
+    /// Read a file's raw bytes, which need not be valid UTF-8
+    fn read(&self, path: &Path) -> Result<Vec<u8>> {
+        self.read_to_string(path).map(String::into_bytes)
+    }
+
     /// Write contents to a file
     fn write(&self, path: &Path, contents: &str) -> Result<()>;
 
@@ -59,6 +64,10 @@ impl Storage for RealStorage {
             .with_context(|| format!("Failed to read {}", path.display()))
     }
 
+    fn read(&self, path: &Path) -> Result<Vec<u8>> {
+        std::fs::read(self.full_path(path)).with_context(|| format!("Failed to read {}", path.display()))
+    }
+
     fn write(&self, path: &Path, contents: &str) -> Result<()> {
         std::fs::write(self.full_path(path), contents)
             .with_context(|| format!("Failed to write {}", path.display()))
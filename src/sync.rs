@@ -0,0 +1,711 @@
+//! Syncing a notes repository with its remote
+//!
+//! The [`Sync`] trait describes what bnotes needs from version control:
+//! committing local changes, pulling and pushing. [`GitSync`] implements it
+//! with an embedded git library, so no git binary is needed and failures come
+//! back as [`SyncError`] values instead of scraped stderr.
+//!
+//! Notes that conflict during a pull go through [`merge_notes`] first, so the
+//! same edits the merge driver reconciles never stop a sync.
+
+use crate::changes::{ChangeSummary, NoteChange};
+use crate::config::LibraryConfig;
+use crate::merge::merge_notes;
+use crate::note::TaskVocabulary;
+use crate::storage::RealStorage;
+use chrono::Utc;
+use git2::build::CheckoutBuilder;
+use git2::{
+    AutotagOption, Cred, CredentialType, ErrorCode, FetchOptions, IndexAddOption, IndexEntry, IndexTime, Oid,
+    PushOptions, RemoteCallbacks, Repository, Signature, StashFlags, StatusOptions,
+};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Error from a sync operation
+#[derive(Debug)]
+pub enum SyncError {
+    /// The notes directory isn't a git repository
+    NotARepository(PathBuf),
+    /// No remote is configured to sync with
+    NoRemote,
+    /// HEAD doesn't point at a branch
+    DetachedHead,
+    /// No author name and email are configured for commits
+    MissingIdentity,
+    /// Another process holds the sync lock
+    Locked,
+    /// Pulling left files that couldn't be merged; nothing was changed
+    Conflicts(Vec<PathBuf>),
+    /// Pulled, but uncommitted changes couldn't be reapplied and are kept in the stash
+    StashConflict,
+    /// The remote refused the push
+    PushRejected(String),
+    /// Any other failure reported by git
+    Git(git2::Error),
+    Io(std::io::Error),
+}
+
+impl fmt::Display for SyncError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SyncError::NotARepository(path) => write!(
+                f,
+                "{} is not a git repository\n\
+                Run 'git init' in your notes directory to get started.",
+                path.display()
+            ),
+            SyncError::NoRemote => write!(
+                f,
+                "No remote repository configured\n\
+                Run 'git remote add origin <url>' to configure a remote."
+            ),
+            SyncError::DetachedHead => write!(f, "HEAD is detached\nCheck out a branch before syncing."),
+            SyncError::MissingIdentity => write!(
+                f,
+                "No author identity configured\n\
+                Set one with 'git config user.name' and 'git config user.email'."
+            ),
+            SyncError::Locked => write!(
+                f,
+                "Another bnotes process is syncing this repository\n\
+                Try again in a moment."
+            ),
+            SyncError::Conflicts(paths) => {
+                writeln!(f, "Merge conflicts detected\n\nThe following files have conflicts:")?;
+                for path in paths {
+                    writeln!(f, "  - {}", path.display())?;
+                }
+                write!(f, "\nNothing was merged. Reconcile these notes with the remote and sync again.")
+            }
+            SyncError::StashConflict => write!(
+                f,
+                "Pulled, but your uncommitted changes conflict with the pulled ones\n\
+                They are preserved in the stash; run 'git stash pop' to resolve them."
+            ),
+            SyncError::PushRejected(reason) => write!(f, "The remote rejected the push: {}", reason),
+            SyncError::Git(e) => write!(f, "{}", e.message()),
+            SyncError::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for SyncError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SyncError::Git(e) => Some(e),
+            SyncError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<git2::Error> for SyncError {
+    fn from(e: git2::Error) -> Self {
+        SyncError::Git(e)
+    }
+}
+
+impl From<std::io::Error> for SyncError {
+    fn from(e: std::io::Error) -> Self {
+        SyncError::Io(e)
+    }
+}
+
+pub type SyncResult<T> = std::result::Result<T, SyncError>;
+
+/// What a pull did to the local branch
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PullOutcome {
+    UpToDate,
+    FastForward,
+    Merged,
+}
+
+/// Version control backend for a notes directory
+pub trait Sync {
+    /// Check if a remote is configured
+    fn has_remote(&self) -> SyncResult<bool>;
+
+    /// Uncommitted changes, with file content before and after
+    fn pending_changes(&self) -> SyncResult<Vec<NoteChange>>;
+
+    /// Commit every change in the working tree; returns false if there was nothing to commit
+    fn commit_all(&self, message: &str) -> SyncResult<bool>;
+
+    /// Bring in remote changes, setting uncommitted changes aside while merging
+    fn pull(&self) -> SyncResult<PullOutcome>;
+
+    /// Send local commits to the remote
+    fn push(&self) -> SyncResult<()>;
+
+    /// The task statuses configured for the notes
+    fn task_vocabulary(&self) -> TaskVocabulary;
+
+    /// Summarize uncommitted changes in terms of notes, tasks and tags
+    fn change_summary(&self) -> SyncResult<ChangeSummary> {
+        Ok(ChangeSummary::from_changes(&self.pending_changes()?, &self.task_vocabulary()))
+    }
+
+    /// Commit pending changes with a summary under `subject`, then pull and push
+    ///
+    /// Returns the number of files committed.
+    fn sync(&self, subject: &str) -> SyncResult<usize> {
+        let summary = self.change_summary()?;
+        let committed = if self.commit_all(&summary.commit_message(subject))? {
+            summary.file_count()
+        } else {
+            0
+        };
+
+        self.pull()?;
+        self.push()?;
+        Ok(committed)
+    }
+}
+
+// ============================================================================
+// Locking
+// ============================================================================
+
+/// Locks older than this are assumed to be left over from a crashed process
+const STALE_LOCK_AGE: Duration = Duration::from_secs(10 * 60);
+
+/// Exclusive lock that keeps concurrent bnotes processes from syncing at once
+///
+/// The lock file lives in the git directory and is removed when this is dropped.
+pub struct SyncLock {
+    path: PathBuf,
+}
+
+impl Drop for SyncLock {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+// ============================================================================
+// Git
+// ============================================================================
+
+/// [`Sync`] implementation for a git repository
+pub struct GitSync {
+    root: PathBuf,
+    git_dir: PathBuf,
+}
+
+impl GitSync {
+    /// Open the git repository at the root of a notes directory
+    pub fn open(root: &Path) -> SyncResult<Self> {
+        let repo = Repository::open(root).map_err(|e| match e.code() {
+            ErrorCode::NotFound => SyncError::NotARepository(root.to_path_buf()),
+            _ => SyncError::Git(e),
+        })?;
+
+        Ok(Self {
+            root: root.to_path_buf(),
+            git_dir: repo.path().to_path_buf(),
+        })
+    }
+
+    fn repo(&self) -> SyncResult<Repository> {
+        Ok(Repository::open(&self.root)?)
+    }
+
+    /// Take the sync lock, or return None if another process holds it
+    pub fn try_lock(&self) -> SyncResult<Option<SyncLock>> {
+        let path = self.git_dir.join("bnotes.lock");
+
+        // Clear out locks left behind by crashed processes
+        if let Ok(modified) = std::fs::metadata(&path).and_then(|m| m.modified())
+            && SystemTime::now().duration_since(modified).unwrap_or_default() > STALE_LOCK_AGE
+        {
+            let _ = std::fs::remove_file(&path);
+        }
+
+        match std::fs::OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(_) => Ok(Some(SyncLock { path })),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Take the sync lock, failing if another process holds it
+    pub fn lock(&self) -> SyncResult<SyncLock> {
+        self.try_lock()?.ok_or(SyncError::Locked)
+    }
+
+    /// When this repository was last pulled and pushed by bnotes
+    pub fn last_sync_time(&self) -> Option<SystemTime> {
+        std::fs::metadata(self.git_dir.join("bnotes-last-sync"))
+            .and_then(|m| m.modified())
+            .ok()
+    }
+
+    /// Remember that the repository was just pulled and pushed
+    pub fn record_sync(&self) -> SyncResult<()> {
+        let timestamp = Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string();
+        std::fs::write(self.git_dir.join("bnotes-last-sync"), timestamp)?;
+        match std::fs::remove_file(self.git_dir.join("bnotes-sync-pending")) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Remember that commits were made that still need to be pulled and pushed
+    pub fn mark_sync_pending(&self) -> SyncResult<()> {
+        std::fs::write(self.git_dir.join("bnotes-sync-pending"), "")?;
+        Ok(())
+    }
+
+    /// Whether a sync was put off since the last one
+    pub fn is_sync_pending(&self) -> bool {
+        self.git_dir.join("bnotes-sync-pending").exists()
+    }
+
+    /// Download the upstream branch without touching the working tree
+    ///
+    /// The network half of [`Sync::pull`]; [`GitSync::merge_fetched`] does the rest.
+    pub fn fetch(&self) -> SyncResult<()> {
+        let repo = self.repo()?;
+        let branch = current_branch(&repo)?;
+        let (remote_name, remote_branch) = upstream(&repo, &branch)?;
+
+        let mut fetch_options = FetchOptions::new();
+        fetch_options
+            .remote_callbacks(remote_callbacks(&repo)?)
+            .download_tags(AutotagOption::None);
+        repo.find_remote(&remote_name)?.fetch(
+            &[format!("+refs/heads/{}:{}", remote_branch, tracking_ref(&remote_name, &remote_branch))],
+            Some(&mut fetch_options),
+            None,
+        )?;
+
+        Ok(())
+    }
+
+    /// Merge the last fetched upstream branch into the current one
+    pub fn merge_fetched(&self) -> SyncResult<PullOutcome> {
+        let mut repo = self.repo()?;
+        let branch = current_branch(&repo)?;
+        let (remote_name, remote_branch) = upstream(&repo, &branch)?;
+        let tracking_ref = tracking_ref(&remote_name, &remote_branch);
+
+        // Nothing on the remote yet
+        let their_oid = match repo.find_reference(&tracking_ref) {
+            Ok(their_ref) => their_ref.peel_to_commit()?.id(),
+            Err(_) => return Ok(PullOutcome::UpToDate),
+        };
+
+        // Set uncommitted changes aside so the merge can check out files freely
+        let stashed = if self.has_changes()? {
+            let signature = signature(&repo)?;
+            let message = format!("bnotes pull auto-stash {}", Utc::now().format("%Y-%m-%dT%H:%M:%SZ"));
+            repo.stash_save2(&signature, Some(&message), Some(StashFlags::INCLUDE_UNTRACKED))?;
+            true
+        } else {
+            false
+        };
+
+        let outcome = merge_into_head(&repo, &branch, their_oid, &tracking_ref, &self.task_vocabulary());
+
+        if stashed && repo.stash_pop(0, None).is_err() {
+            // A failed pop leaves the stash in place
+            return outcome.and(Err(SyncError::StashConflict));
+        }
+
+        outcome
+    }
+
+    /// Check if there is anything to commit
+    pub fn has_changes(&self) -> SyncResult<bool> {
+        let repo = self.repo()?;
+        Ok(!repo.statuses(Some(&mut status_options()))?.is_empty())
+    }
+}
+
+/// Status options covering every untracked file but not ignored ones
+fn status_options() -> StatusOptions {
+    let mut options = StatusOptions::new();
+    options
+        .include_untracked(true)
+        .recurse_untracked_dirs(true)
+        .include_ignored(false)
+        .renames_head_to_index(true);
+    options
+}
+
+/// The branch HEAD points at (which may not have any commits yet)
+fn current_branch(repo: &Repository) -> SyncResult<String> {
+    let head = repo.find_reference("HEAD")?;
+    head.symbolic_target()
+        .and_then(|target| target.strip_prefix("refs/heads/"))
+        .map(str::to_string)
+        .ok_or(SyncError::DetachedHead)
+}
+
+/// The remote and remote branch a local branch syncs with
+///
+/// Uses the branch's upstream if set, otherwise a branch of the same name on
+/// `origin` (or the only remote).
+fn upstream(repo: &Repository, branch: &str) -> SyncResult<(String, String)> {
+    let config = repo.config()?;
+
+    let remote = match config.get_string(&format!("branch.{}.remote", branch)) {
+        Ok(remote) => remote,
+        Err(_) => {
+            let remotes = repo.remotes()?;
+            let names: Vec<&str> = remotes.iter().flatten().collect();
+            match names.as_slice() {
+                [] => return Err(SyncError::NoRemote),
+                [only] => only.to_string(),
+                _ if names.contains(&"origin") => "origin".to_string(),
+                _ => return Err(SyncError::NoRemote),
+            }
+        }
+    };
+
+    let remote_branch = config
+        .get_string(&format!("branch.{}.merge", branch))
+        .ok()
+        .and_then(|merge| merge.strip_prefix("refs/heads/").map(str::to_string))
+        .unwrap_or_else(|| branch.to_string());
+
+    Ok((remote, remote_branch))
+}
+
+/// Remote-tracking ref an upstream branch is fetched into
+fn tracking_ref(remote: &str, branch: &str) -> String {
+    format!("refs/remotes/{}/{}", remote, branch)
+}
+
+/// Commit author, honoring the same environment variables as the git binary
+fn signature(repo: &Repository) -> SyncResult<Signature<'static>> {
+    if let (Ok(name), Ok(email)) = (std::env::var("GIT_AUTHOR_NAME"), std::env::var("GIT_AUTHOR_EMAIL")) {
+        return Ok(Signature::now(&name, &email)?);
+    }
+
+    repo.signature()
+        .map(|sig| sig.to_owned())
+        .map_err(|_| SyncError::MissingIdentity)
+}
+
+/// Callbacks that authenticate with the ssh agent or configured credential helpers
+fn remote_callbacks(repo: &Repository) -> SyncResult<RemoteCallbacks<'static>> {
+    let config = repo.config()?;
+    let ssh_keys = default_ssh_keys();
+    let mut attempts = 0;
+
+    let mut callbacks = RemoteCallbacks::new();
+    callbacks.credentials(move |url, username, allowed| {
+        // libgit2 keeps asking as long as credentials are offered, so give up
+        // once the agent and every key file have been tried
+        attempts += 1;
+        if attempts > 3.max(1 + ssh_keys.len()) {
+            return Err(git2::Error::from_str("authentication failed"));
+        }
+
+        if allowed.contains(CredentialType::SSH_KEY) {
+            let username = username.unwrap_or("git");
+            match attempts {
+                1 => Cred::ssh_key_from_agent(username),
+                n => match ssh_keys.get(n - 2) {
+                    Some(key) => Cred::ssh_key(username, None, key, None),
+                    None => Err(git2::Error::from_str("authentication failed")),
+                },
+            }
+        } else if allowed.contains(CredentialType::USER_PASS_PLAINTEXT) {
+            Cred::credential_helper(&config, url, username)
+        } else {
+            Cred::default()
+        }
+    });
+
+    Ok(callbacks)
+}
+
+/// Private keys ssh would try when there's no agent, that exist
+fn default_ssh_keys() -> Vec<PathBuf> {
+    let Some(home) = std::env::var_os("HOME") else {
+        return Vec::new();
+    };
+    let ssh_dir = Path::new(&home).join(".ssh");
+    ["id_ed25519", "id_ecdsa", "id_rsa"]
+        .iter()
+        .map(|name| ssh_dir.join(name))
+        .filter(|path| path.is_file())
+        .collect()
+}
+
+/// Read a file's content from a commit, or None if it isn't there
+fn read_blob(repo: &Repository, tree: Option<&git2::Tree>, path: &Path) -> SyncResult<Option<String>> {
+    let Some(tree) = tree else {
+        return Ok(None);
+    };
+    match tree.get_path(path) {
+        Ok(entry) => {
+            let blob = entry.to_object(repo)?.peel_to_blob()?;
+            Ok(Some(String::from_utf8_lossy(blob.content()).into_owned()))
+        }
+        Err(e) if e.code() == ErrorCode::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+impl Sync for GitSync {
+    fn has_remote(&self) -> SyncResult<bool> {
+        Ok(!self.repo()?.remotes()?.is_empty())
+    }
+
+    fn task_vocabulary(&self) -> TaskVocabulary {
+        LibraryConfig::load_or_default(&RealStorage::new(self.root.clone())).task_vocabulary()
+    }
+
+    fn pending_changes(&self) -> SyncResult<Vec<NoteChange>> {
+        let repo = self.repo()?;
+        let head_tree = match repo.head() {
+            Ok(head) => Some(head.peel_to_tree()?),
+            Err(e) if e.code() == ErrorCode::UnbornBranch => None,
+            Err(e) => return Err(e.into()),
+        };
+
+        let statuses = repo.statuses(Some(&mut status_options()))?;
+        let mut changes = Vec::new();
+
+        for entry in statuses.iter() {
+            let Some(path) = entry.path().map(PathBuf::from) else {
+                continue;
+            };
+            let status = entry.status();
+
+            // Staged renames report both paths; everything else is a single path
+            let old_path = match entry.head_to_index() {
+                Some(delta) if status.is_index_renamed() => delta.old_file().path().map(Path::to_path_buf),
+                _ => Some(path.clone()),
+            };
+            let new_path = match entry.head_to_index() {
+                Some(delta) if status.is_index_renamed() => delta.new_file().path().map(Path::to_path_buf),
+                _ => Some(path),
+            };
+
+            let old_content = match &old_path {
+                Some(old) => read_blob(&repo, head_tree.as_ref(), old)?,
+                None => None,
+            };
+            let new_content = new_path
+                .as_ref()
+                .and_then(|new| std::fs::read_to_string(self.root.join(new)).ok());
+
+            if old_content.is_none() && new_content.is_none() {
+                continue;
+            }
+
+            changes.push(NoteChange {
+                old_path: old_path.filter(|_| old_content.is_some()),
+                new_path: new_path.filter(|_| new_content.is_some()),
+                old_content,
+                new_content,
+            });
+        }
+
+        Ok(changes)
+    }
+
+    fn commit_all(&self, message: &str) -> SyncResult<bool> {
+        let repo = self.repo()?;
+
+        let mut index = repo.index()?;
+        index.add_all(["*"], IndexAddOption::DEFAULT, None)?;
+        index.update_all(["*"], None)?;
+        index.write()?;
+        let tree_id = index.write_tree()?;
+
+        let parent = match repo.head() {
+            Ok(head) => Some(head.peel_to_commit()?),
+            Err(e) if e.code() == ErrorCode::UnbornBranch => None,
+            Err(e) => return Err(e.into()),
+        };
+        if parent.as_ref().is_some_and(|p| p.tree_id() == tree_id) {
+            return Ok(false);
+        }
+
+        let signature = signature(&repo)?;
+        let tree = repo.find_tree(tree_id)?;
+        let parents: Vec<&git2::Commit> = parent.iter().collect();
+        repo.commit(Some("HEAD"), &signature, &signature, message, &tree, &parents)?;
+
+        Ok(true)
+    }
+
+    fn pull(&self) -> SyncResult<PullOutcome> {
+        self.fetch()?;
+        self.merge_fetched()
+    }
+
+    fn push(&self) -> SyncResult<()> {
+        let repo = self.repo()?;
+        let branch = current_branch(&repo)?;
+        let (remote_name, remote_branch) = upstream(&repo, &branch)?;
+
+        // Nothing committed yet, so nothing to push
+        let Ok(head) = repo.head() else {
+            return Ok(());
+        };
+        let head_oid = head.peel_to_commit()?.id();
+
+        let mut rejection = None;
+        {
+            let mut callbacks = remote_callbacks(&repo)?;
+            callbacks.push_update_reference(|_, status| {
+                if let Some(status) = status {
+                    rejection = Some(status.to_string());
+                }
+                Ok(())
+            });
+
+            let mut push_options = PushOptions::new();
+            push_options.remote_callbacks(callbacks);
+            repo.find_remote(&remote_name)?.push(
+                &[format!("refs/heads/{}:refs/heads/{}", branch, remote_branch)],
+                Some(&mut push_options),
+            )?;
+        }
+
+        if let Some(reason) = rejection {
+            return Err(SyncError::PushRejected(reason));
+        }
+
+        // Keep the remote-tracking branch in step, as the git binary does
+        repo.reference(
+            &tracking_ref(&remote_name, &remote_branch),
+            head_oid,
+            true,
+            "bnotes: push",
+        )?;
+
+        Ok(())
+    }
+}
+
+/// Fast-forward or merge a fetched commit into the current branch
+fn merge_into_head(
+    repo: &Repository,
+    branch: &str,
+    their_oid: Oid,
+    their_name: &str,
+    vocabulary: &TaskVocabulary,
+) -> SyncResult<PullOutcome> {
+    let their_commit = repo.find_commit(their_oid)?;
+    let annotated = repo.find_annotated_commit(their_oid)?;
+    let (analysis, _) = repo.merge_analysis(&[&annotated])?;
+
+    let branch_ref = format!("refs/heads/{}", branch);
+    let mut checkout = CheckoutBuilder::new();
+    checkout.force();
+
+    if analysis.is_up_to_date() {
+        return Ok(PullOutcome::UpToDate);
+    }
+
+    if analysis.is_fast_forward() || analysis.is_unborn() {
+        repo.reference(&branch_ref, their_oid, true, "bnotes: fast-forward")?;
+        repo.set_head(&branch_ref)?;
+        repo.checkout_head(Some(&mut checkout))?;
+        return Ok(PullOutcome::FastForward);
+    }
+
+    let our_commit = repo.head()?.peel_to_commit()?;
+    let mut index = repo.merge_commits(&our_commit, &their_commit, None)?;
+
+    if index.has_conflicts() {
+        let unresolved = resolve_note_conflicts(repo, &mut index, vocabulary)?;
+        if !unresolved.is_empty() {
+            return Err(SyncError::Conflicts(unresolved));
+        }
+    }
+
+    let tree = repo.find_tree(index.write_tree_to(repo)?)?;
+    let signature = signature(repo)?;
+    let their_branch = their_name.strip_prefix("refs/remotes/").unwrap_or(their_name);
+    let message = format!("Merge remote-tracking branch '{}'", their_branch);
+    repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        &message,
+        &tree,
+        &[&our_commit, &their_commit],
+    )?;
+    repo.checkout_head(Some(&mut checkout))?;
+
+    Ok(PullOutcome::Merged)
+}
+
+/// Merge conflicting notes structurally; returns the paths that still conflict
+fn resolve_note_conflicts(
+    repo: &Repository,
+    index: &mut git2::Index,
+    vocabulary: &TaskVocabulary,
+) -> SyncResult<Vec<PathBuf>> {
+    let read = |entry: &IndexEntry| -> SyncResult<String> {
+        let blob = repo.find_blob(entry.id)?;
+        Ok(String::from_utf8_lossy(blob.content()).into_owned())
+    };
+
+    let mut resolved: Vec<(PathBuf, u32, String)> = Vec::new();
+    let mut unresolved = Vec::new();
+
+    for conflict in index.conflicts()? {
+        let conflict = conflict?;
+        let path = conflict
+            .our
+            .as_ref()
+            .or(conflict.their.as_ref())
+            .or(conflict.ancestor.as_ref())
+            .map(|entry| PathBuf::from(String::from_utf8_lossy(&entry.path).into_owned()))
+            .unwrap_or_default();
+
+        // Only notes changed on both sides can be merged structurally
+        let (Some(base), Some(ours), Some(theirs)) = (&conflict.ancestor, &conflict.our, &conflict.their) else {
+            unresolved.push(path);
+            continue;
+        };
+        if path.extension().is_none_or(|e| e != "md") {
+            unresolved.push(path);
+            continue;
+        }
+
+        let result = merge_notes(&read(base)?, &read(ours)?, &read(theirs)?, vocabulary);
+        if result.has_conflicts {
+            unresolved.push(path);
+        } else {
+            resolved.push((path, ours.mode, result.content));
+        }
+    }
+
+    if unresolved.is_empty() {
+        for (path, mode, content) in resolved {
+            index.conflict_remove(&path)?;
+
+            let entry = IndexEntry {
+                ctime: IndexTime::new(0, 0),
+                mtime: IndexTime::new(0, 0),
+                dev: 0,
+                ino: 0,
+                mode,
+                uid: 0,
+                gid: 0,
+                file_size: content.len() as u32,
+                id: repo.blob(content.as_bytes())?,
+                flags: 0,
+                flags_extended: 0,
+                path: path.to_string_lossy().into_owned().into_bytes(),
+            };
+            index.add(&entry)?;
+        }
+    }
+
+    unresolved.sort();
+    Ok(unresolved)
+}
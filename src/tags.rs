@@ -0,0 +1,453 @@
+//! Hierarchical tags
+//!
+//! Note tags (from frontmatter) and task tags (`@tag`) share one model: a tag
+//! is a `/`-separated path compared case-insensitively, so a filter for `work`
+//! matches `work`, `work/meetings` and `work/clientA/ops`, but not `workshop`.
+
+use crate::note::{Task, TaskVocabulary};
+use anyhow::Result;
+use pulldown_cmark::{Event, Options, Parser, Tag, TagEnd};
+use std::collections::{BTreeMap, HashSet};
+use std::ops::Range;
+
+/// Normalize a tag for comparison
+///
+/// Lowercases, trims whitespace, strips a leading `@` or `#` and any
+/// trailing `/` so `@Work/` and `work` compare equal.
+pub fn normalize(tag: &str) -> String {
+    let tag = tag.trim();
+    let tag = tag
+        .strip_prefix('@')
+        .or_else(|| tag.strip_prefix('#'))
+        .unwrap_or(tag);
+    tag.trim_end_matches('/').to_lowercase()
+}
+
+/// Check whether `tag` equals `filter` or is nested underneath it
+pub fn tag_matches(tag: &str, filter: &str) -> bool {
+    let tag = normalize(tag);
+    let filter = normalize(filter);
+
+    tag == filter
+        || (tag.len() > filter.len()
+            && tag.starts_with(&filter)
+            && tag.as_bytes()[filter.len()] == b'/')
+}
+
+/// Rename `tag` if it equals `old` or is nested underneath it
+///
+/// The nested part keeps its original spelling, so renaming `work` to `job`
+/// turns `work/ClientA` into `job/ClientA`. Returns None if the tag doesn't match.
+pub fn rename(tag: &str, old: &str, new: &str) -> Option<String> {
+    if !tag_matches(tag, old) {
+        return None;
+    }
+
+    let stripped = tag.trim();
+    let stripped = stripped
+        .strip_prefix('@')
+        .or_else(|| stripped.strip_prefix('#'))
+        .unwrap_or(stripped)
+        .trim_end_matches('/');
+    let new = new.trim().trim_start_matches(['@', '#']).trim_end_matches('/');
+
+    // Match by `/` components: lowercasing can change a component's byte length
+    let depth = normalize(old).split('/').count();
+    match stripped.splitn(depth + 1, '/').nth(depth) {
+        Some(suffix) => Some(format!("{}/{}", new, suffix)),
+        None => Some(new.to_string()),
+    }
+}
+
+/// Rename `@tag` markers in tasks
+///
+/// Tasks are found the same way task extraction finds them, so `* [ ]`, `+ [ ]`
+/// and `1. [ ]` items count too, while code spans and code blocks are left alone.
+pub fn rename_task_tags(content: &str, old: &str, new: &str, vocabulary: &TaskVocabulary) -> String {
+    // Own text of each task item (without nested lists), and code anywhere
+    let mut tasks: Vec<Range<usize>> = Vec::new();
+    let mut code: Vec<Range<usize>> = Vec::new();
+    let mut open_items: Vec<Option<usize>> = Vec::new();
+
+    for (event, range) in Parser::new_ext(content, Options::empty()).into_offset_iter() {
+        match event {
+            Event::Start(Tag::Item) => {
+                let task = Task::split_checkbox(&content[range.clone()], vocabulary).map(|_| {
+                    tasks.push(range);
+                    tasks.len() - 1
+                });
+                open_items.push(task);
+            }
+            Event::Start(Tag::List(_)) => {
+                if let Some(&Some(task)) = open_items.last() {
+                    tasks[task].end = tasks[task].end.min(range.start);
+                }
+            }
+            Event::End(TagEnd::Item) => {
+                open_items.pop();
+            }
+            Event::Start(Tag::CodeBlock(_)) | Event::Code(_) => code.push(range),
+            _ => {}
+        }
+    }
+
+    let mut out = String::with_capacity(content.len());
+    let mut pos = 0;
+    for task in tasks {
+        let mut start = task.start;
+        for span in code.iter().filter(|span| span.start >= task.start && span.end <= task.end) {
+            out.push_str(&content[pos..start]);
+            out.push_str(&rename_words(&content[start..span.start], old, new));
+            pos = span.start;
+            start = span.end;
+        }
+        out.push_str(&content[pos..start]);
+        out.push_str(&rename_words(&content[start..task.end], old, new));
+        pos = task.end;
+    }
+    out.push_str(&content[pos..]);
+    out
+}
+
+/// Rename `@tag` words in text, keeping the original whitespace
+fn rename_words(text: &str, old: &str, new: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while !rest.is_empty() {
+        let word_end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        let (word, after) = rest.split_at(word_end);
+        match word.strip_prefix('@').and_then(|t| rename(t, old, new)) {
+            Some(renamed) => {
+                out.push('@');
+                out.push_str(&renamed);
+            }
+            None => out.push_str(word),
+        }
+
+        let space_end = after.find(|c: char| !c.is_whitespace()).unwrap_or(after.len());
+        out.push_str(&after[..space_end]);
+        rest = &after[space_end..];
+    }
+    out
+}
+
+/// One term of a tag filter: matches if any alternative matches
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct TagTerm {
+    alternatives: Vec<String>,
+    negated: bool,
+}
+
+impl TagTerm {
+    fn matches(&self, tags: &[String]) -> bool {
+        let found = self
+            .alternatives
+            .iter()
+            .any(|alt| tags.iter().any(|tag| tag_matches(tag, alt)));
+
+        found != self.negated
+    }
+}
+
+/// Tag filter built from one or more `--tag` arguments
+///
+/// Each argument is a term and all terms must match (AND). Within a term,
+/// alternatives separated by `,` or `|` are ORed together, and a leading `!`
+/// negates the whole term:
+///
+/// - `work` - tagged `work` or anything under `work/`
+/// - `work,personal` - tagged with either
+/// - `!archive` - not tagged `archive` or anything under it
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TagFilter {
+    terms: Vec<TagTerm>,
+}
+
+impl TagFilter {
+    /// Parse a filter from command-line style arguments
+    pub fn parse(args: &[String]) -> Result<Self> {
+        let mut terms = Vec::new();
+
+        for arg in args {
+            let arg = arg.trim();
+            let (negated, rest) = match arg.strip_prefix('!') {
+                Some(rest) => (true, rest),
+                None => (false, arg),
+            };
+
+            let alternatives: Vec<String> = rest
+                .split([',', '|'])
+                .map(normalize)
+                .collect();
+
+            if alternatives.iter().any(|alt| alt.is_empty()) {
+                anyhow::bail!("Invalid tag filter: '{}'", arg);
+            }
+
+            if alternatives.iter().any(|alt| alt.starts_with('!')) {
+                anyhow::bail!(
+                    "Invalid tag filter: '{}'. Negation applies to the whole group, use '!a,b' instead.",
+                    arg
+                );
+            }
+
+            terms.push(TagTerm { alternatives, negated });
+        }
+
+        Ok(Self { terms })
+    }
+
+    /// Check if the filter has no terms (matches everything)
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    /// Check whether a set of tags satisfies every term of the filter
+    pub fn matches(&self, tags: &[String]) -> bool {
+        self.terms.iter().all(|term| term.matches(tags))
+    }
+}
+
+// ============================================================================
+// TagTree
+// ============================================================================
+
+/// A node in the tag hierarchy
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TagNode {
+    /// Full normalized tag path (e.g. "work/meetings")
+    pub path: String,
+    /// Notes tagged with exactly this tag
+    pub notes: usize,
+    /// Tasks tagged with exactly this tag
+    pub tasks: usize,
+    /// Notes tagged with this tag or any tag nested under it
+    pub total_notes: usize,
+    /// Tasks tagged with this tag or any tag nested under it
+    pub total_tasks: usize,
+    /// Child tags keyed by their last path segment
+    pub children: BTreeMap<String, TagNode>,
+}
+
+/// Hierarchy of all tags used by notes and tasks
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TagTree {
+    /// Top-level tags keyed by name
+    pub roots: BTreeMap<String, TagNode>,
+}
+
+impl TagTree {
+    /// Build a tag tree from the tag lists of notes and tasks
+    pub fn build<'a>(
+        note_tags: impl IntoIterator<Item = &'a [String]>,
+        task_tags: impl IntoIterator<Item = &'a [String]>,
+    ) -> Self {
+        let mut tree = Self::default();
+
+        for tags in note_tags {
+            tree.add_item(tags, true);
+        }
+
+        for tags in task_tags {
+            tree.add_item(tags, false);
+        }
+
+        tree
+    }
+
+    /// Add one note or task, counting each tag path at most once
+    fn add_item(&mut self, tags: &[String], is_note: bool) {
+        let exact: HashSet<String> = tags
+            .iter()
+            .map(|t| normalize(t))
+            .filter(|t| !t.is_empty())
+            .collect();
+
+        // Every ancestor path of every tag gets a total count
+        let mut prefixes: HashSet<String> = HashSet::new();
+        for tag in &exact {
+            let mut path = String::new();
+            for segment in tag.split('/').filter(|s| !s.is_empty()) {
+                if !path.is_empty() {
+                    path.push('/');
+                }
+                path.push_str(segment);
+                prefixes.insert(path.clone());
+            }
+        }
+
+        for prefix in prefixes {
+            let node = self.node_mut(&prefix);
+            if is_note {
+                node.total_notes += 1;
+            } else {
+                node.total_tasks += 1;
+            }
+
+            if exact.contains(&prefix) {
+                if is_note {
+                    node.notes += 1;
+                } else {
+                    node.tasks += 1;
+                }
+            }
+        }
+    }
+
+    /// Get or create the node for a normalized tag path
+    fn node_mut(&mut self, path: &str) -> &mut TagNode {
+        let mut segments = path.split('/');
+        let first = segments.next().unwrap_or_default();
+
+        let mut node = self.roots.entry(first.to_string()).or_default();
+        node.path = first.to_string();
+        let mut current_path = first.to_string();
+
+        for segment in segments {
+            current_path.push('/');
+            current_path.push_str(segment);
+            node = node.children.entry(segment.to_string()).or_default();
+            node.path = current_path.clone();
+        }
+
+        node
+    }
+
+    /// Flatten the tree into a depth-first, alphabetically sorted list
+    pub fn flatten(&self) -> Vec<&TagNode> {
+        fn walk<'a>(nodes: &'a BTreeMap<String, TagNode>, out: &mut Vec<&'a TagNode>) {
+            for node in nodes.values() {
+                out.push(node);
+                walk(&node.children, out);
+            }
+        }
+
+        let mut out = Vec::new();
+        walk(&self.roots, &mut out);
+        out
+    }
+
+    /// Check if there are no tags at all
+    pub fn is_empty(&self) -> bool {
+        self.roots.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tags(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_tag_matches_hierarchy() {
+        assert!(tag_matches("work", "work"));
+        assert!(tag_matches("work/meetings", "work"));
+        assert!(tag_matches("Work/ClientA/ops", "work/clienta"));
+        assert!(!tag_matches("workshop", "work"));
+        assert!(!tag_matches("work", "work/meetings"));
+        assert!(tag_matches("@work", "#work"));
+    }
+
+    #[test]
+    fn test_rename() {
+        assert_eq!(rename("work", "work", "job").as_deref(), Some("job"));
+        assert_eq!(rename("Work/ClientA", "work", "job").as_deref(), Some("job/ClientA"));
+        assert_eq!(rename("workshop", "work", "job"), None);
+
+        let content = "- [ ] Call @work/clienta @home\n\nMeeting @work notes\n";
+        assert_eq!(
+            rename_task_tags(content, "work", "job", &TaskVocabulary::default()),
+            "- [ ] Call @job/clienta @home\n\nMeeting @work notes\n"
+        );
+    }
+
+    #[test]
+    fn test_rename_non_ascii() {
+        assert_eq!(rename("İstanbul/Office", "i̇stanbul", "ist").as_deref(), Some("ist/Office"));
+        assert_eq!(rename("İstanbul/Office", "İstanbul", "ist").as_deref(), Some("ist/Office"));
+        assert_eq!(rename("\u{212A}elvin/Lab", "kelvin", "temp").as_deref(), Some("temp/Lab"));
+        assert_eq!(rename("Café/Terrasse", "café", "bar").as_deref(), Some("bar/Terrasse"));
+    }
+
+    #[test]
+    fn test_rename_task_tags_list_markers_and_code() {
+        let content = "\
+* [ ] Star @work
++ [x] Plus @work
+1. [ ] Numbered @work
+- Plain item @work
+- [ ] Inline `@work` code @work
+  - [ ] Nested @work/ops
+
+```
+- [ ] Fenced @work
+```
+";
+        assert_eq!(
+            rename_task_tags(content, "work", "job", &TaskVocabulary::default()),
+            "\
+* [ ] Star @job
++ [x] Plus @job
+1. [ ] Numbered @job
+- Plain item @work
+- [ ] Inline `@work` code @job
+  - [ ] Nested @job/ops
+
+```
+- [ ] Fenced @work
+```
+"
+        );
+    }
+
+    #[test]
+    fn test_filter_and_or_not() {
+        let filter = TagFilter::parse(&tags(&["work,personal", "!archive"])).unwrap();
+
+        assert!(filter.matches(&tags(&["work/meetings"])));
+        assert!(filter.matches(&tags(&["personal"])));
+        assert!(!filter.matches(&tags(&["work", "archive/2025"])));
+        assert!(!filter.matches(&tags(&["other"])));
+    }
+
+    #[test]
+    fn test_filter_empty_matches_everything() {
+        let filter = TagFilter::parse(&[]).unwrap();
+        assert!(filter.is_empty());
+        assert!(filter.matches(&[]));
+    }
+
+    #[test]
+    fn test_filter_parse_errors() {
+        assert!(TagFilter::parse(&tags(&[""])).is_err());
+        assert!(TagFilter::parse(&tags(&["work,"])).is_err());
+        assert!(TagFilter::parse(&tags(&["work,!archive"])).is_err());
+    }
+
+    #[test]
+    fn test_tag_tree_counts() {
+        let note_tags = [tags(&["work/meetings", "work"]), tags(&["work/clientA"])];
+        let task_tags = [tags(&["work/clienta"]), tags(&["home"])];
+
+        let tree = TagTree::build(
+            note_tags.iter().map(|t| t.as_slice()),
+            task_tags.iter().map(|t| t.as_slice()),
+        );
+
+        let work = &tree.roots["work"];
+        assert_eq!(work.notes, 1);
+        assert_eq!(work.total_notes, 2);
+        assert_eq!(work.total_tasks, 1);
+
+        let client = &work.children["clienta"];
+        assert_eq!(client.path, "work/clienta");
+        assert_eq!(client.notes, 1);
+        assert_eq!(client.tasks, 1);
+
+        let paths: Vec<&str> = tree.flatten().iter().map(|n| n.path.as_str()).collect();
+        assert_eq!(paths, vec!["home", "work", "work/clienta", "work/meetings"]);
+    }
+}
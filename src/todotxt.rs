@@ -0,0 +1,182 @@
+//! todo.txt import and export
+//!
+//! Maps between [todo.txt](https://github.com/todotxt/todo.txt) lines and
+//! tasks: `(A)` is the priority, `x 2026-10-01` marks a task done on that
+//! date, `due:` is the due date, `@context` is a tag and `+project` is the
+//! tag `project/<name>`, so projects come back as `+project` on export.
+
+use crate::note::{Task, TaskStatus};
+use chrono::NaiveDate;
+use std::path::PathBuf;
+
+/// Tag prefix `+project` is stored under
+const PROJECT_PREFIX: &str = "project/";
+
+/// Format a task as a todo.txt line
+///
+/// Closed tasks are written as done; their priority moves to `pri:`, as
+/// todo.txt clients do. Urgency markers have no todo.txt equivalent.
+pub fn to_line(task: &Task) -> String {
+    let mut parts: Vec<String> = Vec::new();
+    let done = !task.status.is_incomplete();
+
+    if done {
+        parts.push("x".to_string());
+        if let Some(date) = task.done {
+            parts.push(format_date(date));
+        }
+    } else if let Some(priority) = &task.priority {
+        parts.push(format!("({})", priority));
+    }
+
+    if !task.text.is_empty() {
+        parts.push(task.text.clone());
+    }
+    for tag in &task.tags {
+        match tag.strip_prefix(PROJECT_PREFIX) {
+            Some(project) if !project.is_empty() => parts.push(format!("+{}", project)),
+            _ => parts.push(format!("@{}", tag)),
+        }
+    }
+    parts.extend(task.dependency_attributes());
+    if let Some(due) = task.due {
+        parts.push(format!("due:{}", format_date(due)));
+    }
+    if done && let Some(priority) = &task.priority {
+        parts.push(format!("pri:{}", priority));
+    }
+
+    parts.join(" ")
+}
+
+/// Parse a todo.txt line into a task that isn't in any note yet
+///
+/// Only the fields [`Task::to_markdown_line`] writes are filled in. The
+/// creation date is dropped; other `key:value` pairs stay in the text.
+/// Returns None for blank lines.
+pub fn parse_line(line: &str) -> Option<Task> {
+    let mut words = line.split_whitespace().peekable();
+    words.peek()?;
+
+    let done = words.next_if_eq(&"x").is_some();
+    let mut done_on = None;
+    if done {
+        done_on = words.next_if(|w| parse_date(w).is_some()).and_then(parse_date);
+    }
+    let mut priority = words.next_if(|w| priority_marker(w).is_some()).and_then(priority_marker);
+    // Creation date
+    words.next_if(|w| parse_date(w).is_some());
+
+    let mut text = Vec::new();
+    let mut tags: Vec<String> = Vec::new();
+    let mut due = None;
+    for word in words {
+        let tag = match word.as_bytes() {
+            [b'+', _, ..] => Some(format!("{}{}", PROJECT_PREFIX, &word[1..])),
+            [b'@', _, ..] => Some(word[1..].to_string()),
+            _ => None,
+        };
+        if let Some(tag) = tag {
+            let tag = tag.to_lowercase();
+            if !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        } else if let Some(date) = word.strip_prefix("due:").and_then(parse_date) {
+            due = Some(date);
+        } else if let Some(p) = word.strip_prefix("pri:").filter(|p| is_priority(p)) {
+            priority = Some(p.to_string());
+        } else {
+            text.push(word);
+        }
+    }
+
+    Some(Task {
+        note_path: PathBuf::new(),
+        note_title: String::new(),
+        index: 0,
+        id: String::new(),
+        anchor: None,
+        line: 0,
+        column: 0,
+        source: String::new(),
+        parent: None,
+        depth: 0,
+        progress: None,
+        under_completed: false,
+        status: if done { TaskStatus::Completed } else { TaskStatus::Uncompleted },
+        text: text.join(" "),
+        priority,
+        urgency: None,
+        tags,
+        dependencies: Vec::new(),
+        due,
+        done: done_on,
+        breadcrumb: Vec::new(),
+    })
+}
+
+fn format_date(date: NaiveDate) -> String {
+    date.format("%Y-%m-%d").to_string()
+}
+
+fn parse_date(s: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d").ok()
+}
+
+/// The letter of a `(A)` priority
+fn priority_marker(word: &str) -> Option<String> {
+    let letter = word.strip_prefix('(')?.strip_suffix(')')?;
+    is_priority(letter).then(|| letter.to_string())
+}
+
+fn is_priority(s: &str) -> bool {
+    s.len() == 1 && s.chars().all(|c| c.is_ascii_uppercase())
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::note::Note;
+    use std::path::Path;
+
+    #[test]
+    fn test_parse_line() {
+        let task = parse_line("(A) 2026-09-20 Call @Phone mom +Family due:2026-10-05 t:2026-10-01").unwrap();
+        assert_eq!(task.priority.as_deref(), Some("A"));
+        assert_eq!(task.text, "Call mom t:2026-10-01");
+        assert_eq!(task.tags, vec!["phone", "project/family"]);
+        assert_eq!(task.due, parse_date("2026-10-05"));
+        assert_eq!(
+            task.to_markdown_line(),
+            "- [ ] (A) Call mom t:2026-10-01 due:2026-10-05 @phone @project/family"
+        );
+
+        let task = parse_line("x 2026-10-01 2026-09-20 File taxes pri:B").unwrap();
+        assert_eq!(task.status, TaskStatus::Completed);
+        assert_eq!(task.done, parse_date("2026-10-01"));
+        assert_eq!(task.priority.as_deref(), Some("B"));
+        assert_eq!(task.to_markdown_line(), "- [x] (B) File taxes done:2026-10-01");
+
+        // Not a priority or completion marker without the exact syntax
+        assert_eq!(parse_line("xylophone (a) lesson").unwrap().text, "xylophone (a) lesson");
+        assert!(parse_line("   ").is_none());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let lines = [
+            "(A) Call mom @phone +family due:2026-10-05",
+            "x 2026-10-01 File taxes +home pri:B",
+            "Review [[Design Doc]] blocked-by:^t-4f2a",
+        ];
+        let markdown: Vec<String> = lines.iter().map(|l| parse_line(l).unwrap().to_markdown_line()).collect();
+        let note = Note::parse(Path::new("inbox.md"), &format!("# Inbox\n\n{}\n", markdown.join("\n"))).unwrap();
+
+        let exported: Vec<String> = Task::extract_from_note(&note).iter().map(to_line).collect();
+        assert_eq!(exported, lines);
+    }
+}
@@ -0,0 +1,46 @@
+//! Helpers shared by the integration tests that need git repositories
+
+// Each test crate uses a different subset of these
+#![allow(dead_code)]
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tempfile::TempDir;
+
+/// Run git in a directory with a fixed identity, panicking on failure
+pub fn git(dir: &Path, args: &[&str]) -> String {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .env("GIT_AUTHOR_NAME", "Test")
+        .env("GIT_AUTHOR_EMAIL", "test@example.com")
+        .env("GIT_COMMITTER_NAME", "Test")
+        .env("GIT_COMMITTER_EMAIL", "test@example.com")
+        .output()
+        .expect("failed to run git");
+    assert!(output.status.success(), "git {:?} failed: {}", args, String::from_utf8_lossy(&output.stderr));
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+/// Create a bare remote and a notes repo `name` pointing at it as `origin`,
+/// holding `files` in an initial pushed commit
+///
+/// Returns the remote and the repo.
+pub fn setup_remote(temp: &TempDir, name: &str, files: &[(&str, &str)]) -> (PathBuf, PathBuf) {
+    let remote = temp.path().join("remote.git");
+    let repo = temp.path().join(name);
+
+    git(temp.path(), &["init", "--quiet", "--bare", "-b", "main", remote.to_str().unwrap()]);
+    git(temp.path(), &["init", "--quiet", "-b", "main", repo.to_str().unwrap()]);
+    git(&repo, &["remote", "add", "origin", remote.to_str().unwrap()]);
+
+    for (path, content) in files {
+        fs::write(repo.join(path), content).unwrap();
+    }
+    git(&repo, &["add", "."]);
+    git(&repo, &["commit", "--quiet", "-m", "initial"]);
+    git(&repo, &["push", "--quiet", "-u", "origin", "main"]);
+
+    (remote, repo)
+}
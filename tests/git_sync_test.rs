@@ -0,0 +1,143 @@
+mod common;
+
+use bnotes::sync::{GitSync, PullOutcome, Sync, SyncError};
+use common::{git, setup_remote};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+/// Clone the remote into `name` with a local identity for commits
+fn clone(temp: &TempDir, remote: &Path, name: &str) -> PathBuf {
+    let dir = temp.path().join(name);
+    git(temp.path(), &["clone", "--quiet", remote.to_str().unwrap(), dir.to_str().unwrap()]);
+    git(&dir, &["config", "user.name", "Test"]);
+    git(&dir, &["config", "user.email", "test@example.com"]);
+    dir
+}
+
+/// Create a bare remote holding one note, and two clones of it
+fn setup() -> (TempDir, PathBuf, PathBuf) {
+    let temp = TempDir::new().unwrap();
+    let (remote, _seed) = setup_remote(
+        &temp,
+        "seed",
+        &[("plan.md", "---\ntags: [work]\n---\n# Plan\n\n- [ ] Write spec\n- [ ] Review\n\nNotes here.\n")],
+    );
+
+    let laptop = clone(&temp, &remote, "laptop");
+    let desktop = clone(&temp, &remote, "desktop");
+    (temp, laptop, desktop)
+}
+
+fn head(dir: &Path) -> String {
+    git(dir, &["rev-parse", "HEAD"])
+}
+
+#[test]
+fn test_commit_push_and_fast_forward() {
+    let (_temp, laptop, desktop) = setup();
+
+    fs::write(laptop.join("idea.md"), "# Idea\n\n- [ ] Try it\n").unwrap();
+    let sync = GitSync::open(&laptop).unwrap();
+    let summary = sync.change_summary().unwrap();
+    assert_eq!(summary.created, vec!["Idea"]);
+
+    assert!(sync.commit_all(&summary.commit_message("Add idea")).unwrap());
+    assert!(!sync.commit_all("nothing").unwrap());
+    sync.push().unwrap();
+    assert_eq!(head(&laptop), git(&laptop, &["rev-parse", "origin/main"]));
+
+    let other = GitSync::open(&desktop).unwrap();
+    assert_eq!(other.pull().unwrap(), PullOutcome::FastForward);
+    assert_eq!(head(&desktop), head(&laptop));
+    assert!(desktop.join("idea.md").exists());
+    assert_eq!(other.pull().unwrap(), PullOutcome::UpToDate);
+}
+
+#[test]
+fn test_pull_merges_notes_structurally() {
+    let (_temp, laptop, desktop) = setup();
+
+    fs::write(
+        laptop.join("plan.md"),
+        "---\ntags: [work, launch]\n---\n# Plan\n\n- [x] Write spec\n- [ ] Review\n\nNotes here.\n",
+    )
+    .unwrap();
+    GitSync::open(&laptop).unwrap().sync("laptop").unwrap();
+
+    fs::write(
+        desktop.join("plan.md"),
+        "---\ntags: [work, q3]\n---\n# Plan\n\n- [ ] Write spec\n- [x] Review\n\nNotes here.\n",
+    )
+    .unwrap();
+    let sync = GitSync::open(&desktop).unwrap();
+    assert_eq!(sync.sync("desktop").unwrap(), 1);
+
+    assert_eq!(
+        fs::read_to_string(desktop.join("plan.md")).unwrap(),
+        "---\ntags: [work, q3, launch]\n---\n# Plan\n\n- [x] Write spec\n- [x] Review\n\nNotes here.\n"
+    );
+    assert!(git(&desktop, &["log", "-1", "--format=%s"]).starts_with("Merge remote-tracking branch 'origin/main'"));
+    assert!(git(&desktop, &["status", "--porcelain"]).is_empty());
+    assert_eq!(head(&desktop), git(&desktop, &["rev-parse", "origin/main"]));
+}
+
+#[test]
+fn test_conflicting_pull_changes_nothing() {
+    let (_temp, laptop, desktop) = setup();
+
+    fs::write(laptop.join("plan.md"), "# Plan\n\nLaptop version.\n").unwrap();
+    GitSync::open(&laptop).unwrap().sync("laptop").unwrap();
+
+    fs::write(desktop.join("plan.md"), "# Plan\n\nDesktop version.\n").unwrap();
+    let sync = GitSync::open(&desktop).unwrap();
+    sync.commit_all("desktop").unwrap();
+    let before = head(&desktop);
+
+    match sync.pull() {
+        Err(SyncError::Conflicts(paths)) => assert_eq!(paths, vec![PathBuf::from("plan.md")]),
+        other => panic!("expected conflicts, got {:?}", other),
+    }
+    assert_eq!(head(&desktop), before);
+    assert!(git(&desktop, &["status", "--porcelain"]).is_empty());
+}
+
+#[test]
+fn test_pull_keeps_uncommitted_changes() {
+    let (_temp, laptop, desktop) = setup();
+
+    fs::write(laptop.join("idea.md"), "# Idea\n").unwrap();
+    GitSync::open(&laptop).unwrap().sync("laptop").unwrap();
+
+    fs::write(desktop.join("draft.md"), "# Draft\n").unwrap();
+    let sync = GitSync::open(&desktop).unwrap();
+    assert_eq!(sync.pull().unwrap(), PullOutcome::FastForward);
+
+    assert!(desktop.join("idea.md").exists());
+    assert_eq!(fs::read_to_string(desktop.join("draft.md")).unwrap(), "# Draft\n");
+    assert!(git(&desktop, &["stash", "list"]).is_empty());
+}
+
+#[test]
+fn test_structured_errors() {
+    let temp = TempDir::new().unwrap();
+    assert!(matches!(GitSync::open(temp.path()), Err(SyncError::NotARepository(_))));
+
+    git(temp.path(), &["init", "--quiet", "-b", "main"]);
+    let sync = GitSync::open(temp.path()).unwrap();
+    assert!(!sync.has_remote().unwrap());
+    assert!(matches!(sync.pull(), Err(SyncError::NoRemote)));
+}
+
+#[test]
+fn test_lock_is_exclusive() {
+    let (_temp, laptop, _desktop) = setup();
+    let sync = GitSync::open(&laptop).unwrap();
+
+    let lock = sync.lock().unwrap();
+    assert!(sync.try_lock().unwrap().is_none());
+    assert!(matches!(sync.lock(), Err(SyncError::Locked)));
+
+    drop(lock);
+    assert!(sync.try_lock().unwrap().is_some());
+}
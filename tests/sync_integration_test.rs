@@ -0,0 +1,221 @@
+mod common;
+
+use common::{git, setup_remote};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tempfile::TempDir;
+
+/// Run the bnotes binary against a notes directory
+fn bnotes(notes_dir: &Path, args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_bnotes"))
+        .arg("--notes-dir")
+        .arg(notes_dir)
+        .arg("--color")
+        .arg("never")
+        .args(args)
+        .env("GIT_AUTHOR_NAME", "Test")
+        .env("GIT_AUTHOR_EMAIL", "test@example.com")
+        .env("GIT_COMMITTER_NAME", "Test")
+        .env("GIT_COMMITTER_EMAIL", "test@example.com")
+        .output()
+        .expect("failed to run bnotes");
+    assert!(output.status.success(), "bnotes {:?} failed: {}", args, String::from_utf8_lossy(&output.stderr));
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+/// Create a notes repo with a bare remote and an initial pushed commit
+fn setup_repo() -> (TempDir, PathBuf) {
+    let temp = TempDir::new().unwrap();
+    let (_remote, notes) = setup_remote(
+        &temp,
+        "notes",
+        &[
+            ("plan.md", "---\ntags: [work]\n---\n# Launch Plan\n\n- [ ] Write spec\n- [ ] Review\n"),
+            ("draft.md", "# Essay\n\nSome thoughts.\n"),
+            ("old.md", "# Old Idea\n"),
+        ],
+    );
+    (temp, notes)
+}
+
+/// Make one of each kind of change the summary knows about
+fn make_changes(notes: &Path) {
+    fs::write(
+        notes.join("plan.md"),
+        "---\ntags: [work, launch]\n---\n# Launch Plan\n\n- [x] Write spec\n- [ ] Review\n- [ ] Announce\n",
+    )
+    .unwrap();
+    fs::write(notes.join("new.md"), "# Fresh Note\n").unwrap();
+    fs::create_dir(notes.join("essays")).unwrap();
+    fs::rename(notes.join("draft.md"), notes.join("essays/essay.md")).unwrap();
+    fs::remove_file(notes.join("old.md")).unwrap();
+}
+
+#[test]
+fn test_sync_commit_message_summarizes_changes() {
+    let (_temp, notes) = setup_repo();
+    make_changes(&notes);
+
+    let output = bnotes(&notes, &["sync", "--message", "Daily sync"]);
+    assert!(output.contains("committed 4 changes"), "unexpected output: {}", output);
+
+    let message = git(&notes, &["log", "-1", "--format=%B"]);
+    assert!(message.starts_with("Daily sync\n\n"));
+    assert!(message.contains("Created:\n- Fresh Note\n"));
+    assert!(message.contains("Renamed:\n- Essay (draft.md -> essays/essay.md)\n"));
+    assert!(message.contains("Deleted:\n- Old Idea\n"));
+    assert!(message.contains("Updated:\n- Launch Plan\n"));
+    assert!(message.contains("Completed:\n- Write spec (Launch Plan)\n"));
+    assert!(message.contains("Added tasks:\n- Announce (Launch Plan)\n"));
+    assert!(message.contains("Tags added:\n- launch (Launch Plan)\n"));
+
+    // Everything was pushed to the remote
+    assert!(git(&notes, &["status", "--porcelain"]).is_empty());
+    assert_eq!(
+        git(&notes, &["rev-parse", "HEAD"]),
+        git(&notes, &["rev-parse", "origin/main"])
+    );
+}
+
+#[test]
+fn test_sync_dry_run_leaves_repo_untouched() {
+    let (_temp, notes) = setup_repo();
+    make_changes(&notes);
+
+    let head_before = git(&notes, &["rev-parse", "HEAD"]);
+    let output = bnotes(&notes, &["sync", "--dry-run"]);
+
+    assert!(output.contains("Would commit with message:"));
+    assert!(output.contains("- Write spec (Launch Plan)"));
+    assert_eq!(git(&notes, &["rev-parse", "HEAD"]), head_before);
+    assert!(git(&notes, &["diff", "--cached", "--name-only"]).is_empty());
+}
+
+#[test]
+fn test_restore_refuses_uncommitted_note_with_space_in_path() {
+    let (_temp, notes) = setup_repo();
+    fs::write(notes.join("my note.md"), "# My Note\n\nFirst draft.\n").unwrap();
+    git(&notes, &["add", "."]);
+    git(&notes, &["commit", "--quiet", "-m", "add my note"]);
+    fs::write(notes.join("my note.md"), "# My Note\n\nUnsaved edit.\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_bnotes"))
+        .args(["--notes-dir", notes.to_str().unwrap(), "note", "restore", "My Note", "HEAD"])
+        .output()
+        .expect("failed to run bnotes");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("uncommitted changes"));
+    assert_eq!(fs::read_to_string(notes.join("my note.md")).unwrap(), "# My Note\n\nUnsaved edit.\n");
+
+    bnotes(&notes, &["note", "restore", "My Note", "HEAD", "--force"]);
+    assert_eq!(fs::read_to_string(notes.join("my note.md")).unwrap(), "# My Note\n\nFirst draft.\n");
+}
+
+#[test]
+fn test_touch_updates_notes_with_spaces_and_non_ascii_names() {
+    let (_temp, notes) = setup_repo();
+    let note = "---\nupdated: 2020-01-01T00:00:00Z\n---\n# Café Notes\n\nFirst.\n";
+    fs::write(notes.join("café notes.md"), note).unwrap();
+    git(&notes, &["add", "."]);
+    git(&notes, &["commit", "--quiet", "-m", "add café notes"]);
+    fs::write(notes.join("café notes.md"), note.replace("First.", "Second.")).unwrap();
+
+    let output = bnotes(&notes, &["touch"]);
+
+    assert!(output.contains("café notes.md"), "{}", output);
+    assert!(output.contains("Updated 1 note."));
+    assert!(!fs::read_to_string(notes.join("café notes.md")).unwrap().contains("2020-01-01"));
+}
+
+#[test]
+fn test_status_shows_titles_for_quoted_paths() {
+    let (_temp, notes) = setup_repo();
+    fs::write(notes.join("my note.md"), "# My Note\n").unwrap();
+    git(&notes, &["mv", "old.md", "a -> b.md"]);
+
+    let output = bnotes(&notes, &["status"]);
+
+    assert!(output.contains("My Note (my note.md)"), "{}", output);
+    assert!(output.contains("Old Idea (old.md -> a -> b.md)"), "{}", output);
+    assert!(!output.contains('"'), "{}", output);
+}
+
+#[test]
+fn test_pending_debounced_sync_is_flushed_by_next_edit() {
+    let (temp, notes) = setup_repo();
+    fs::create_dir(notes.join(".bnotes")).unwrap();
+    fs::write(notes.join(".bnotes/config.toml"), "[auto_sync]\nmode = \"debounced\"\ndebounce_minutes = 0\n").unwrap();
+    fs::write(notes.join("idea.md"), "# Idea\n").unwrap();
+    git(&notes, &["add", "."]);
+    git(&notes, &["commit", "--quiet", "-m", "edit left behind by debouncing"]);
+    fs::write(notes.join(".git/bnotes-sync-pending"), "").unwrap();
+
+    // Read-only commands leave the remote alone
+    let remote = temp.path().join("remote.git");
+    bnotes(&notes, &["tasks"]);
+    bnotes(&notes, &["status"]);
+    assert_ne!(git(&remote, &["rev-parse", "main"]), git(&notes, &["rev-parse", "HEAD"]));
+    assert!(notes.join(".git/bnotes-sync-pending").exists());
+
+    let output = Command::new(env!("CARGO_BIN_EXE_bnotes"))
+        .args(["--notes-dir", notes.to_str().unwrap(), "edit", "Idea"])
+        .env("EDITOR", "true")
+        .output()
+        .expect("failed to run bnotes");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    assert_eq!(git(&remote, &["rev-parse", "main"]), git(&notes, &["rev-parse", "HEAD"]));
+    assert!(!notes.join(".git/bnotes-sync-pending").exists());
+}
+
+#[test]
+fn test_pull_before_open_gives_up_on_unresponsive_remote() {
+    let (_temp, notes) = setup_repo();
+    fs::create_dir(notes.join(".bnotes")).unwrap();
+    fs::write(
+        notes.join(".bnotes/config.toml"),
+        "[auto_sync]\nmode = \"on-exit-from-editor\"\npull_before_open = true\nnetwork_timeout_seconds = 1\n",
+    )
+    .unwrap();
+
+    // Accepts connections and never answers
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("git://{}/notes.git", listener.local_addr().unwrap());
+    git(&notes, &["remote", "set-url", "origin", &url]);
+    std::thread::spawn(move || {
+        let _connections: Vec<_> = listener.incoming().collect();
+    });
+
+    let started = std::time::Instant::now();
+    let output = Command::new(env!("CARGO_BIN_EXE_bnotes"))
+        .args(["--notes-dir", notes.to_str().unwrap(), "edit", "Launch Plan"])
+        .env("EDITOR", "true")
+        .output()
+        .expect("failed to run bnotes");
+
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("didn't answer within 1s"), "{}", stderr);
+    assert!(started.elapsed() < std::time::Duration::from_secs(10));
+
+    // The hung fetch keeps the lock until the process exits
+    assert!(stderr.contains("another bnotes process is running git"), "{}", stderr);
+    assert!(!notes.join(".git/bnotes.lock").exists());
+}
+
+#[test]
+fn test_task_age_falls_back_to_first_commit() {
+    let (_temp, notes) = setup_repo();
+    fs::write(notes.join("someday.md"), "# Someday\n\n- [ ] Old chore\n").unwrap();
+    git(&notes, &["add", "someday.md"]);
+    git(&notes, &["commit", "--quiet", "--date", "2020-01-01T00:00:00Z", "-m", "someday"]);
+    git(&notes, &["mv", "someday.md", "later.md"]);
+    git(&notes, &["commit", "--quiet", "-m", "rename"]);
+
+    // Neither note has a `created` date; the one committed years ago ranks first
+    let output = bnotes(&notes, &["task", "next", "--show-score"]);
+    let first = output.lines().find(|line| line.contains("Old chore") || line.contains("Write spec")).unwrap();
+    assert!(first.contains("Old chore"), "{}", output);
+    assert!(first.contains("2.0"), "{}", output);
+}